@@ -0,0 +1,7 @@
+-- Colonnes de compte attendues par le modèle User
+ALTER TABLE users
+    ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'user' CHECK (role IN ('user', 'reviewer', 'moderator', 'admin', 'restricted')),
+    ADD COLUMN settings JSONB DEFAULT '{}',
+    ADD COLUMN last_login TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_users_role ON users(role);
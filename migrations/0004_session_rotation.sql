@@ -0,0 +1,57 @@
+-- Rotation des refresh tokens et journal d'audit de sécurité
+
+-- Révocation explicite d'une session (rotation compromise, logout...)
+ALTER TABLE user_sessions ADD COLUMN revoked_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_user_sessions_refresh_token_hash ON user_sessions(refresh_token_hash) WHERE refresh_token_hash IS NOT NULL;
+
+-- Refresh tokens déjà consommés, conservés pour détecter leur réutilisation
+CREATE TABLE session_rotated_refresh_tokens (
+    token_hash VARCHAR(255) PRIMARY KEY,
+    session_id UUID NOT NULL REFERENCES user_sessions(id) ON DELETE CASCADE,
+    rotated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_session_rotated_refresh_tokens_session_id ON session_rotated_refresh_tokens(session_id);
+
+-- Table d'audit spécialisée pour les événements de sécurité
+CREATE TABLE security_audit_logs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    
+    -- Événement de sécurité
+    event_type VARCHAR(50) NOT NULL CHECK (event_type IN (
+        'login_success', 'login_failure', 'logout', 'password_change',
+        'role_change', 'permission_change', 'account_locked', 'account_unlocked',
+        'suspicious_activity', 'data_breach_attempt', 'unauthorized_access'
+    )),
+    
+    -- Utilisateur concerné
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    target_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    
+    -- Contexte technique
+    ip_address INET NOT NULL,
+    user_agent TEXT,
+    session_id UUID,
+    
+    -- Détails de l'événement
+    severity VARCHAR(10) NOT NULL CHECK (severity IN ('low', 'medium', 'high', 'critical')),
+    details JSONB,
+    
+    -- Géolocalisation (optionnel)
+    country VARCHAR(2),
+    city VARCHAR(100),
+    
+    -- Flags de sécurité
+    is_anomaly BOOLEAN DEFAULT FALSE,
+    requires_investigation BOOLEAN DEFAULT FALSE,
+    investigated_by UUID REFERENCES users(id),
+    investigation_notes TEXT,
+    
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_security_audit_user_event ON security_audit_logs(user_id, event_type, created_at);
+CREATE INDEX idx_security_audit_ip ON security_audit_logs(ip_address, created_at);
+CREATE INDEX idx_security_audit_anomaly ON security_audit_logs(is_anomaly, created_at) WHERE is_anomaly = TRUE;
+CREATE INDEX idx_security_audit_severity ON security_audit_logs(severity, created_at);
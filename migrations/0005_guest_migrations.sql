@@ -0,0 +1,56 @@
+-- Migration des invités vers un compte et commentaires
+
+-- Table de suivi des migrations
+CREATE TABLE user_migrations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    
+    -- Utilisateur source (invité) et destination (compte)
+    source_anonymous_id UUID NOT NULL,
+    destination_user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    
+    -- Statut de la migration
+    migration_status VARCHAR(20) DEFAULT 'pending' CHECK (migration_status IN ('pending', 'in_progress', 'completed', 'failed')),
+    
+    -- Détails de la migration
+    entities_migrated JSONB DEFAULT '{}', -- Compteurs par type d'entité
+    migration_log JSONB DEFAULT '[]', -- Log des étapes
+    
+    -- Métadonnées
+    started_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    completed_at TIMESTAMP WITH TIME ZONE,
+    error_message TEXT,
+    
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_user_migrations_source ON user_migrations(source_anonymous_id);
+CREATE INDEX idx_user_migrations_destination ON user_migrations(destination_user_id);
+CREATE INDEX idx_user_migrations_status ON user_migrations(migration_status);
+
+-- Table des commentaires (générique pour notes, snippets, tâches)
+CREATE TABLE comments (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    
+    -- Référence à l'entité commentée
+    entity_type VARCHAR(50) NOT NULL, -- 'note', 'snippet', 'task', 'public_snippet'
+    entity_id UUID NOT NULL,
+    
+    author_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    
+    -- Réponses
+    parent_id UUID REFERENCES comments(id) ON DELETE CASCADE,
+    
+    -- Métadonnées
+    is_edited BOOLEAN DEFAULT FALSE,
+    edited_at TIMESTAMP WITH TIME ZONE,
+    
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_comments_entity_type_id ON comments(entity_type, entity_id);
+CREATE INDEX idx_comments_author_id ON comments(author_id);
+CREATE INDEX idx_comments_parent_id ON comments(parent_id);
+
+CREATE TRIGGER trigger_comments_updated_at BEFORE UPDATE ON comments FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
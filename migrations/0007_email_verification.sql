@@ -0,0 +1,2 @@
+-- Limitation des renvois d'email de vérification
+ALTER TABLE users ADD COLUMN email_verification_sent_at TIMESTAMP WITH TIME ZONE;
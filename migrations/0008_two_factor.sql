@@ -0,0 +1,23 @@
+-- Double authentification TOTP (RFC 6238)
+
+-- Le secret doit rester lisible pour calculer les codes : il n'est pas hashé
+CREATE TABLE user_two_factor (
+    user_id UUID PRIMARY KEY REFERENCES users(id) ON DELETE CASCADE,
+    secret VARCHAR(64) NOT NULL,
+    -- NULL tant que l'enrôlement n'a pas été confirmé par un premier code
+    confirmed_at TIMESTAMP WITH TIME ZONE,
+    -- Dernière fenêtre TOTP acceptée, pour refuser le rejeu d'un code
+    last_used_step BIGINT,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Codes de secours à usage unique, stockés hashés
+CREATE TABLE user_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash VARCHAR(255) NOT NULL,
+    used_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_user_recovery_codes_user_id ON user_recovery_codes(user_id);
@@ -0,0 +1,32 @@
+-- Connexion via des fournisseurs OAuth2/OIDC
+
+-- Un compte créé par un fournisseur externe n'a pas de mot de passe
+ALTER TABLE users DROP CONSTRAINT users_password_required_for_registered;
+
+-- Identités externes liées à un compte
+CREATE TABLE user_identities (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider VARCHAR(50) NOT NULL,
+    provider_user_id VARCHAR(255) NOT NULL,
+    email CITEXT,
+    last_login_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW(),
+
+    UNIQUE(provider, provider_user_id)
+);
+
+CREATE INDEX idx_user_identities_user_id ON user_identities(user_id);
+
+-- Autorisations en cours (state et code_verifier PKCE), à usage unique
+CREATE TABLE oauth_authorization_requests (
+    state_hash VARCHAR(255) PRIMARY KEY,
+    provider VARCHAR(50) NOT NULL,
+    code_verifier VARCHAR(128) NOT NULL,
+    -- Utilisateur connecté au lancement : invité à convertir ou compte à lier
+    initiator_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_oauth_authorization_requests_expires_at ON oauth_authorization_requests(expires_at);
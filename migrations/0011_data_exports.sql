@@ -0,0 +1,23 @@
+-- Exports RGPD des données d'un utilisateur
+
+CREATE TABLE data_exports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending' CHECK (status IN ('pending', 'processing', 'completed', 'failed', 'expired')),
+
+    -- Archive produite et lien de téléchargement (seul le hash du token est stocké)
+    storage_key VARCHAR(255),
+    file_size BIGINT,
+    download_token_hash VARCHAR(255) UNIQUE,
+    expires_at TIMESTAMP WITH TIME ZONE,
+
+    error_message TEXT,
+    completed_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_data_exports_user_id ON data_exports(user_id, created_at);
+CREATE INDEX idx_data_exports_expires_at ON data_exports(expires_at) WHERE status = 'completed';
+
+-- Un seul export en cours par utilisateur
+CREATE UNIQUE INDEX idx_data_exports_in_progress ON data_exports(user_id) WHERE status IN ('pending', 'processing');
@@ -0,0 +1,24 @@
+-- Suppression de compte différée et journal d'audit général
+
+-- Suppression programmée, annulable jusqu'à `deletion_scheduled_at`
+ALTER TABLE users
+    ADD COLUMN deletion_requested_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN deletion_scheduled_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN deletion_snippet_policy VARCHAR(20) CHECK (deletion_snippet_policy IN ('anonymize', 'delete'));
+
+CREATE INDEX idx_users_deletion_scheduled_at ON users(deletion_scheduled_at) WHERE deletion_scheduled_at IS NOT NULL;
+
+-- Journal des opérations sur les comptes et leur contenu
+-- Sans clé étrangère sur l'entité : l'entrée survit à la suppression du compte
+CREATE TABLE audit_logs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    actor_id UUID REFERENCES users(id) ON DELETE SET NULL, -- NULL pour le système
+    action VARCHAR(50) NOT NULL,
+    entity_type VARCHAR(50) NOT NULL,
+    entity_id UUID,
+    details JSONB DEFAULT '{}',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+CREATE INDEX idx_audit_logs_entity ON audit_logs(entity_type, entity_id, created_at);
+CREATE INDEX idx_audit_logs_actor_id ON audit_logs(actor_id, created_at);
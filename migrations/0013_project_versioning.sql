@@ -0,0 +1,7 @@
+-- Verrouillage optimiste des projets : la version est toujours définie
+
+UPDATE projects SET version = 1 WHERE version IS NULL;
+
+ALTER TABLE projects
+    ALTER COLUMN version SET DEFAULT 1,
+    ALTER COLUMN version SET NOT NULL;
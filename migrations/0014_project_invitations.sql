@@ -0,0 +1,21 @@
+-- Invitations à rejoindre un projet, envoyées par email
+
+CREATE TABLE project_invitations (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    email CITEXT NOT NULL,
+    role VARCHAR(20) NOT NULL CHECK (role IN ('admin', 'editor', 'viewer')),
+    invited_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'declined', 'revoked', 'expired')),
+    -- NULL tant que l'adresse n'a pas de compte : l'invitation est conservée
+    -- et le délai démarre quand son titulaire la consulte après inscription
+    expires_at TIMESTAMP WITH TIME ZONE,
+    responded_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Une seule invitation en attente par adresse et par projet
+CREATE UNIQUE INDEX idx_project_invitations_pending
+    ON project_invitations(project_id, email) WHERE status = 'pending';
+CREATE INDEX idx_project_invitations_email ON project_invitations(email) WHERE status = 'pending';
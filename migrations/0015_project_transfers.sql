@@ -0,0 +1,18 @@
+-- Transferts de propriété d'un projet, effectifs une fois acceptés par le destinataire
+
+CREATE TABLE project_transfers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    project_id UUID NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+    from_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    to_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    requested_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending'
+        CHECK (status IN ('pending', 'accepted', 'declined', 'cancelled', 'expired')),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    responded_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT NOW()
+);
+
+-- Un seul transfert en attente par projet
+CREATE UNIQUE INDEX idx_project_transfers_pending ON project_transfers(project_id) WHERE status = 'pending';
+CREATE INDEX idx_project_transfers_to_user_id ON project_transfers(to_user_id) WHERE status = 'pending';
@@ -0,0 +1,6 @@
+-- Chiffrement des secrets TOTP
+
+-- Le secret est chiffré par l'application (AES-256-GCM, clé TWO_FACTOR_ENCRYPTION_KEY) :
+-- nonce et tag allongent la valeur stockée. Les secrets déjà enregistrés en clair
+-- sont chiffrés à leur prochaine lecture.
+ALTER TABLE user_two_factor ALTER COLUMN secret TYPE TEXT;
@@ -0,0 +1,9 @@
+-- Événements de blocage par adresse IP, distincts du verrouillage d'un compte
+
+ALTER TABLE security_audit_logs DROP CONSTRAINT security_audit_logs_event_type_check;
+ALTER TABLE security_audit_logs ADD CONSTRAINT security_audit_logs_event_type_check CHECK (event_type IN (
+    'login_success', 'login_failure', 'logout', 'password_change',
+    'role_change', 'permission_change', 'account_locked', 'account_unlocked',
+    'ip_blocked', 'ip_unblocked',
+    'suspicious_activity', 'data_breach_attempt', 'unauthorized_access'
+));
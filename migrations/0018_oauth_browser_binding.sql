@@ -0,0 +1,7 @@
+-- Lie chaque autorisation OAuth au navigateur qui l'a lancée
+
+-- Les autorisations en cours, sans liaison, ne peuvent plus aboutir
+DELETE FROM oauth_authorization_requests;
+
+-- Empreinte du nonce déposé en cookie HttpOnly au lancement de l'autorisation
+ALTER TABLE oauth_authorization_requests ADD COLUMN browser_binding_hash VARCHAR(255) NOT NULL;
@@ -0,0 +1,9 @@
+-- Les liens de téléchargement des exports ne sont plus envoyés par notification
+
+-- Les liens déjà envoyés sont révoqués : un nouveau lien est remis par le statut de l'export
+UPDATE data_exports SET download_token_hash = NULL WHERE status = 'completed';
+
+UPDATE notifications
+SET message = 'Votre archive est disponible dans vos paramètres jusqu''au ' || to_char(e.expires_at AT TIME ZONE 'UTC', 'DD/MM/YYYY HH24:MI') || ' UTC.'
+FROM data_exports e
+WHERE notifications.type = 'data_export_ready' AND notifications.entity_id = e.id;
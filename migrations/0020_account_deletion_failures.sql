@@ -0,0 +1,5 @@
+-- Échecs de suppression de compte : un compte en échec ne bloque plus les suivants
+
+ALTER TABLE users
+    ADD COLUMN deletion_attempts INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN deletion_last_error TEXT;
@@ -0,0 +1,23 @@
+-- Les booléens de `project_permissions` sont des exceptions au rôle du membre :
+-- NULL hérite du droit du rôle
+
+ALTER TABLE project_permissions
+    ALTER COLUMN can_edit_project DROP DEFAULT,
+    ALTER COLUMN can_manage_members DROP DEFAULT,
+    ALTER COLUMN can_create_notes DROP DEFAULT,
+    ALTER COLUMN can_edit_notes DROP DEFAULT,
+    ALTER COLUMN can_delete_notes DROP DEFAULT,
+    ALTER COLUMN can_create_snippets DROP DEFAULT,
+    ALTER COLUMN can_edit_snippets DROP DEFAULT,
+    ALTER COLUMN can_delete_snippets DROP DEFAULT,
+    ALTER COLUMN can_create_tasks DROP DEFAULT,
+    ALTER COLUMN can_edit_tasks DROP DEFAULT,
+    ALTER COLUMN can_delete_tasks DROP DEFAULT;
+
+-- Aucune exception n'a été accordée jusqu'ici : les valeurs existantes ne sont que
+-- des copies des droits du propriétaire (TRUE) ou de la valeur par défaut (FALSE)
+UPDATE project_permissions SET
+    can_edit_project = NULL, can_manage_members = NULL,
+    can_create_notes = NULL, can_edit_notes = NULL, can_delete_notes = NULL,
+    can_create_snippets = NULL, can_edit_snippets = NULL, can_delete_snippets = NULL,
+    can_create_tasks = NULL, can_edit_tasks = NULL, can_delete_tasks = NULL;
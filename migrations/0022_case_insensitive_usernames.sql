@@ -0,0 +1,18 @@
+-- Les noms d'utilisateur sont uniques sans tenir compte de la casse
+
+-- Les doublons existants gardent leur nom pour le compte le plus ancien, les
+-- autres reçoivent un suffixe tiré de leur identifiant
+WITH duplicates AS (
+    SELECT id,
+           ROW_NUMBER() OVER (PARTITION BY lower(username) ORDER BY created_at, id) AS rank
+    FROM users
+    WHERE username IS NOT NULL
+)
+UPDATE users
+SET username = left(users.username, 41) || '-' || left(users.id::text, 8)
+FROM duplicates
+WHERE users.id = duplicates.id AND duplicates.rank > 1;
+
+-- Remplace l'index de recherche : les recherches se font sur lower(username)
+DROP INDEX IF EXISTS idx_users_username;
+CREATE UNIQUE INDEX idx_users_username_lower ON users (lower(username));
@@ -1,16 +1,61 @@
+use ipnet::IpNet;
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::net::IpAddr;
+
+use crate::models::UserRole;
+
+/// Secret utilisé quand `JWT_SECRET` n'est pas défini, refusé en production
+pub const DEFAULT_JWT_SECRET: &str = "default-secret-key";
+
+/// Clé utilisée quand `TWO_FACTOR_ENCRYPTION_KEY` n'est pas définie, refusée en production
+pub const DEFAULT_TWO_FACTOR_ENCRYPTION_KEY: &str =
+    "0000000000000000000000000000000000000000000000000000000000000000";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub database_url: String,
     pub redis_url: String,
     pub jwt_secret: String,
+    pub auth: AuthConfig,
     pub server: ServerConfig,
     pub cors: CorsConfig,
     pub email: EmailConfig,
     pub logging: LoggingConfig,
     pub features: FeatureConfig,
+    pub jobs: JobsConfig,
+    pub lockout: LockoutConfig,
+    pub oauth: OAuthConfig,
+    pub storage: StorageConfig,
+    pub projects: ProjectsConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// Durée de validité du token d'accès, en secondes
+    pub access_token_expiration: i64,
+    /// Durée de validité du refresh token (et donc de la session), en secondes
+    pub refresh_token_expiration: i64,
+    /// Durée de validité d'une session invité (et de son token), en secondes
+    pub guest_session_expiration: i64,
+    /// Coût bcrypt des nouveaux hash ; les hash existants sont mis à jour à la connexion
+    pub bcrypt_cost: u32,
+    /// Identifiant (`kid`) de la clé courante, utilisée pour signer les tokens
+    pub jwt_key_id: String,
+    /// Anciennes clés encore acceptées en vérification pendant une rotation
+    pub jwt_previous_keys: Vec<JwtKeyConfig>,
+    /// Rôles pour lesquels la double authentification est obligatoire
+    pub mfa_enforced_roles: Vec<UserRole>,
+    /// Délai pendant lequel une suppression de compte peut être annulée, en secondes
+    pub deletion_grace_period: i64,
+    /// Clé AES-256 (64 caractères hexadécimaux) chiffrant les secrets TOTP en base
+    pub two_factor_encryption_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JwtKeyConfig {
+    pub kid: String,
+    pub secret: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +63,12 @@ pub struct ServerConfig {
     pub host: String,
     pub port: u16,
     pub environment: Environment,
+    /// URL publique du frontend, utilisée pour construire les liens envoyés par email
+    pub frontend_url: String,
+    /// URL publique de l'API, utilisée pour les liens de téléchargement
+    pub public_url: String,
+    /// Reverse proxies autorisés à transmettre l'adresse du client dans `X-Forwarded-For`
+    pub trusted_proxies: Vec<IpNet>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +87,9 @@ pub struct EmailConfig {
     pub smtp_password: String,
     pub from_email: String,
     pub from_name: String,
+    /// Journalise le contenu des emails non envoyés faute de SMTP ; jamais en production,
+    /// les emails contenant des liens à usage unique
+    pub log_unsent_content: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +108,78 @@ pub struct FeatureConfig {
     pub rate_limiting: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobsConfig {
+    /// Intervalle entre deux passages du nettoyage, en secondes
+    pub cleanup_interval: u64,
+    /// Délai avant expiration à partir duquel un invité persistant est prévenu, en secondes
+    pub guest_expiration_warning: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockoutConfig {
+    /// Échecs de connexion tolérés par compte avant verrouillage
+    pub max_failures: u64,
+    /// Échecs de connexion tolérés par adresse IP avant blocage
+    pub ip_max_failures: u64,
+    /// Fenêtre pendant laquelle les échecs sont cumulés, en secondes
+    pub failure_window: u64,
+    /// Durée du premier verrouillage, doublée à chaque récidive, en secondes
+    pub base_duration: u64,
+    /// Durée maximale d'un verrouillage, en secondes
+    pub max_duration: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthConfig {
+    /// URL du frontend recevant le code, complétée par `/{provider}`
+    pub redirect_url: String,
+    pub github: Option<OAuthClientConfig>,
+    pub oidc: Option<OidcConfig>,
+    /// Fournisseur local sans réseau, pour le développement et les tests
+    pub local_provider: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Répertoire des fichiers stockés ; seuls les avatars sont servis, sous `/uploads/avatars`
+    pub local_path: String,
+    /// URL publique correspondant à `local_path`
+    pub public_url: String,
+    /// Taille maximale d'un avatar envoyé, en octets
+    pub avatar_max_size: usize,
+    /// Durée de conservation d'une archive d'export RGPD, en secondes
+    pub export_ttl: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectsConfig {
+    /// Durée de validité d'une invitation à un projet, en secondes
+    ///
+    /// Pour une adresse sans compte, le délai court à partir de l'inscription.
+    pub invitation_ttl: i64,
+    /// Délai laissé au destinataire pour accepter un transfert de propriété, en secondes
+    pub transfer_ttl: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthClientConfig {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    /// Nom du fournisseur dans les routes, ex. `google`
+    pub name: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorization_url: String,
+    pub token_url: String,
+    pub userinfo_url: String,
+    pub scopes: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Environment {
     Development,
@@ -74,6 +200,64 @@ pub enum ConfigError {
     MissingEnv(String),
 }
 
+/// Parse `JWT_PREVIOUS_KEYS`, au format `kid1:secret1,kid2:secret2`
+fn parse_jwt_keys(value: &str) -> Result<Vec<JwtKeyConfig>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((kid, secret)) if !kid.is_empty() && !secret.is_empty() => Ok(JwtKeyConfig {
+                kid: kid.to_string(),
+                secret: secret.to_string(),
+            }),
+            _ => Err(ConfigError::ParseError("Invalid JWT_PREVIOUS_KEYS entry".to_string())),
+        })
+        .collect()
+}
+
+/// Parse `MFA_ENFORCED_ROLES`, au format `admin,moderator`
+fn parse_roles(value: &str) -> Result<Vec<UserRole>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|role| !role.is_empty())
+        .map(|role| role.parse().map_err(|_| ConfigError::ParseError("Invalid MFA_ENFORCED_ROLES".to_string())))
+        .collect()
+}
+
+/// Parse `TRUSTED_PROXIES`, au format `10.0.0.1,172.16.0.0/12`
+fn parse_proxies(value: &str) -> Result<Vec<IpNet>, ConfigError> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|proxy| !proxy.is_empty())
+        .map(|proxy| {
+            proxy
+                .parse::<IpNet>()
+                .or_else(|_| proxy.parse::<IpAddr>().map(IpNet::from))
+                .map_err(|_| ConfigError::ParseError("Invalid TRUSTED_PROXIES entry".to_string()))
+        })
+        .collect()
+}
+
+/// Valide `TWO_FACTOR_ENCRYPTION_KEY` : 32 octets en hexadécimal, la clé par défaut si vide
+fn parse_encryption_key(value: &str) -> Result<String, ConfigError> {
+    let key = value.trim();
+    if key.is_empty() {
+        return Ok(DEFAULT_TWO_FACTOR_ENCRYPTION_KEY.to_string());
+    }
+    if key.len() != 64 || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(ConfigError::ParseError("Invalid TWO_FACTOR_ENCRYPTION_KEY".to_string()));
+    }
+
+    Ok(key.to_string())
+}
+
+fn required_env(name: &str) -> Result<String, ConfigError> {
+    env::var(name).map_err(|_| ConfigError::MissingEnv(name.to_string()))
+}
+
 impl Config {
     pub fn from_env() -> Result<Self, ConfigError> {
         dotenvy::dotenv().ok();
@@ -85,15 +269,54 @@ impl Config {
             .unwrap_or_else(|_| "redis://localhost:6379".to_string());
         
         let jwt_secret = env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "default-secret-key".to_string());
+            .unwrap_or_else(|_| DEFAULT_JWT_SECRET.to_string());
+        
+        let auth = AuthConfig {
+            access_token_expiration: env::var("JWT_EXPIRATION")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid JWT_EXPIRATION".to_string()))?,
+            refresh_token_expiration: env::var("JWT_REFRESH_EXPIRATION")
+                .unwrap_or_else(|_| "2592000".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid JWT_REFRESH_EXPIRATION".to_string()))?,
+            guest_session_expiration: env::var("GUEST_SESSION_DURATION")
+                .unwrap_or_else(|_| "2592000".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid GUEST_SESSION_DURATION".to_string()))?,
+            bcrypt_cost: env::var("BCRYPT_COST")
+                .unwrap_or_else(|_| "12".to_string())
+                .parse()
+                .ok()
+                .filter(|cost| (4..=31).contains(cost))
+                .ok_or_else(|| ConfigError::ParseError("Invalid BCRYPT_COST".to_string()))?,
+            jwt_key_id: env::var("JWT_KEY_ID").unwrap_or_else(|_| "primary".to_string()),
+            jwt_previous_keys: parse_jwt_keys(&env::var("JWT_PREVIOUS_KEYS").unwrap_or_default())?,
+            mfa_enforced_roles: parse_roles(&env::var("MFA_ENFORCED_ROLES").unwrap_or_default())?,
+            deletion_grace_period: env::var("ACCOUNT_DELETION_GRACE_PERIOD")
+                .unwrap_or_else(|_| "2592000".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid ACCOUNT_DELETION_GRACE_PERIOD".to_string()))?,
+            two_factor_encryption_key: parse_encryption_key(
+                &env::var("TWO_FACTOR_ENCRYPTION_KEY").unwrap_or_default(),
+            )?,
+        };
+        
+        let port: u16 = env::var("PORT")
+            .unwrap_or_else(|_| "8080".to_string())
+            .parse()
+            .map_err(|_| ConfigError::ParseError("Invalid port number".to_string()))?;
         
         let server = ServerConfig {
             host: env::var("HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
-            port: env::var("PORT")
-                .unwrap_or_else(|_| "8080".to_string())
+            port,
+            environment: env::var("ENVIRONMENT")
+                .unwrap_or_else(|_| "development".to_string())
                 .parse()
-                .map_err(|_| ConfigError::ParseError("Invalid port number".to_string()))?,
-            environment: Environment::Development,
+                .map_err(ConfigError::ParseError)?,
+            frontend_url: env::var("FRONTEND_URL").unwrap_or_else(|_| "http://localhost:5173".to_string()),
+            public_url: env::var("API_PUBLIC_URL").unwrap_or_else(|_| format!("http://localhost:{}", port)),
+            trusted_proxies: parse_proxies(&env::var("TRUSTED_PROXIES").unwrap_or_default())?,
         };
         
         let cors = CorsConfig {
@@ -117,6 +340,7 @@ impl Config {
             smtp_password: env::var("EMAIL_SMTP_PASSWORD").unwrap_or_default(),
             from_email: env::var("EMAIL_FROM").unwrap_or_else(|_| "noreply@ettu.dev".to_string()),
             from_name: env::var("EMAIL_FROM_NAME").unwrap_or_else(|_| "ETTU".to_string()),
+            log_unsent_content: !matches!(server.environment, Environment::Production),
         };
         
         let logging = LoggingConfig {
@@ -133,15 +357,131 @@ impl Config {
             rate_limiting: env::var("RATE_LIMITING").unwrap_or_else(|_| "true".to_string()).parse().unwrap_or(true),
         };
         
+        let jobs = JobsConfig {
+            cleanup_interval: env::var("CLEANUP_INTERVAL")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid CLEANUP_INTERVAL".to_string()))?,
+            guest_expiration_warning: env::var("GUEST_EXPIRATION_WARNING")
+                .unwrap_or_else(|_| "604800".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid GUEST_EXPIRATION_WARNING".to_string()))?,
+        };
+        
+        let lockout = LockoutConfig {
+            max_failures: env::var("LOGIN_MAX_FAILURES")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid LOGIN_MAX_FAILURES".to_string()))?,
+            ip_max_failures: env::var("LOGIN_IP_MAX_FAILURES")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid LOGIN_IP_MAX_FAILURES".to_string()))?,
+            failure_window: env::var("LOGIN_FAILURE_WINDOW")
+                .unwrap_or_else(|_| "900".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid LOGIN_FAILURE_WINDOW".to_string()))?,
+            base_duration: env::var("LOGIN_LOCKOUT_DURATION")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid LOGIN_LOCKOUT_DURATION".to_string()))?,
+            max_duration: env::var("LOGIN_LOCKOUT_MAX_DURATION")
+                .unwrap_or_else(|_| "86400".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid LOGIN_LOCKOUT_MAX_DURATION".to_string()))?,
+        };
+        
+        let oauth = OAuthConfig {
+            redirect_url: env::var("OAUTH_REDIRECT_URL")
+                .unwrap_or_else(|_| format!("{}/auth/callback", server.frontend_url.trim_end_matches('/'))),
+            github: match env::var("GITHUB_CLIENT_ID") {
+                Ok(client_id) => Some(OAuthClientConfig {
+                    client_id,
+                    client_secret: required_env("GITHUB_CLIENT_SECRET")?,
+                }),
+                Err(_) => None,
+            },
+            oidc: match env::var("OIDC_CLIENT_ID") {
+                Ok(client_id) => Some(OidcConfig {
+                    name: env::var("OIDC_PROVIDER_NAME").unwrap_or_else(|_| "oidc".to_string()),
+                    client_id,
+                    client_secret: required_env("OIDC_CLIENT_SECRET")?,
+                    authorization_url: required_env("OIDC_AUTHORIZATION_URL")?,
+                    token_url: required_env("OIDC_TOKEN_URL")?,
+                    userinfo_url: required_env("OIDC_USERINFO_URL")?,
+                    scopes: env::var("OIDC_SCOPES").unwrap_or_else(|_| "openid email profile".to_string()),
+                }),
+                Err(_) => None,
+            },
+            local_provider: env::var("OAUTH_LOCAL_PROVIDER").unwrap_or_else(|_| "false".to_string()).parse().unwrap_or(false),
+        };
+        
+        let storage = StorageConfig {
+            local_path: env::var("UPLOAD_DIR").unwrap_or_else(|_| "./uploads".to_string()),
+            public_url: env::var("UPLOAD_PUBLIC_URL")
+                .unwrap_or_else(|_| format!("{}/uploads", server.public_url.trim_end_matches('/'))),
+            avatar_max_size: env::var("AVATAR_MAX_SIZE")
+                .unwrap_or_else(|_| "5242880".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid AVATAR_MAX_SIZE".to_string()))?,
+            export_ttl: env::var("DATA_EXPORT_TTL")
+                .unwrap_or_else(|_| "604800".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid DATA_EXPORT_TTL".to_string()))?,
+        };
+        
+        let projects = ProjectsConfig {
+            invitation_ttl: env::var("PROJECT_INVITATION_TTL")
+                .unwrap_or_else(|_| "604800".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid PROJECT_INVITATION_TTL".to_string()))?,
+            transfer_ttl: env::var("PROJECT_TRANSFER_TTL")
+                .unwrap_or_else(|_| "604800".to_string())
+                .parse()
+                .map_err(|_| ConfigError::ParseError("Invalid PROJECT_TRANSFER_TTL".to_string()))?,
+        };
+        
+        if matches!(server.environment, Environment::Production) && oauth.local_provider {
+            return Err(ConfigError::ParseError(
+                "OAUTH_LOCAL_PROVIDER cannot be enabled in production".to_string(),
+            ));
+        }
+        
+        if matches!(server.environment, Environment::Production) && jwt_secret == DEFAULT_JWT_SECRET {
+            return Err(ConfigError::MissingEnv(
+                "JWT_SECRET must be set in production".to_string(),
+            ));
+        }
+        
+        if matches!(server.environment, Environment::Production)
+            && auth.two_factor_encryption_key == DEFAULT_TWO_FACTOR_ENCRYPTION_KEY
+        {
+            return Err(ConfigError::MissingEnv(
+                "TWO_FACTOR_ENCRYPTION_KEY must be set in production".to_string(),
+            ));
+        }
+        
+        if matches!(server.environment, Environment::Production) && email.smtp_username.is_empty() {
+            return Err(ConfigError::MissingEnv(
+                "EMAIL_SMTP_USER must be set in production".to_string(),
+            ));
+        }
+        
         Ok(Config {
             database_url,
             redis_url,
             jwt_secret,
+            auth,
             server,
             cors,
             email,
             logging,
             features,
+            jobs,
+            lockout,
+            oauth,
+            storage,
+            projects,
         })
     }
     
@@ -1,6 +1,29 @@
-use axum::{Router, routing::post};
+use std::sync::Arc;
 
-pub fn routes() -> Router {
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::middleware::auth::{AuthContext, RequireRegistered, RequireUser};
+use crate::models::{
+    ApiResponse, ForgotPasswordRequest, GuestMigrationResponse, GuestSessionRequest,
+    GuestSessionResponse, GuestToUserMigrationRequest, LoginOutcome, LoginRequest, LoginResponse,
+    OAuthCallbackRequest, RefreshTokenRequest, RegisterRequest, ResetPasswordRequest,
+    TwoFactorConfirmRequest, TwoFactorConfirmResponse, TwoFactorDisableRequest,
+    TwoFactorEnrollRequest, TwoFactorEnrollResponse, TwoFactorVerifyRequest, UserMigration,
+    UserResponse, VerifyEmailRequest,
+};
+use crate::services;
+use crate::utils::error::AppResult;
+use crate::utils::request::ClientInfo;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/login", post(login))
         .route("/register", post(register))
@@ -8,28 +31,286 @@ pub fn routes() -> Router {
         .route("/refresh", post(refresh_token))
         .route("/guest", post(create_guest))
         .route("/migrate", post(migrate_guest_to_user))
+        .route("/migrate/:id", get(get_migration))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .route("/verify-email", post(verify_email))
+        .route("/verify-email/resend", post(resend_verification))
+        .route("/2fa/enroll", post(enroll_two_factor))
+        .route("/2fa/confirm", post(confirm_two_factor))
+        .route("/2fa/verify", post(verify_two_factor))
+        .route("/2fa/disable", post(disable_two_factor))
+        .route("/oauth/providers", get(list_oauth_providers))
+        .route("/oauth/:provider/authorize", get(oauth_authorize))
+        .route("/oauth/:provider/callback", post(oauth_callback))
+}
+
+async fn login(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(payload): Json<LoginRequest>,
+) -> AppResult<Json<ApiResponse<LoginOutcome>>> {
+    let pool = state.pool()?;
+    let response =
+        services::auth::login(pool, &state.config, &state.login_guard, payload, &client).await?;
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+async fn register(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(payload): Json<RegisterRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<LoginResponse>>)> {
+    let pool = state.pool()?;
+    let response =
+        services::auth::register(pool, &state.config, &state.email, payload, &client).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(response))))
+}
+
+async fn logout(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    client: ClientInfo,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::session::logout(pool, &context, &client).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn refresh_token(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(payload): Json<RefreshTokenRequest>,
+) -> AppResult<Json<ApiResponse<LoginResponse>>> {
+    let pool = state.pool()?;
+    let response = services::auth::refresh(pool, &state.config, payload, &client).await?;
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+async fn create_guest(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(payload): Json<GuestSessionRequest>,
+) -> AppResult<Json<ApiResponse<GuestSessionResponse>>> {
+    let pool = state.pool()?;
+    let response =
+        services::auth::create_guest_session(pool, &state.config, payload, &client).await?;
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+async fn migrate_guest_to_user(
+    State(state): State<Arc<AppState>>,
+    context: AuthContext,
+    client: ClientInfo,
+    Json(payload): Json<GuestToUserMigrationRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<GuestMigrationResponse>>)> {
+    let pool = state.pool()?;
+    let response = services::migration::migrate_guest(
+        pool,
+        &state.config,
+        &state.email,
+        &context,
+        payload,
+        &client,
+    )
+    .await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(response))))
+}
+
+async fn forgot_password(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<()>>)> {
+    let pool = state.pool()?;
+    services::password_reset::forgot_password(pool, &state.config, &state.email, payload, &client)
+        .await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ApiResponse::success_with_message(
+            (),
+            "If an account exists for this email, a reset link has been sent".to_string(),
+        )),
+    ))
 }
 
-async fn login() -> &'static str {
-    "Login endpoint - TODO: Implement"
+async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::password_reset::reset_password(pool, &state.config, payload, &client).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn verify_email(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> AppResult<Json<ApiResponse<UserResponse>>> {
+    let pool = state.pool()?;
+    let user = services::verification::verify_email(pool, &state.config, payload).await?;
+
+    Ok(Json(ApiResponse::success(user)))
+}
+
+async fn resend_verification(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::verification::resend_verification(pool, &state.config, &state.email, &context)
+        .await?;
+
+    Ok(StatusCode::ACCEPTED)
 }
 
-async fn register() -> &'static str {
-    "Register endpoint - TODO: Implement"
+async fn get_migration(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<UserMigration>>> {
+    let pool = state.pool()?;
+    let migration = services::migration::find_migration(pool, &context, id).await?;
+
+    Ok(Json(ApiResponse::success(migration)))
 }
 
-async fn logout() -> &'static str {
-    "Logout endpoint - TODO: Implement"
+async fn enroll_two_factor(
+    State(state): State<Arc<AppState>>,
+    context: Option<AuthContext>,
+    payload: Option<Json<TwoFactorEnrollRequest>>,
+) -> AppResult<Json<ApiResponse<TwoFactorEnrollResponse>>> {
+    let pool = state.pool()?;
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    let response =
+        services::two_factor::enroll(pool, &state.config, context.as_ref(), payload).await?;
+
+    Ok(Json(ApiResponse::success(response)))
 }
 
-async fn refresh_token() -> &'static str {
-    "Refresh token endpoint - TODO: Implement"
+async fn confirm_two_factor(
+    State(state): State<Arc<AppState>>,
+    context: Option<AuthContext>,
+    client: ClientInfo,
+    Json(payload): Json<TwoFactorConfirmRequest>,
+) -> AppResult<Json<ApiResponse<TwoFactorConfirmResponse>>> {
+    let pool = state.pool()?;
+    let response =
+        services::two_factor::confirm(pool, &state.config, context.as_ref(), payload, &client)
+            .await?;
+
+    Ok(Json(ApiResponse::success(response)))
 }
 
-async fn create_guest() -> &'static str {
-    "Create guest endpoint - TODO: Implement"
+async fn verify_two_factor(
+    State(state): State<Arc<AppState>>,
+    client: ClientInfo,
+    Json(payload): Json<TwoFactorVerifyRequest>,
+) -> AppResult<Json<ApiResponse<LoginResponse>>> {
+    let pool = state.pool()?;
+    let response =
+        services::two_factor::verify(pool, &state.config, &state.login_guard, payload, &client)
+            .await?;
+
+    Ok(Json(ApiResponse::success(response)))
+}
+
+async fn disable_two_factor(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Json(payload): Json<TwoFactorDisableRequest>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::two_factor::disable(pool, &state.config, &context, payload).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_oauth_providers(
+    State(state): State<Arc<AppState>>,
+) -> Json<ApiResponse<Vec<String>>> {
+    Json(ApiResponse::success(state.oauth.names()))
+}
+
+async fn oauth_authorize(
+    State(state): State<Arc<AppState>>,
+    context: Option<AuthContext>,
+    Path(provider): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let pool = state.pool()?;
+    let (response, browser_nonce) = services::oauth::authorize(
+        pool,
+        &state.config,
+        &state.oauth,
+        &provider,
+        context.as_ref(),
+    )
+    .await?;
+
+    // SameSite=Lax : le cookie suit le retour depuis le fournisseur, pas une requête tierce
+    let cookie = format!(
+        "{}={}; Path=/api/v1/auth/oauth; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        services::oauth::BROWSER_BINDING_COOKIE,
+        browser_nonce,
+        services::oauth::AUTHORIZATION_TTL_MINUTES * 60,
+        if state.config.is_production() {
+            "; Secure"
+        } else {
+            ""
+        },
+    );
+
+    Ok((
+        [(header::SET_COOKIE, cookie)],
+        Json(ApiResponse::success(response)),
+    ))
+}
+
+async fn oauth_callback(
+    State(state): State<Arc<AppState>>,
+    context: Option<AuthContext>,
+    client: ClientInfo,
+    headers: HeaderMap,
+    Path(provider): Path<String>,
+    Json(payload): Json<OAuthCallbackRequest>,
+) -> AppResult<Json<ApiResponse<LoginOutcome>>> {
+    let pool = state.pool()?;
+    let browser_nonce = cookie_value(&headers, services::oauth::BROWSER_BINDING_COOKIE);
+    let response = services::oauth::callback(
+        pool,
+        &state.config,
+        &state.email,
+        &state.oauth,
+        &provider,
+        services::oauth::CallbackOrigin {
+            client: &client,
+            context: context.as_ref(),
+            browser_nonce,
+        },
+        payload,
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success(response)))
 }
 
-async fn migrate_guest_to_user() -> &'static str {
-    "Migrate guest to user endpoint - TODO: Implement"
+/// Valeur d'un cookie de la requête
+fn cookie_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
 }
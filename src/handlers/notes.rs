@@ -1,10 +1,17 @@
+use std::sync::Arc;
+
 use axum::{Router, routing::get};
 
-pub fn routes() -> Router {
+use crate::middleware::auth::{RequireScope, RequireUser};
+use crate::models::TokenScope;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(list_notes))
+        .route_layer(RequireScope::new(TokenScope::NotesRead, TokenScope::NotesWrite))
 }
 
-async fn list_notes() -> &'static str {
+async fn list_notes(_: RequireUser) -> &'static str {
     "List notes endpoint - TODO: Implement"
 }
@@ -1,19 +1,328 @@
-use axum::{Router, routing::{get, post}};
+use std::sync::Arc;
 
-pub fn routes() -> Router {
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    routing::{delete, get, patch, post, put},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::middleware::auth::{RequireRegistered, RequireScope, RequireUser};
+use crate::middleware::project_access::ProjectAccess;
+use crate::models::{
+    ApiResponse, CreateFromTemplateRequest, CreateProjectRequest, DuplicateProjectRequest,
+    DuplicateProjectResponse, InviteMemberRequest, PaginatedResponse, PaginationParams,
+    ProjectFilter, ProjectInvitationResponse, ProjectMemberResponse, ProjectPermissions,
+    ProjectResponse, ProjectTransferResponse, PublishTemplateRequest, TemplateCategory,
+    TemplateFilter, TokenScope, TransferOwnershipRequest, UpdateMemberRoleRequest,
+    UpdateProjectRequest,
+};
+use crate::services;
+use crate::utils::error::AppResult;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(list_projects).post(create_project))
-        .route("/:id", get(get_project))
+        .route("/:id", get(get_project).patch(update_project).delete(delete_project))
+        .route("/templates", get(list_templates))
+        .route("/templates/categories", get(list_template_categories))
+        .route("/templates/:id/instantiate", post(instantiate_template))
+        .route("/:id/template", put(publish_template).delete(unpublish_template))
+        .route("/:id/duplicate", post(duplicate_project))
+        .route("/:id/transfer", post(request_transfer).delete(cancel_transfer))
+        .route("/transfers", get(list_my_transfers))
+        .route("/transfers/:id/accept", post(accept_transfer))
+        .route("/transfers/:id/decline", post(decline_transfer))
+        .route("/:id/permissions", get(get_permissions))
+        .route("/:id/members", get(list_members))
+        .route("/:id/members/:user_id", patch(update_member_role).delete(remove_member))
+        .route("/:id/invitations", get(list_invitations).post(invite_member))
+        .route("/:id/invitations/:invitation_id", delete(revoke_invitation))
+        .route("/invitations", get(list_my_invitations))
+        .route("/invitations/:id/accept", post(accept_invitation))
+        .route("/invitations/:id/decline", post(decline_invitation))
+        .route_layer(RequireScope::new(TokenScope::ProjectsRead, TokenScope::ProjectsWrite))
+}
+
+async fn list_projects(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    Query(pagination): Query<PaginationParams>,
+    Query(filter): Query<ProjectFilter>,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<ProjectResponse>>>> {
+    let pool = state.pool()?;
+    let projects = services::project::list_projects(pool, &context, &pagination, &filter).await?;
+
+    Ok(Json(ApiResponse::success(projects)))
 }
 
-async fn list_projects() -> &'static str {
-    "List projects endpoint - TODO: Implement"
+async fn create_project(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    Json(payload): Json<CreateProjectRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<ProjectResponse>>)> {
+    let pool = state.pool()?;
+    let project = services::project::create_project(pool, &context, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(project))))
 }
 
-async fn create_project() -> &'static str {
-    "Create project endpoint - TODO: Implement"
+async fn get_project(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+) -> AppResult<Json<ApiResponse<ProjectResponse>>> {
+    let pool = state.pool()?;
+    let project = services::project::get_project(pool, &access).await?;
+
+    Ok(Json(ApiResponse::success(project)))
+}
+
+async fn update_project(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+    Json(payload): Json<UpdateProjectRequest>,
+) -> AppResult<Json<ApiResponse<ProjectResponse>>> {
+    let pool = state.pool()?;
+    let project = services::project::update_project(pool, &access, payload).await?;
+
+    Ok(Json(ApiResponse::success(project)))
 }
 
-async fn get_project() -> &'static str {
-    "Get project endpoint - TODO: Implement"
+async fn delete_project(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::project::delete_project(pool, &access).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Permissions effectives de l'utilisateur, pour adapter l'interface
+async fn get_permissions(access: ProjectAccess) -> Json<ApiResponse<ProjectPermissions>> {
+    Json(ApiResponse::success(access.permissions))
+}
+
+async fn list_members(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+) -> AppResult<Json<ApiResponse<Vec<ProjectMemberResponse>>>> {
+    let pool = state.pool()?;
+    let members = services::project_member::list_members(pool, &access).await?;
+
+    Ok(Json(ApiResponse::success(members)))
+}
+
+async fn update_member_role(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+    Path((_, user_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<UpdateMemberRoleRequest>,
+) -> AppResult<Json<ApiResponse<ProjectMemberResponse>>> {
+    let pool = state.pool()?;
+    let member =
+        services::project_member::update_member_role(pool, &access, user_id, payload).await?;
+
+    Ok(Json(ApiResponse::success(member)))
+}
+
+async fn remove_member(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+    Path((_, user_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::project_member::remove_member(pool, &access, user_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_invitations(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+) -> AppResult<Json<ApiResponse<Vec<ProjectInvitationResponse>>>> {
+    let pool = state.pool()?;
+    let invitations = services::project_member::list_invitations(pool, &access).await?;
+
+    Ok(Json(ApiResponse::success(invitations)))
+}
+
+async fn invite_member(
+    State(state): State<Arc<AppState>>,
+    _: RequireRegistered,
+    access: ProjectAccess,
+    Json(payload): Json<InviteMemberRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<ProjectInvitationResponse>>)> {
+    let pool = state.pool()?;
+    let invitation =
+        services::project_member::invite(pool, &state.config, &state.email, &access, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(invitation))))
+}
+
+async fn revoke_invitation(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+    Path((_, invitation_id)): Path<(Uuid, Uuid)>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::project_member::revoke_invitation(pool, &access, invitation_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_my_invitations(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+) -> AppResult<Json<ApiResponse<Vec<ProjectInvitationResponse>>>> {
+    let pool = state.pool()?;
+    let invitations =
+        services::project_member::list_my_invitations(pool, &state.config, &context).await?;
+
+    Ok(Json(ApiResponse::success(invitations)))
+}
+
+async fn accept_invitation(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<ProjectMemberResponse>>> {
+    let pool = state.pool()?;
+    let member =
+        services::project_member::accept_invitation(pool, &state.config, &context, id).await?;
+
+    Ok(Json(ApiResponse::success(member)))
+}
+
+async fn decline_invitation(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::project_member::decline_invitation(pool, &state.config, &context, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_templates(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    Query(pagination): Query<PaginationParams>,
+    Query(filter): Query<TemplateFilter>,
+) -> AppResult<Json<ApiResponse<PaginatedResponse<ProjectResponse>>>> {
+    let pool = state.pool()?;
+    let templates =
+        services::project_template::list_templates(pool, &context, &pagination, &filter).await?;
+
+    Ok(Json(ApiResponse::success(templates)))
+}
+
+async fn list_template_categories(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+) -> AppResult<Json<ApiResponse<Vec<TemplateCategory>>>> {
+    let pool = state.pool()?;
+    let categories = services::project_template::list_categories(pool, &context).await?;
+
+    Ok(Json(ApiResponse::success(categories)))
+}
+
+async fn instantiate_template(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+    Json(payload): Json<CreateFromTemplateRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<ProjectResponse>>)> {
+    let pool = state.pool()?;
+    let project = services::project_template::instantiate(pool, &access, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(project))))
+}
+
+async fn publish_template(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+    Json(payload): Json<PublishTemplateRequest>,
+) -> AppResult<Json<ApiResponse<ProjectResponse>>> {
+    let pool = state.pool()?;
+    let project = services::project_template::publish(pool, &access, payload).await?;
+
+    Ok(Json(ApiResponse::success(project)))
+}
+
+async fn unpublish_template(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+) -> AppResult<Json<ApiResponse<ProjectResponse>>> {
+    let pool = state.pool()?;
+    let project = services::project_template::unpublish(pool, &access).await?;
+
+    Ok(Json(ApiResponse::success(project)))
+}
+
+async fn duplicate_project(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+    Json(payload): Json<DuplicateProjectRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<DuplicateProjectResponse>>)> {
+    let pool = state.pool()?;
+    let duplicate = services::project_copy::duplicate(pool, &access, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(duplicate))))
+}
+
+async fn request_transfer(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+    Json(payload): Json<TransferOwnershipRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<ProjectTransferResponse>>)> {
+    let pool = state.pool()?;
+    let transfer =
+        services::project_transfer::request_transfer(pool, &state.config, &access, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(transfer))))
+}
+
+async fn cancel_transfer(
+    State(state): State<Arc<AppState>>,
+    access: ProjectAccess,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::project_transfer::cancel_transfer(pool, &access).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_my_transfers(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+) -> AppResult<Json<ApiResponse<Vec<ProjectTransferResponse>>>> {
+    let pool = state.pool()?;
+    let transfers = services::project_transfer::list_my_transfers(pool, &context).await?;
+
+    Ok(Json(ApiResponse::success(transfers)))
+}
+
+async fn accept_transfer(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<ProjectResponse>>> {
+    let pool = state.pool()?;
+    let project = services::project_transfer::accept_transfer(pool, &context, id).await?;
+
+    Ok(Json(ApiResponse::success(project)))
+}
+
+async fn decline_transfer(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::project_transfer::decline_transfer(pool, &context, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -1,10 +1,30 @@
-use axum::{Router, routing::get};
+use std::sync::Arc;
 
-pub fn routes() -> Router {
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+
+use crate::middleware::auth::{RequireRegistered, RequireScope};
+use crate::models::{ApiResponse, PublicSnippet, PublishSnippetRequest, TokenScope};
+use crate::services;
+use crate::utils::error::AppResult;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/snippets", get(list_public_snippets))
+        .route("/snippets", get(list_public_snippets).post(publish_snippet))
+        .route_layer(RequireScope::new(TokenScope::SnippetsRead, TokenScope::SnippetsWrite))
 }
 
 async fn list_public_snippets() -> &'static str {
     "List public snippets endpoint - TODO: Implement"
 }
+
+async fn publish_snippet(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Json(payload): Json<PublishSnippetRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<PublicSnippet>>)> {
+    let pool = state.pool()?;
+    let snippet = services::public_snippet::publish(pool, &state.config, &context, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(snippet))))
+}
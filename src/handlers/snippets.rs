@@ -1,10 +1,17 @@
+use std::sync::Arc;
+
 use axum::{Router, routing::get};
 
-pub fn routes() -> Router {
+use crate::middleware::auth::{RequireScope, RequireUser};
+use crate::models::TokenScope;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(list_snippets))
+        .route_layer(RequireScope::new(TokenScope::SnippetsRead, TokenScope::SnippetsWrite))
 }
 
-async fn list_snippets() -> &'static str {
+async fn list_snippets(_: RequireUser) -> &'static str {
     "List snippets endpoint - TODO: Implement"
 }
@@ -1,10 +1,17 @@
+use std::sync::Arc;
+
 use axum::{Router, routing::get};
 
-pub fn routes() -> Router {
+use crate::middleware::auth::{RequireScope, RequireUser};
+use crate::models::TokenScope;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/", get(list_tasks))
+        .route_layer(RequireScope::new(TokenScope::TasksRead, TokenScope::TasksWrite))
 }
 
-async fn list_tasks() -> &'static str {
+async fn list_tasks(_: RequireUser) -> &'static str {
     "List tasks endpoint - TODO: Implement"
 }
@@ -1,15 +1,299 @@
-use axum::{Router, routing::get};
+use std::sync::Arc;
 
-pub fn routes() -> Router {
+use axum::{
+    extract::{DefaultBodyLimit, Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    routing::{delete, get, post},
+    Json, Router,
+};
+use uuid::Uuid;
+
+use crate::middleware::auth::{AuthContext, RequireRegistered, RequireRole, RequireScope, RequireUser};
+use crate::models::{
+    AccountDeletionRequest, AccountDeletionResponse, ApiResponse, AvatarResponse, CreatePersonalAccessTokenRequest, DataExportDownloadQuery,
+    DataExportLinkResponse, DataExportResponse, CreatedPersonalAccessTokenResponse,
+    PersonalAccessTokenResponse, PublicProfileResponse, RevokedSessionsResponse, SessionResponse,
+    TokenScope, UpdateUserRequest, UserResponse, UserRole,
+};
+use crate::services;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::request::ClientInfo;
+use crate::AppState;
+
+pub fn routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/me/sessions", get(list_sessions).delete(revoke_other_sessions))
+        .route("/me/sessions/:id", delete(revoke_session))
+        .route("/me/tokens", get(list_tokens).post(create_token))
+        .route("/me/tokens/:id", delete(revoke_token))
+        .route("/me/deletion", post(request_account_deletion).delete(cancel_account_deletion))
+        .route("/me/export", post(request_export))
+        .route("/me/exports", get(list_exports))
+        .route("/me/exports/:id", get(get_export))
+        .route("/me/exports/:id/link", post(issue_export_link))
+        .route("/me/exports/:id/download", get(download_export))
+        .merge(profile_routes())
+        .merge(admin_routes())
+}
+
+/// Routes accessibles aux tokens personnels ; sessions et tokens restent réservés aux sessions
+fn profile_routes() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/me", get(get_current_user).patch(update_current_user))
+        // La taille est contrôlée pendant la lecture, selon `AVATAR_MAX_SIZE`
+        .route("/me/avatar", post(upload_avatar).layer(DefaultBodyLimit::disable()))
+        .route("/:user", get(get_user_profile))
+        .route_layer(RequireScope::new(TokenScope::UserRead, TokenScope::UserWrite))
+}
+
+fn admin_routes() -> Router<Arc<AppState>> {
     Router::new()
-        .route("/me", get(get_current_user))
-        .route("/profile", get(get_user_profile))
+        .route("/:user/unlock", post(unlock_user))
+        .route_layer(RequireRole(UserRole::Admin))
+}
+
+async fn get_current_user(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+) -> AppResult<Json<ApiResponse<UserResponse>>> {
+    let pool = state.pool()?;
+    let user = services::user::current_user(pool, &context).await?;
+
+    Ok(Json(ApiResponse::success(user)))
+}
+
+async fn update_current_user(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    Json(payload): Json<UpdateUserRequest>,
+) -> AppResult<Json<ApiResponse<UserResponse>>> {
+    let pool = state.pool()?;
+    let user = services::user::update_current_user(
+        pool,
+        &state.config,
+        &state.email,
+        state.storage.as_ref(),
+        &context,
+        payload,
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success(user)))
+}
+
+async fn upload_avatar(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    multipart: Multipart,
+) -> AppResult<Json<ApiResponse<AvatarResponse>>> {
+    let pool = state.pool()?;
+    let data = read_avatar(multipart, state.config.storage.avatar_max_size).await?;
+    let avatar = services::avatar::upload(pool, state.storage.as_ref(), &context, data).await?;
+
+    Ok(Json(ApiResponse::success(avatar)))
+}
+
+/// Lit l'unique champ `avatar` du formulaire en s'arrêtant dès que la limite est dépassée
+async fn read_avatar(mut multipart: Multipart, max_size: usize) -> AppResult<Vec<u8>> {
+    let invalid = |e: axum::extract::multipart::MultipartError| AppError::BadRequest(e.body_text());
+    let mut avatar = None;
+
+    while let Some(mut field) = multipart.next_field().await.map_err(invalid)? {
+        if field.name() != Some("avatar") || avatar.is_some() {
+            return Err(AppError::BadRequest(
+                "Expected a single `avatar` file field".to_string(),
+            ));
+        }
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(invalid)? {
+            if data.len() + chunk.len() > max_size {
+                return Err(AppError::PayloadTooLarge(format!(
+                    "Avatar must not exceed {} bytes",
+                    max_size
+                )));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        avatar = Some(data);
+    }
+
+    avatar.ok_or_else(|| AppError::BadRequest("Missing `avatar` file field".to_string()))
+}
+
+async fn get_user_profile(
+    State(state): State<Arc<AppState>>,
+    Path(username): Path<String>,
+) -> AppResult<Json<ApiResponse<PublicProfileResponse>>> {
+    let pool = state.pool()?;
+    let profile = services::user::find_public_profile(pool, &username).await?;
+
+    Ok(Json(ApiResponse::success(profile)))
+}
+
+async fn list_sessions(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+) -> AppResult<Json<ApiResponse<Vec<SessionResponse>>>> {
+    let pool = state.pool()?;
+    let sessions = services::session::list_sessions(pool, &context).await?;
+
+    Ok(Json(ApiResponse::success(sessions)))
 }
 
-async fn get_current_user() -> &'static str {
-    "Get current user endpoint - TODO: Implement"
+async fn revoke_session(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::session::revoke_session(pool, &context, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Révoque toutes les sessions sauf celle de la requête
+async fn revoke_other_sessions(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+) -> AppResult<Json<ApiResponse<RevokedSessionsResponse>>> {
+    let pool = state.pool()?;
+    let revoked = services::session::revoke_other_sessions(pool, &context).await?;
+
+    Ok(Json(ApiResponse::success(RevokedSessionsResponse { revoked })))
 }
 
-async fn get_user_profile() -> &'static str {
-    "Get user profile endpoint - TODO: Implement"
+async fn unlock_user(
+    State(state): State<Arc<AppState>>,
+    context: AuthContext,
+    client: ClientInfo,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    state.login_guard.unlock(pool, &context, id, &client).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_tokens(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+) -> AppResult<Json<ApiResponse<Vec<PersonalAccessTokenResponse>>>> {
+    let pool = state.pool()?;
+    let tokens = services::personal_token::list(pool, &context).await?;
+
+    Ok(Json(ApiResponse::success(tokens)))
+}
+
+async fn create_token(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Json(payload): Json<CreatePersonalAccessTokenRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<CreatedPersonalAccessTokenResponse>>)> {
+    let pool = state.pool()?;
+    let token = services::personal_token::create(pool, &context, payload).await?;
+
+    Ok((StatusCode::CREATED, Json(ApiResponse::success(token))))
+}
+
+async fn revoke_token(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Path(id): Path<Uuid>,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::personal_token::revoke(pool, &context, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn request_account_deletion(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+    Json(payload): Json<AccountDeletionRequest>,
+) -> AppResult<(StatusCode, Json<ApiResponse<AccountDeletionResponse>>)> {
+    let pool = state.pool()?;
+    let deletion =
+        services::account_deletion::request_deletion(pool, &state.config, &context, payload).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(ApiResponse::success(deletion))))
+}
+
+async fn cancel_account_deletion(
+    State(state): State<Arc<AppState>>,
+    RequireRegistered(context): RequireRegistered,
+) -> AppResult<StatusCode> {
+    let pool = state.pool()?;
+    services::account_deletion::cancel_deletion(pool, &context).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn request_export(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+) -> AppResult<(StatusCode, Json<ApiResponse<DataExportResponse>>)> {
+    let pool = state.pool()?;
+    let export =
+        services::export::request_export(pool, &state.config, state.storage.clone(), &context)
+            .await?;
+
+    Ok((StatusCode::ACCEPTED, Json(ApiResponse::success(export))))
+}
+
+async fn list_exports(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+) -> AppResult<Json<ApiResponse<Vec<DataExportResponse>>>> {
+    let pool = state.pool()?;
+    let exports = services::export::list_exports(pool, &context).await?;
+
+    Ok(Json(ApiResponse::success(exports)))
+}
+
+async fn get_export(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<DataExportResponse>>> {
+    let pool = state.pool()?;
+    let export = services::export::find_export(pool, &context, id).await?;
+
+    Ok(Json(ApiResponse::success(export)))
+}
+
+async fn issue_export_link(
+    State(state): State<Arc<AppState>>,
+    RequireUser(context): RequireUser,
+    Path(id): Path<Uuid>,
+) -> AppResult<Json<ApiResponse<DataExportLinkResponse>>> {
+    let pool = state.pool()?;
+    let link = services::export::issue_download_link(pool, &state.config, &context, id).await?;
+
+    Ok(Json(ApiResponse::success(link)))
+}
+
+/// Téléchargement par le lien généré pour l'export, sans session : le token fait foi
+async fn download_export(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DataExportDownloadQuery>,
+) -> AppResult<impl IntoResponse> {
+    let pool = state.pool()?;
+    let (export, archive) =
+        services::export::download(pool, state.storage.as_ref(), id, &query.token).await?;
+    let disposition = format!(
+        "attachment; filename=\"ettu-export-{}.zip\"",
+        export.created_at.format("%Y-%m-%d")
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        archive,
+    ))
 }
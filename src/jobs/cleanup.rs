@@ -0,0 +1,138 @@
+use chrono::Duration;
+use metrics::counter;
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::services::user::NULLABLE_REFERENCES;
+use crate::utils::error::AppResult;
+
+/// Type de la notification envoyée avant la suppression d'un invité persistant
+pub const GUEST_EXPIRATION_NOTIFICATION: &str = "guest_expiration_warning";
+
+/// Purge les sessions et invités expirés
+///
+/// Les invités non persistants sont supprimés dès leur expiration. Les invités
+/// persistants reçoivent une notification dans la fenêtre précédant leur
+/// expiration et ne sont supprimés qu'une fois ce délai écoulé depuis
+/// l'avertissement, même s'ils ont été prévenus en retard.
+pub async fn run(pool: &PgPool, config: &Config) -> AppResult<()> {
+    let warning_window = Duration::seconds(config.jobs.guest_expiration_warning);
+
+    let guests_warned = sqlx::query(
+        r#"
+        INSERT INTO notifications (user_id, type, title, message)
+        SELECT u.id, $1,
+               'Votre espace invité va expirer',
+               'Créez un compte pour conserver vos projets, ou reconnectez-vous pour prolonger votre session.'
+        FROM users u
+        WHERE u.user_type = 'guest'
+          AND u.session_expires_at < NOW() + $2
+          AND EXISTS (SELECT 1 FROM user_sessions s WHERE s.user_id = u.id AND s.is_persistent)
+          AND NOT EXISTS (
+              SELECT 1 FROM notifications n
+              WHERE n.user_id = u.id AND n.type = $1 AND n.created_at >= u.session_expires_at - $2
+          )
+        "#,
+    )
+    .bind(GUEST_EXPIRATION_NOTIFICATION)
+    .bind(warning_window)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    let expired_guests: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT u.id FROM users u
+        WHERE u.user_type = 'guest'
+          AND u.session_expires_at < NOW()
+          AND (
+              NOT EXISTS (SELECT 1 FROM user_sessions s WHERE s.user_id = u.id AND s.is_persistent)
+              OR EXISTS (
+                  SELECT 1 FROM notifications n
+                  WHERE n.user_id = u.id AND n.type = $1
+                    AND n.created_at >= u.session_expires_at - $2
+                    AND n.created_at <= NOW() - $2
+              )
+          )
+        "#,
+    )
+    .bind(GUEST_EXPIRATION_NOTIFICATION)
+    .bind(warning_window)
+    .fetch_all(pool)
+    .await?;
+
+    // Un invité par transaction : un invité impossible à supprimer ne bloque pas les autres
+    let mut guests_deleted = 0;
+    for guest_id in expired_guests {
+        match delete_guest(pool, guest_id).await {
+            Ok(true) => guests_deleted += 1,
+            Ok(false) => {}
+            Err(e) => warn!(user_id = %guest_id, "Failed to delete expired guest: {}", e),
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Les sessions persistantes servent de marqueur tant que leur invité existe
+    let sessions_deleted = sqlx::query(
+        "DELETE FROM user_sessions WHERE expires_at < NOW() AND NOT is_persistent",
+    )
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    // Autorisations OAuth abandonnées avant le callback
+    sqlx::query("DELETE FROM oauth_authorization_requests WHERE expires_at < NOW()")
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    counter!("ettu_cleanup_sessions_deleted_total").increment(sessions_deleted);
+    counter!("ettu_cleanup_guests_warned_total").increment(guests_warned);
+    counter!("ettu_cleanup_guests_deleted_total").increment(guests_deleted);
+
+    info!(sessions_deleted, guests_warned, guests_deleted, "Cleanup completed");
+
+    Ok(())
+}
+
+/// Supprime un invité expiré, s'il l'est toujours
+///
+/// Un invité reconnecté ou converti depuis la sélection est laissé en place.
+async fn delete_guest(pool: &PgPool, guest_id: Uuid) -> AppResult<bool> {
+    let mut tx = pool.begin().await?;
+
+    let still_expired: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM users
+        WHERE id = $1 AND user_type = 'guest' AND session_expires_at < NOW()
+        FOR UPDATE SKIP LOCKED
+        "#,
+    )
+    .bind(guest_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    if still_expired.is_none() {
+        return Ok(false);
+    }
+
+    for statement in NULLABLE_REFERENCES {
+        sqlx::query(statement)
+            .bind(vec![guest_id])
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    // Le contenu de l'invité (projets, notes, sessions...) suit par cascade
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(guest_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
@@ -0,0 +1,33 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::PgPool;
+use tracing::{error, info};
+
+use crate::config::Config;
+use crate::services::{account_deletion, export};
+use crate::utils::storage::Storage;
+
+pub mod cleanup;
+
+/// Démarre les tâches périodiques en arrière-plan
+pub fn spawn(pool: PgPool, config: Config, storage: Arc<dyn Storage>) {
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(Duration::from_secs(config.jobs.cleanup_interval.max(1)));
+        info!(interval_secs = config.jobs.cleanup_interval, "Cleanup job scheduled");
+
+        loop {
+            interval.tick().await;
+            if let Err(e) = cleanup::run(&pool, &config).await {
+                error!("Cleanup job failed: {}", e);
+            }
+            if let Err(e) = export::purge_expired(&pool, storage.as_ref()).await {
+                error!("Data export purge failed: {}", e);
+            }
+            if let Err(e) = account_deletion::purge_due(&pool, storage.as_ref()).await {
+                error!("Account deletion job failed: {}", e);
+            }
+        }
+    });
+}
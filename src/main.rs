@@ -11,6 +11,7 @@ use axum::{
 use tower::ServiceBuilder;
 use tower_http::{
     cors::{Any, CorsLayer},
+    services::ServeDir,
     trace::TraceLayer,
 };
 use tracing::{info, warn};
@@ -19,6 +20,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 mod config;
 mod database;
 mod handlers;
+mod jobs;
 mod middleware;
 mod models;
 mod services;
@@ -26,11 +28,30 @@ mod utils;
 
 use config::Config;
 use database::Database;
+use services::email::EmailService;
+use services::lockout::LoginGuard;
+use services::oauth::OAuthProviders;
+use utils::error::{AppError, AppResult};
+use utils::storage::{LocalStorage, Storage};
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: Option<Database>,
     pub config: Config,
+    pub email: EmailService,
+    pub login_guard: LoginGuard,
+    pub oauth: OAuthProviders,
+    pub storage: Arc<dyn Storage>,
+}
+
+impl AppState {
+    /// Pool de connexions, ou une erreur 503 en mode sans base de données
+    pub fn pool(&self) -> AppResult<&sqlx::PgPool> {
+        self.db
+            .as_ref()
+            .map(|db| db.pool())
+            .ok_or_else(|| AppError::ServiceUnavailable("Database is not available".to_string()))
+    }
 }
 
 #[tokio::main]
@@ -73,10 +94,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(&config.storage));
+
+    if let Some(ref database) = db {
+        jobs::spawn(database.pool().clone(), config.clone(), storage.clone());
+    }
+
+    let email = EmailService::new(&config.email).expect("Failed to initialize email service");
+
+    let counters = utils::store::connect(&config.redis_url).await;
+    let login_guard = LoginGuard::new(counters, &config.lockout);
+
     // Application state
     let app_state = AppState {
         db: db.clone(),
         config: config.clone(),
+        email,
+        login_guard,
+        oauth: OAuthProviders::from_config(&config),
+        storage,
     };
 
     // Build application routes
@@ -88,8 +124,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     
-    axum::serve(listener, app)
-        .await
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
         .expect("Failed to start server");
 
     Ok(())
@@ -111,8 +150,17 @@ fn build_router(state: Arc<AppState>) -> Router {
         // Health check
         .route("/health", get(handlers::health::health_check))
         .route("/metrics", get(handlers::metrics::metrics))
+        // Avatars envoyés ; les autres fichiers stockés (exports) restent privés
+        .nest_service(
+            "/uploads/avatars",
+            ServeDir::new(std::path::Path::new(&state.config.storage.local_path).join("avatars")),
+        )
         // API routes
         .nest("/api/v1", api_routes())
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            middleware::auth::authenticate,
+        ))
         .with_state(state)
         .layer(
             ServiceBuilder::new()
@@ -124,6 +172,11 @@ fn build_router(state: Arc<AppState>) -> Router {
 fn api_routes() -> Router<Arc<AppState>> {
     Router::new()
         .route("/status", get(|| async { "API is running" }))
-        // Pour l'instant, on ajoute juste une route de test
-        // On ajoutera les autres routes plus tard
+        .nest("/auth", handlers::auth::routes())
+        .nest("/users", handlers::users::routes())
+        .nest("/projects", handlers::projects::routes())
+        .nest("/tasks", handlers::tasks::routes())
+        .nest("/notes", handlers::notes::routes())
+        .nest("/snippets", handlers::snippets::routes())
+        .nest("/public", handlers::public::routes())
 }
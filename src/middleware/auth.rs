@@ -1 +1,466 @@
-// Auth middleware - TODO: Implement
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap, Method},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Duration, Utc};
+use futures::future::BoxFuture;
+use sqlx::FromRow;
+use tower::{Layer, Service};
+use uuid::Uuid;
+
+use crate::models::{SessionType, TokenScope, UserRole, UserType};
+use crate::services::personal_token;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::jwt;
+use crate::utils::token::hash_token;
+use crate::AppState;
+
+/// Intervalle minimal entre deux mises à jour de `last_used_at` pour une session
+const LAST_USED_RESOLUTION_SECS: i64 = 60;
+
+/// Identité résolue à partir du Bearer token de la requête
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    pub user_id: Uuid,
+    /// Session courante, ou token personnel utilisé
+    pub session_id: Uuid,
+    pub session_type: SessionType,
+    pub user_type: UserType,
+    pub role: UserRole,
+    pub is_verified: bool,
+    /// Scopes du token personnel ; `None` pour une session, qui a tous les droits
+    pub scopes: Option<Vec<TokenScope>>,
+}
+
+/// Marque posée par `RequireScope` lorsque le token personnel a le scope requis
+#[derive(Debug, Clone, Copy)]
+struct ScopeGranted;
+
+impl AuthContext {
+    pub fn is_personal_token(&self) -> bool {
+        self.scopes.is_some()
+    }
+
+    /// Indique si le contexte autorise `scope` (toujours vrai pour une session)
+    pub fn allows(&self, scope: TokenScope) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.iter().any(|s| s.includes(scope)))
+    }
+
+    pub fn is_guest(&self) -> bool {
+        matches!(self.session_type, SessionType::Guest) || matches!(self.user_type, UserType::Guest)
+    }
+
+    pub fn is_registered(&self) -> bool {
+        !self.is_guest()
+    }
+}
+
+/// Résout le Bearer token en `AuthContext` et l'insère dans les extensions
+///
+/// Une requête sans token, ou avec un token invalide, continue sans contexte :
+/// ce sont les extracteurs `Require*` qui refusent l'accès.
+pub async fn authenticate(
+    State(state): State<Arc<AppState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(token) = bearer_token(request.headers()) {
+        let resolved = if personal_token::is_personal_token(&token) {
+            resolve_personal_token(&state, &token).await
+        } else {
+            resolve(&state, &token).await
+        };
+
+        match resolved {
+            Ok(context) => {
+                request.extensions_mut().insert(context);
+            }
+            Err(AppError::Unauthorized(_)) => {}
+            Err(e) => return e.into_response(),
+        }
+    }
+
+    next.run(request).await
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// Valide le JWT puis vérifie que sa session est toujours active
+async fn resolve(state: &AppState, token: &str) -> AppResult<AuthContext> {
+    let claims = jwt::validate_access_token(&state.config, token)?;
+    let pool = state.pool()?;
+
+    // Le hash du token courant est comparé pour invalider les tokens d'accès
+    // remplacés lors d'un refresh
+    let row: Option<(String, DateTime<Utc>, String, String, bool)> = sqlx::query_as(
+        r#"
+        SELECT s.session_type, s.last_used_at, u.user_type, u.role, u.is_verified
+        FROM user_sessions s
+        JOIN users u ON u.id = s.user_id
+        WHERE s.id = $1
+          AND s.user_id = $2
+          AND s.token_hash = $3
+          AND s.revoked_at IS NULL
+          AND s.expires_at > NOW()
+          AND u.is_active
+        "#,
+    )
+    .bind(claims.sid)
+    .bind(claims.sub)
+    .bind(hash_token(token))
+    .fetch_optional(pool)
+    .await?;
+
+    let (session_type, last_used_at, user_type, role, is_verified) =
+        row.ok_or_else(|| AppError::Unauthorized("Session is no longer valid".to_string()))?;
+
+    if Utc::now() - last_used_at > Duration::seconds(LAST_USED_RESOLUTION_SECS) {
+        sqlx::query("UPDATE user_sessions SET last_used_at = NOW() WHERE id = $1")
+            .bind(claims.sid)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(AuthContext {
+        user_id: claims.sub,
+        session_id: claims.sid,
+        session_type: session_type.parse().map_err(AppError::Internal)?,
+        user_type: user_type.parse().map_err(AppError::Internal)?,
+        role: role.parse().map_err(AppError::Internal)?,
+        is_verified,
+        scopes: None,
+    })
+}
+
+/// Token personnel actif et compte auquel il appartient
+#[derive(FromRow)]
+struct PersonalTokenRow {
+    id: Uuid,
+    user_id: Uuid,
+    scopes: Vec<String>,
+    last_used_at: Option<DateTime<Utc>>,
+    user_type: String,
+    role: String,
+    is_verified: bool,
+}
+
+/// Vérifie qu'un token personnel est actif et retourne ses scopes
+async fn resolve_personal_token(state: &AppState, token: &str) -> AppResult<AuthContext> {
+    let pool = state.pool()?;
+
+    let token = sqlx::query_as::<_, PersonalTokenRow>(
+        r#"
+        SELECT t.id, t.user_id, t.scopes, t.last_used_at, u.user_type, u.role, u.is_verified
+        FROM personal_access_tokens t
+        JOIN users u ON u.id = t.user_id
+        WHERE t.token_hash = $1
+          AND t.revoked_at IS NULL
+          AND (t.expires_at IS NULL OR t.expires_at > NOW())
+          AND u.is_active
+        "#,
+    )
+    .bind(hash_token(token))
+    .fetch_optional(pool)
+    .await?;
+
+    let token = token.ok_or_else(|| AppError::Unauthorized("Token is no longer valid".to_string()))?;
+
+    if token
+        .last_used_at
+        .is_none_or(|at| Utc::now() - at > Duration::seconds(LAST_USED_RESOLUTION_SECS))
+    {
+        sqlx::query("UPDATE personal_access_tokens SET last_used_at = NOW() WHERE id = $1")
+            .bind(token.id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(AuthContext {
+        user_id: token.user_id,
+        session_id: token.id,
+        session_type: SessionType::Authenticated,
+        user_type: token.user_type.parse().map_err(AppError::Internal)?,
+        role: token.role.parse().map_err(AppError::Internal)?,
+        is_verified: token.is_verified,
+        scopes: Some(token.scopes.iter().filter_map(|s| s.parse().ok()).collect()),
+    })
+}
+
+fn unauthenticated() -> AppError {
+    AppError::Unauthorized("Authentication required".to_string())
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for AuthContext
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let context = parts
+            .extensions
+            .get::<AuthContext>()
+            .cloned()
+            .ok_or_else(unauthenticated)?;
+
+        // Un token personnel n'accède qu'aux routes qui déclarent un scope
+        if context.is_personal_token() && parts.extensions.get::<ScopeGranted>().is_none() {
+            return Err(AppError::Forbidden(
+                "Personal access tokens cannot access this endpoint".to_string(),
+            ));
+        }
+
+        Ok(context)
+    }
+}
+
+/// Exige une session valide, invité ou compte
+#[derive(Debug, Clone)]
+pub struct RequireUser(pub AuthContext);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequireUser
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        AuthContext::from_request_parts(parts, state).await.map(RequireUser)
+    }
+}
+
+/// Exige une session authentifiée d'un compte enregistré
+#[derive(Debug, Clone)]
+pub struct RequireRegistered(pub AuthContext);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequireRegistered
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let context = AuthContext::from_request_parts(parts, state).await?;
+        if !context.is_registered() {
+            return Err(AppError::Forbidden("A registered account is required".to_string()));
+        }
+
+        Ok(RequireRegistered(context))
+    }
+}
+
+/// Layer réservant des routes à un rôle minimal, ex. `RequireRole(UserRole::Moderator)`
+#[derive(Debug, Clone, Copy)]
+pub struct RequireRole(pub UserRole);
+
+impl<S> Layer<S> for RequireRole {
+    type Service = RequireRoleService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireRoleService {
+            inner,
+            role: self.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequireRoleService<S> {
+    inner: S,
+    role: UserRole,
+}
+
+impl<S> Service<Request> for RequireRoleService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let rejection = match request.extensions().get::<AuthContext>() {
+            None => Some(unauthenticated()),
+            Some(context) if !context.role.includes(self.role) => {
+                Some(AppError::Forbidden("Insufficient role".to_string()))
+            }
+            Some(_) => None,
+        };
+
+        match rejection {
+            Some(error) => Box::pin(async move { Ok(error.into_response()) }),
+            None => Box::pin(self.inner.call(request)),
+        }
+    }
+}
+
+/// Layer déclarant les scopes requis d'un token personnel sur des routes
+///
+/// Le scope de lecture s'applique aux requêtes GET et HEAD, celui d'écriture
+/// aux autres méthodes. Les sessions ne sont pas concernées.
+#[derive(Debug, Clone, Copy)]
+pub struct RequireScope {
+    read: TokenScope,
+    write: TokenScope,
+}
+
+impl RequireScope {
+    pub fn new(read: TokenScope, write: TokenScope) -> Self {
+        Self { read, write }
+    }
+
+    fn required(&self, method: &Method) -> TokenScope {
+        if method == Method::GET || method == Method::HEAD {
+            self.read
+        } else {
+            self.write
+        }
+    }
+}
+
+impl<S> Layer<S> for RequireScope {
+    type Service = RequireScopeService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequireScopeService { inner, scope: *self }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequireScopeService<S> {
+    inner: S,
+    scope: RequireScope,
+}
+
+impl<S> Service<Request> for RequireScopeService<S>
+where
+    S: Service<Request, Response = Response> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request) -> Self::Future {
+        let required = self.scope.required(request.method());
+        let granted = match request.extensions().get::<AuthContext>() {
+            Some(context) if context.is_personal_token() => context.allows(required),
+            _ => true,
+        };
+
+        if !granted {
+            let error = AppError::Forbidden(format!("Token is missing the `{}` scope", required));
+            return Box::pin(async move { Ok(error.into_response()) });
+        }
+
+        request.extensions_mut().insert(ScopeGranted);
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn context(role: UserRole) -> AuthContext {
+        AuthContext {
+            user_id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            session_type: SessionType::Authenticated,
+            user_type: UserType::Registered,
+            role,
+            is_verified: true,
+            scopes: None,
+        }
+    }
+
+    async fn call(context: Option<AuthContext>) -> StatusCode {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .route_layer(RequireRole(UserRole::Moderator));
+
+        let mut request = Request::builder().uri("/").body(Body::empty()).unwrap();
+        if let Some(context) = context {
+            request.extensions_mut().insert(context);
+        }
+
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_require_role() {
+        assert_eq!(call(None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(call(Some(context(UserRole::User))).await, StatusCode::FORBIDDEN);
+        assert_eq!(call(Some(context(UserRole::Moderator))).await, StatusCode::OK);
+        assert_eq!(call(Some(context(UserRole::Admin))).await, StatusCode::OK);
+    }
+
+    async fn call_scoped(context: AuthContext, method: Method, uri: &str) -> StatusCode {
+        let handler = |_: AuthContext| async { "ok" };
+        let app = Router::new()
+            .route("/scoped", get(handler).post(handler))
+            .route_layer(RequireScope::new(TokenScope::ProjectsRead, TokenScope::ProjectsWrite))
+            .route("/unscoped", get(handler));
+
+        let mut request = Request::builder().method(method).uri(uri).body(Body::empty()).unwrap();
+        request.extensions_mut().insert(context);
+
+        app.oneshot(request).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_require_scope() {
+        let mut token = context(UserRole::User);
+        token.scopes = Some(vec![TokenScope::ProjectsRead]);
+
+        assert_eq!(call_scoped(token.clone(), Method::GET, "/scoped").await, StatusCode::OK);
+        assert_eq!(call_scoped(token.clone(), Method::POST, "/scoped").await, StatusCode::FORBIDDEN);
+        // Une route sans scope déclaré reste fermée aux tokens personnels
+        assert_eq!(call_scoped(token, Method::GET, "/unscoped").await, StatusCode::FORBIDDEN);
+
+        let session = context(UserRole::User);
+        assert_eq!(call_scoped(session.clone(), Method::POST, "/scoped").await, StatusCode::OK);
+        assert_eq!(call_scoped(session, Method::GET, "/unscoped").await, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_bearer_token() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(bearer_token(&headers), None);
+
+        headers.insert(header::AUTHORIZATION, "Bearer abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers).as_deref(), Some("abc"));
+
+        headers.insert(header::AUTHORIZATION, "Basic abc".parse().unwrap());
+        assert_eq!(bearer_token(&headers), None);
+    }
+}
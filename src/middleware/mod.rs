@@ -1,4 +1,5 @@
 pub mod auth;
+pub mod project_access;
 pub mod cors;
 pub mod rate_limit;
 pub mod logging;
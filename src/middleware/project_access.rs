@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Path},
+    http::request::Parts,
+};
+use uuid::Uuid;
+
+use crate::middleware::auth::AuthContext;
+use crate::models::{ProjectPermission, ProjectPermissions, ProjectRole, UserRole};
+use crate::services::permission;
+use crate::utils::error::{AppError, AppResult};
+use crate::AppState;
+
+/// Accès de l'utilisateur au projet désigné par le paramètre `:id` de la route
+///
+/// Refuse (404) un projet que l'utilisateur ne peut pas voir ; les autres
+/// actions se vérifient avec `require`.
+#[derive(Debug, Clone)]
+pub struct ProjectAccess {
+    pub context: AuthContext,
+    pub project_id: Uuid,
+    pub permissions: ProjectPermissions,
+}
+
+impl ProjectAccess {
+    /// Refuse l'action si les permissions effectives ne l'autorisent pas
+    pub fn require(&self, permission: ProjectPermission) -> AppResult<()> {
+        if self.permissions.allows(permission) {
+            Ok(())
+        } else {
+            Err(AppError::Forbidden(format!("Missing project permission: {:?}", permission)))
+        }
+    }
+
+    /// Vrai pour le propriétaire et pour un admin global, seuls à gérer les admins du projet
+    pub fn has_owner_rights(&self) -> bool {
+        self.permissions.role == Some(ProjectRole::Owner) || self.context.role == UserRole::Admin
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for ProjectAccess {
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let context = AuthContext::from_request_parts(parts, state).await?;
+
+        let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+        let project_id = params
+            .get("id")
+            .and_then(|id| id.parse::<Uuid>().ok())
+            .ok_or_else(|| AppError::BadRequest("Invalid project id".to_string()))?;
+
+        let permissions = permission::effective_permissions(state.pool()?, &context, project_id).await?;
+
+        Ok(ProjectAccess {
+            context,
+            project_id,
+            permissions,
+        })
+    }
+}
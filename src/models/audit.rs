@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecurityEventType {
+    LoginSuccess,
+    LoginFailure,
+    Logout,
+    PasswordChange,
+    RoleChange,
+    PermissionChange,
+    AccountLocked,
+    AccountUnlocked,
+    IpBlocked,
+    IpUnblocked,
+    SuspiciousActivity,
+    DataBreachAttempt,
+    UnauthorizedAccess,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SecuritySeverity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl std::fmt::Display for SecurityEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecurityEventType::LoginSuccess => write!(f, "login_success"),
+            SecurityEventType::LoginFailure => write!(f, "login_failure"),
+            SecurityEventType::Logout => write!(f, "logout"),
+            SecurityEventType::PasswordChange => write!(f, "password_change"),
+            SecurityEventType::RoleChange => write!(f, "role_change"),
+            SecurityEventType::PermissionChange => write!(f, "permission_change"),
+            SecurityEventType::AccountLocked => write!(f, "account_locked"),
+            SecurityEventType::AccountUnlocked => write!(f, "account_unlocked"),
+            SecurityEventType::IpBlocked => write!(f, "ip_blocked"),
+            SecurityEventType::IpUnblocked => write!(f, "ip_unblocked"),
+            SecurityEventType::SuspiciousActivity => write!(f, "suspicious_activity"),
+            SecurityEventType::DataBreachAttempt => write!(f, "data_breach_attempt"),
+            SecurityEventType::UnauthorizedAccess => write!(f, "unauthorized_access"),
+        }
+    }
+}
+
+impl std::fmt::Display for SecuritySeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecuritySeverity::Low => write!(f, "low"),
+            SecuritySeverity::Medium => write!(f, "medium"),
+            SecuritySeverity::High => write!(f, "high"),
+            SecuritySeverity::Critical => write!(f, "critical"),
+        }
+    }
+}
@@ -4,6 +4,8 @@ pub mod task;
 pub mod note;
 pub mod snippet;
 pub mod common;
+pub mod audit;
+pub mod token;
 
 pub use user::*;
 pub use project::*;
@@ -11,3 +13,5 @@ pub use task::*;
 pub use note::*;
 pub use snippet::*;
 pub use common::*;
+pub use audit::*;
+pub use token::*;
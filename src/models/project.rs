@@ -2,15 +2,18 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use validator::Validate;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Statuts acceptés par la contrainte CHECK de `projects.status` ; la suppression est définitive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProjectStatus {
     Active,
+    Paused,
+    Completed,
     Archived,
-    Deleted,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProjectVisibility {
     Private,
     Public,
@@ -31,36 +34,149 @@ pub struct Project {
     pub technologies: Option<serde_json::Value>,
     pub repository_url: Option<String>,
     pub live_url: Option<String>,
+    pub is_template: Option<bool>,
+    pub template_category: Option<String>,
     pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct CreateProjectRequest {
+    #[validate(length(min = 1, max = 255))]
     pub name: String,
+    #[validate(length(max = 5000))]
     pub description: Option<String>,
+    #[validate(custom(function = "crate::utils::validation::validate_hex_color"))]
     pub color: Option<String>,
+    #[validate(length(min = 1, max = 50))]
     pub icon: Option<String>,
     pub visibility: ProjectVisibility,
+    #[validate(custom(function = "crate::utils::validation::validate_technologies"))]
     pub technologies: Option<Vec<String>>,
+    #[validate(
+        length(max = 255),
+        custom(function = "crate::utils::validation::validate_optional_url")
+    )]
     pub repository_url: Option<String>,
+    #[validate(
+        length(max = 255),
+        custom(function = "crate::utils::validation::validate_optional_url")
+    )]
     pub live_url: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Mise à jour partielle d'un projet
+///
+/// `version` est celle lue par le client : si le projet a changé depuis, la
+/// mise à jour est refusée (409) au lieu d'écraser les modifications d'un autre.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct UpdateProjectRequest {
+    pub version: i32,
+    #[validate(length(min = 1, max = 255))]
     pub name: Option<String>,
+    #[validate(length(max = 5000))]
     pub description: Option<String>,
+    #[validate(custom(function = "crate::utils::validation::validate_hex_color"))]
     pub color: Option<String>,
+    #[validate(length(min = 1, max = 50))]
     pub icon: Option<String>,
+    pub status: Option<ProjectStatus>,
     pub visibility: Option<ProjectVisibility>,
+    #[validate(custom(function = "crate::utils::validation::validate_technologies"))]
     pub technologies: Option<Vec<String>>,
+    #[validate(
+        length(max = 255),
+        custom(function = "crate::utils::validation::validate_optional_url")
+    )]
     pub repository_url: Option<String>,
+    #[validate(
+        length(max = 255),
+        custom(function = "crate::utils::validation::validate_optional_url")
+    )]
     pub live_url: Option<String>,
     pub settings: Option<serde_json::Value>,
 }
 
+/// Filtres de la liste des projets, combinés à `PaginationParams`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectFilter {
+    pub status: Option<ProjectStatus>,
+}
+
+/// Filtres de la liste des modèles, combinés à `PaginationParams`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TemplateFilter {
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct TemplateCategory {
+    pub category: String,
+    pub template_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PublishTemplateRequest {
+    #[validate(length(min = 1, max = 50))]
+    pub category: String,
+}
+
+/// Nouveau projet créé à partir d'un modèle ; le nom du modèle est repris par défaut
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct CreateFromTemplateRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    pub visibility: Option<ProjectVisibility>,
+}
+
+/// Contenu recopié d'un projet vers un autre ; tout est copié par défaut
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectCopyOptions {
+    pub notes: bool,
+    pub snippets: bool,
+    /// Tâches et leur checklist
+    pub tasks: bool,
+    pub settings: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct DuplicateProjectRequest {
+    /// Par défaut, le nom du projet suivi de « (copy) »
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    pub visibility: Option<ProjectVisibility>,
+    #[serde(default)]
+    pub include: ProjectCopyOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateProjectResponse {
+    pub project: ProjectResponse,
+    pub copied: ProjectCopySummary,
+}
+
+/// Nombre d'éléments recopiés
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectCopySummary {
+    pub notes: u64,
+    pub snippets: u64,
+    pub tasks: u64,
+    pub checklist_items: u64,
+}
+
+impl Default for ProjectCopyOptions {
+    fn default() -> Self {
+        Self {
+            notes: true,
+            snippets: true,
+            tasks: true,
+            settings: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectResponse {
     pub id: Uuid,
@@ -75,6 +191,8 @@ pub struct ProjectResponse {
     pub technologies: Option<Vec<String>>,
     pub repository_url: Option<String>,
     pub live_url: Option<String>,
+    pub is_template: bool,
+    pub template_category: Option<String>,
     pub version: i32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -82,36 +200,175 @@ pub struct ProjectResponse {
     pub note_count: Option<i64>,
 }
 
+/// Rôle d'un membre, tel que stocké dans `project_permissions.role`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProjectRole {
+    Owner,
+    Admin,
+    Editor,
+    Viewer,
+}
+
+/// État d'une invitation ; `Expired` inclut les invitations en attente dont le délai est passé
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InvitationStatus {
+    Pending,
+    Accepted,
+    Declined,
+    Revoked,
+    Expired,
+}
+
+/// Membre ayant accepté son invitation, avec son profil
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct ProjectMember {
     pub id: Uuid,
     pub project_id: Uuid,
     pub user_id: Uuid,
-    pub role: String,
-    pub permissions: Option<serde_json::Value>,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub role: String, // Will be converted to ProjectRole
     pub invited_by: Option<Uuid>,
     pub invited_at: Option<DateTime<Utc>>,
     pub joined_at: Option<DateTime<Utc>>,
-    pub is_active: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct ProjectInvitation {
     pub id: Uuid,
     pub project_id: Uuid,
+    pub project_name: String,
     pub email: String,
-    pub role: String,
-    pub invited_by: Uuid,
+    pub role: String,   // Will be converted to ProjectRole
+    pub status: String, // Will be converted to InvitationStatus
+    pub invited_by: Option<Uuid>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub responded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectMemberResponse {
+    pub user_id: Uuid,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub avatar_url: Option<String>,
+    pub role: ProjectRole,
+    pub invited_by: Option<Uuid>,
+    pub joined_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectInvitationResponse {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub email: String,
+    pub role: ProjectRole,
+    pub status: InvitationStatus,
+    pub invited_by: Option<Uuid>,
+    /// `None` tant que l'adresse invitée n'a pas de compte
+    pub expires_at: Option<DateTime<Utc>>,
+    pub responded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Action soumise à autorisation sur un projet
+///
+/// Les droits sur les notes, snippets et tâches figurent dans `ProjectPermissions`
+/// mais n'ont pas encore de routes à protéger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectPermission {
+    View,
+    EditProject,
+    DeleteProject,
+    ManageMembers,
+}
+
+/// Permissions effectives d'un utilisateur sur un projet
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectPermissions {
+    /// Rôle de membre ; `None` pour un non-membre
+    pub role: Option<ProjectRole>,
+    pub can_view: bool,
+    pub can_edit_project: bool,
+    pub can_delete_project: bool,
+    pub can_manage_members: bool,
+    pub can_create_notes: bool,
+    pub can_edit_notes: bool,
+    pub can_delete_notes: bool,
+    pub can_create_snippets: bool,
+    pub can_edit_snippets: bool,
+    pub can_delete_snippets: bool,
+    pub can_create_tasks: bool,
+    pub can_edit_tasks: bool,
+    pub can_delete_tasks: bool,
+}
+
+/// État d'un transfert de propriété ; `Expired` inclut les transferts en attente dont le délai est passé
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransferStatus {
+    Pending,
+    Accepted,
+    Declined,
+    Cancelled,
+    Expired,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ProjectTransfer {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub from_user_id: Uuid,
+    pub to_user_id: Uuid,
+    pub requested_by: Option<Uuid>,
+    pub status: String, // Will be converted to TransferStatus
     pub expires_at: DateTime<Utc>,
+    pub responded_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProjectTransferResponse {
+    pub id: Uuid,
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub from_user_id: Uuid,
+    pub to_user_id: Uuid,
+    pub requested_by: Option<Uuid>,
+    pub status: TransferStatus,
+    pub expires_at: DateTime<Utc>,
+    pub responded_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Propose la propriété du projet à un membre, qui doit l'accepter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferOwnershipRequest {
+    pub to_user_id: Uuid,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct InviteMemberRequest {
+    #[validate(email, length(max = 255))]
+    pub email: String,
+    /// Tout rôle sauf `Owner`, qui ne s'obtient que par transfert
+    pub role: ProjectRole,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateMemberRoleRequest {
+    pub role: ProjectRole,
+}
+
 impl Project {
     pub fn status(&self) -> ProjectStatus {
         match self.status.as_str() {
-            "active" => ProjectStatus::Active,
+            "paused" => ProjectStatus::Paused,
+            "completed" => ProjectStatus::Completed,
             "archived" => ProjectStatus::Archived,
-            "deleted" => ProjectStatus::Deleted,
             _ => ProjectStatus::Active,
         }
     }
@@ -156,6 +413,8 @@ impl Project {
             technologies: Some(technologies),
             repository_url: self.repository_url,
             live_url: self.live_url,
+            is_template: self.is_template.unwrap_or(false),
+            template_category: self.template_category,
             version: self.version,
             created_at: self.created_at,
             updated_at: self.updated_at,
@@ -165,12 +424,125 @@ impl Project {
     }
 }
 
+impl ProjectMember {
+    pub fn into_response(self) -> ProjectMemberResponse {
+        ProjectMemberResponse {
+            user_id: self.user_id,
+            username: self.username,
+            display_name: self.display_name,
+            avatar_url: self.avatar_url,
+            role: self.role.parse().unwrap_or(ProjectRole::Viewer),
+            invited_by: self.invited_by,
+            joined_at: self.joined_at,
+        }
+    }
+}
+
+impl ProjectInvitation {
+    pub fn status(&self) -> InvitationStatus {
+        match self.status.as_str() {
+            "pending" if self.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) => {
+                InvitationStatus::Expired
+            }
+            "accepted" => InvitationStatus::Accepted,
+            "declined" => InvitationStatus::Declined,
+            "revoked" => InvitationStatus::Revoked,
+            "expired" => InvitationStatus::Expired,
+            _ => InvitationStatus::Pending,
+        }
+    }
+
+    pub fn into_response(self) -> ProjectInvitationResponse {
+        let status = self.status();
+
+        ProjectInvitationResponse {
+            id: self.id,
+            project_id: self.project_id,
+            project_name: self.project_name,
+            email: self.email,
+            role: self.role.parse().unwrap_or(ProjectRole::Viewer),
+            status,
+            invited_by: self.invited_by,
+            expires_at: self.expires_at,
+            responded_at: self.responded_at,
+            created_at: self.created_at,
+        }
+    }
+}
+
+impl ProjectPermissions {
+    pub fn allows(&self, permission: ProjectPermission) -> bool {
+        match permission {
+            ProjectPermission::View => self.can_view,
+            ProjectPermission::EditProject => self.can_edit_project,
+            ProjectPermission::DeleteProject => self.can_delete_project,
+            ProjectPermission::ManageMembers => self.can_manage_members,
+        }
+    }
+}
+
+impl ProjectTransfer {
+    pub fn status(&self) -> TransferStatus {
+        match self.status.as_str() {
+            "pending" if self.expires_at <= Utc::now() => TransferStatus::Expired,
+            "accepted" => TransferStatus::Accepted,
+            "declined" => TransferStatus::Declined,
+            "cancelled" => TransferStatus::Cancelled,
+            "expired" => TransferStatus::Expired,
+            _ => TransferStatus::Pending,
+        }
+    }
+
+    pub fn into_response(self) -> ProjectTransferResponse {
+        let status = self.status();
+
+        ProjectTransferResponse {
+            id: self.id,
+            project_id: self.project_id,
+            project_name: self.project_name,
+            from_user_id: self.from_user_id,
+            to_user_id: self.to_user_id,
+            requested_by: self.requested_by,
+            status,
+            expires_at: self.expires_at,
+            responded_at: self.responded_at,
+            created_at: self.created_at,
+        }
+    }
+}
+
+impl std::fmt::Display for ProjectRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectRole::Owner => write!(f, "owner"),
+            ProjectRole::Admin => write!(f, "admin"),
+            ProjectRole::Editor => write!(f, "editor"),
+            ProjectRole::Viewer => write!(f, "viewer"),
+        }
+    }
+}
+
+impl std::str::FromStr for ProjectRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "owner" => Ok(ProjectRole::Owner),
+            "admin" => Ok(ProjectRole::Admin),
+            "editor" => Ok(ProjectRole::Editor),
+            "viewer" => Ok(ProjectRole::Viewer),
+            _ => Err(format!("Unknown project role: {}", s)),
+        }
+    }
+}
+
 impl std::fmt::Display for ProjectStatus {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ProjectStatus::Active => write!(f, "active"),
+            ProjectStatus::Paused => write!(f, "paused"),
+            ProjectStatus::Completed => write!(f, "completed"),
             ProjectStatus::Archived => write!(f, "archived"),
-            ProjectStatus::Deleted => write!(f, "deleted"),
         }
     }
 }
@@ -184,3 +556,91 @@ impl std::fmt::Display for ProjectVisibility {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(status: &str) -> Project {
+        Project {
+            id: Uuid::new_v4(),
+            name: "ETTU".to_string(),
+            description: None,
+            color: "#3b82f6".to_string(),
+            icon: None,
+            status: status.to_string(),
+            visibility: "team".to_string(),
+            owner_id: Uuid::new_v4(),
+            settings: None,
+            technologies: Some(serde_json::json!(["rust", 42, "axum"])),
+            repository_url: None,
+            live_url: None,
+            is_template: None,
+            template_category: None,
+            version: 3,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_status_matches_schema() {
+        for status in [
+            ProjectStatus::Active,
+            ProjectStatus::Paused,
+            ProjectStatus::Completed,
+            ProjectStatus::Archived,
+        ] {
+            assert_eq!(project(&status.to_string()).status(), status);
+        }
+    }
+
+    #[test]
+    fn test_into_response() {
+        let response = project("paused").into_response();
+        assert_eq!(response.status, ProjectStatus::Paused);
+        assert_eq!(response.visibility, ProjectVisibility::Team);
+        assert_eq!(response.technologies, Some(vec!["rust".to_string(), "axum".to_string()]));
+        assert_eq!(response.version, 3);
+    }
+
+    #[test]
+    fn test_copy_options_default_to_everything() {
+        let options: ProjectCopyOptions = serde_json::from_str(r#"{ "tasks": false }"#).unwrap();
+
+        assert!(options.notes && options.snippets && options.settings);
+        assert!(!options.tasks);
+    }
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in [ProjectRole::Owner, ProjectRole::Admin, ProjectRole::Editor, ProjectRole::Viewer] {
+            assert_eq!(role.to_string().parse::<ProjectRole>(), Ok(role));
+        }
+        assert!("member".parse::<ProjectRole>().is_err());
+    }
+
+    #[test]
+    fn test_invitation_status_expiry() {
+        let mut invitation = ProjectInvitation {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            project_name: "ETTU".to_string(),
+            email: "bob@example.com".to_string(),
+            role: "editor".to_string(),
+            status: "pending".to_string(),
+            invited_by: None,
+            expires_at: None,
+            responded_at: None,
+            created_at: Utc::now(),
+        };
+        // Adresse sans compte : l'invitation est conservée sans échéance
+        assert_eq!(invitation.status(), InvitationStatus::Pending);
+
+        invitation.expires_at = Some(Utc::now() - chrono::Duration::minutes(1));
+        assert_eq!(invitation.status(), InvitationStatus::Expired);
+
+        invitation.status = "accepted".to_string();
+        assert_eq!(invitation.status(), InvitationStatus::Accepted);
+    }
+}
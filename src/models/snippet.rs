@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use validator::Validate;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Snippet {
@@ -33,3 +34,35 @@ pub struct UpdateSnippetRequest {
     pub language: Option<String>,
     pub is_public: Option<bool>,
 }
+
+/// Snippet partagé publiquement, en attente jusqu'à sa modération
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PublicSnippet {
+    pub id: Uuid,
+    pub title: String,
+    pub description: Option<String>,
+    pub code: String,
+    pub language: String,
+    pub tags: Option<serde_json::Value>,
+    pub author_id: Option<Uuid>,
+    pub category: Option<String>,
+    pub moderation_status: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct PublishSnippetRequest {
+    #[validate(length(min = 1, max = 255))]
+    pub title: String,
+    #[validate(length(max = 5000))]
+    pub description: Option<String>,
+    #[validate(length(min = 1, max = 100000))]
+    pub code: String,
+    #[validate(length(min = 1, max = 50))]
+    pub language: String,
+    #[validate(length(max = 20))]
+    pub tags: Option<Vec<String>>,
+    #[validate(length(min = 1, max = 50))]
+    pub category: Option<String>,
+}
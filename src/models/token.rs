@@ -0,0 +1,173 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+use validator::Validate;
+
+/// Permission accordée à un token personnel
+///
+/// Un scope `:write` inclut le scope `:read` de la même ressource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TokenScope {
+    #[serde(rename = "user:read")]
+    UserRead,
+    #[serde(rename = "user:write")]
+    UserWrite,
+    #[serde(rename = "projects:read")]
+    ProjectsRead,
+    #[serde(rename = "projects:write")]
+    ProjectsWrite,
+    #[serde(rename = "tasks:read")]
+    TasksRead,
+    #[serde(rename = "tasks:write")]
+    TasksWrite,
+    #[serde(rename = "notes:read")]
+    NotesRead,
+    #[serde(rename = "notes:write")]
+    NotesWrite,
+    #[serde(rename = "snippets:read")]
+    SnippetsRead,
+    #[serde(rename = "snippets:write")]
+    SnippetsWrite,
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PersonalAccessToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub token_hash: String,
+    /// Début du token en clair, pour le reconnaître dans la liste
+    pub token_prefix: String,
+    pub scopes: Vec<String>, // Will be converted to TokenScope
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonalAccessTokenResponse {
+    pub id: Uuid,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<TokenScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct CreatePersonalAccessTokenRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(min = 1))]
+    pub scopes: Vec<TokenScope>,
+    /// Sans date d'expiration, le token reste valide jusqu'à sa révocation
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreatedPersonalAccessTokenResponse {
+    /// Token en clair, affiché une seule fois
+    pub token: String,
+    #[serde(flatten)]
+    pub details: PersonalAccessTokenResponse,
+}
+
+impl TokenScope {
+    pub const ALL: [TokenScope; 10] = [
+        TokenScope::UserRead,
+        TokenScope::UserWrite,
+        TokenScope::ProjectsRead,
+        TokenScope::ProjectsWrite,
+        TokenScope::TasksRead,
+        TokenScope::TasksWrite,
+        TokenScope::NotesRead,
+        TokenScope::NotesWrite,
+        TokenScope::SnippetsRead,
+        TokenScope::SnippetsWrite,
+    ];
+
+    /// Indique si ce scope suffit pour accéder à `required`
+    pub fn includes(&self, required: TokenScope) -> bool {
+        *self == required || (self.is_write() && self.resource() == required.resource())
+    }
+
+    fn is_write(&self) -> bool {
+        self.to_string().ends_with(":write")
+    }
+
+    fn resource(&self) -> String {
+        self.to_string()
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
+impl PersonalAccessToken {
+    pub fn into_response(self) -> PersonalAccessTokenResponse {
+        PersonalAccessTokenResponse {
+            id: self.id,
+            name: self.name,
+            token_prefix: self.token_prefix,
+            // Un scope retiré du code ne fait qu'être ignoré
+            scopes: self.scopes.iter().filter_map(|s| s.parse().ok()).collect(),
+            expires_at: self.expires_at,
+            last_used_at: self.last_used_at,
+            created_at: self.created_at,
+        }
+    }
+}
+
+impl std::fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenScope::UserRead => write!(f, "user:read"),
+            TokenScope::UserWrite => write!(f, "user:write"),
+            TokenScope::ProjectsRead => write!(f, "projects:read"),
+            TokenScope::ProjectsWrite => write!(f, "projects:write"),
+            TokenScope::TasksRead => write!(f, "tasks:read"),
+            TokenScope::TasksWrite => write!(f, "tasks:write"),
+            TokenScope::NotesRead => write!(f, "notes:read"),
+            TokenScope::NotesWrite => write!(f, "notes:write"),
+            TokenScope::SnippetsRead => write!(f, "snippets:read"),
+            TokenScope::SnippetsWrite => write!(f, "snippets:write"),
+        }
+    }
+}
+
+impl std::str::FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TokenScope::ALL
+            .into_iter()
+            .find(|scope| scope.to_string() == s)
+            .ok_or_else(|| format!("Unknown token scope: {}", s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_scope_includes_read() {
+        assert!(TokenScope::ProjectsWrite.includes(TokenScope::ProjectsRead));
+        assert!(TokenScope::ProjectsRead.includes(TokenScope::ProjectsRead));
+        assert!(!TokenScope::ProjectsRead.includes(TokenScope::ProjectsWrite));
+        assert!(!TokenScope::TasksWrite.includes(TokenScope::ProjectsRead));
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for scope in TokenScope::ALL {
+            assert_eq!(scope.to_string().parse::<TokenScope>().unwrap(), scope);
+            assert_eq!(serde_json::to_value(scope).unwrap(), scope.to_string());
+        }
+        assert!("admin:write".parse::<TokenScope>().is_err());
+    }
+}
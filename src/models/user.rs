@@ -2,6 +2,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
+use validator::Validate;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UserType {
@@ -10,7 +11,7 @@ pub enum UserType {
     Migrated,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum UserRole {
     User,
     Reviewer,
@@ -30,7 +31,8 @@ pub struct User {
     pub role: String,      // Will be converted to UserRole
     pub is_active: bool,
     pub is_verified: bool,
-    pub profile_picture: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub avatar_url: Option<String>,
     pub bio: Option<String>,
     pub location: Option<String>,
     pub website: Option<String>,
@@ -38,6 +40,8 @@ pub struct User {
     pub language: Option<String>,
     pub timezone: Option<String>,
     pub settings: Option<serde_json::Value>,
+    pub anonymous_id: Option<Uuid>,
+    pub session_expires_at: Option<DateTime<Utc>>,
     pub last_login: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -52,19 +56,68 @@ pub struct CreateUserRequest {
     pub user_type: UserType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Mise à jour partielle du compte : un champ absent reste inchangé, une chaîne
+/// vide efface un champ facultatif
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
 pub struct UpdateUserRequest {
+    #[validate(email)]
     pub email: Option<String>,
+    #[validate(
+        length(min = 3, max = 50),
+        custom(function = "crate::utils::validation::validate_username")
+    )]
     pub username: Option<String>,
+    #[validate(length(min = 1, max = 100))]
     pub display_name: Option<String>,
-    pub profile_picture: Option<String>,
+    #[validate(custom(function = "crate::utils::validation::validate_optional_url"))]
+    pub avatar_url: Option<String>,
+    #[validate(length(max = 500))]
     pub bio: Option<String>,
+    #[validate(length(max = 100))]
     pub location: Option<String>,
+    #[validate(
+        length(max = 255),
+        custom(function = "crate::utils::validation::validate_optional_url")
+    )]
     pub website: Option<String>,
+    #[validate(custom(function = "crate::utils::validation::validate_theme"))]
     pub theme: Option<String>,
+    #[validate(custom(function = "crate::utils::validation::validate_language"))]
     pub language: Option<String>,
+    #[validate(custom(function = "crate::utils::validation::validate_timezone"))]
     pub timezone: Option<String>,
     pub settings: Option<serde_json::Value>,
+    /// Mot de passe actuel, exigé pour changer d'email
+    pub current_password: Option<String>,
+}
+
+/// Profil public d'un compte
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PublicProfileResponse {
+    pub id: Uuid,
+    pub username: String,
+    pub display_name: String,
+    pub avatar_url: Option<String>,
+    pub bio: Option<String>,
+    pub location: Option<String>,
+    pub website: Option<String>,
+    pub public_snippets_count: i32,
+    pub public_projects_count: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Avatar envoyé, décliné en plusieurs tailles carrées
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvatarResponse {
+    /// URL de la plus grande taille, enregistrée dans `avatar_url`
+    pub avatar_url: String,
+    pub sizes: Vec<AvatarVariant>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AvatarVariant {
+    pub size: u32,
+    pub url: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -77,30 +130,58 @@ pub struct UserResponse {
     pub role: UserRole,
     pub is_active: bool,
     pub is_verified: bool,
-    pub profile_picture: Option<String>,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub avatar_url: Option<String>,
     pub bio: Option<String>,
     pub location: Option<String>,
     pub website: Option<String>,
     pub theme: Option<String>,
     pub language: Option<String>,
     pub timezone: Option<String>,
+    pub anonymous_id: Option<Uuid>,
+    pub session_expires_at: Option<DateTime<Utc>>,
     pub last_login: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SessionType {
+    Guest,
+    Authenticated,
+}
+
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct UserSession {
     pub id: Uuid,
     pub user_id: Uuid,
-    pub token: String,
-    pub refresh_token: String,
+    pub session_type: String, // Will be converted to SessionType
+    pub device_info: Option<serde_json::Value>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
     pub expires_at: DateTime<Utc>,
+    pub is_persistent: bool,
+    pub revoked_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
-    pub last_used: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionResponse {
+    pub id: Uuid,
+    pub session_type: SessionType,
+    pub device_info: Option<serde_json::Value>,
     pub ip_address: Option<String>,
     pub user_agent: Option<String>,
-    pub is_active: bool,
+    pub is_current: bool,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevokedSessionsResponse {
+    pub revoked: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,23 +199,273 @@ pub struct LoginResponse {
     pub expires_at: DateTime<Utc>,
 }
 
+/// Résultat d'une connexion par mot de passe
+///
+/// Un compte protégé par la double authentification reçoit d'abord un challenge,
+/// échangé contre une session sur `/auth/2fa/verify`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LoginOutcome {
+    Authenticated(Box<LoginResponse>),
+    MfaRequired(MfaChallenge),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MfaChallenge {
+    pub challenge_token: String,
+    pub expires_at: DateTime<Utc>,
+    /// Le rôle du compte impose la 2FA mais elle n'est pas encore configurée
+    pub enrollment_required: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TwoFactorEnrollRequest {
+    /// Challenge de connexion, pour un compte devant s'enrôler avant d'ouvrir une session
+    pub challenge_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorEnrollResponse {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorConfirmRequest {
+    pub code: String,
+    pub challenge_token: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorConfirmResponse {
+    /// Codes de secours, affichés une seule fois
+    pub recovery_codes: Vec<String>,
+    /// Session ouverte lorsque l'enrôlement termine une connexion
+    pub session: Option<LoginResponse>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorVerifyRequest {
+    pub challenge_token: String,
+    /// Code TOTP ou code de secours
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TwoFactorDisableRequest {
+    pub password: String,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthAuthorizeResponse {
+    /// Page de consentement du fournisseur vers laquelle rediriger le navigateur
+    pub authorization_url: String,
+    pub state: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OAuthCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestSessionRequest {
+    /// UUID généré et conservé par le client
+    pub anonymous_id: Uuid,
+    /// Conserver les données de l'invité au-delà de l'expiration de sa session
+    #[serde(default)]
+    pub persistent: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestSessionResponse {
+    pub user: UserResponse,
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyEmailRequest {
+    pub token: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgotPasswordRequest {
+    pub email: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    pub token: String,
+    #[validate(
+        length(max = 128),
+        custom(function = "crate::utils::password::validate_password_strength")
+    )]
+    pub new_password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct RegisterRequest {
+    #[validate(email)]
     pub email: String,
+    #[validate(
+        length(min = 3, max = 50),
+        custom(function = "crate::utils::validation::validate_username")
+    )]
     pub username: String,
+    #[validate(length(min = 1, max = 100))]
     pub display_name: String,
+    #[validate(
+        length(max = 128),
+        custom(function = "crate::utils::password::validate_password_strength")
+    )]
     pub password: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct GuestToUserMigrationRequest {
     pub guest_id: Uuid,
+    #[validate(email)]
     pub email: String,
+    #[validate(
+        length(min = 3, max = 50),
+        custom(function = "crate::utils::validation::validate_username")
+    )]
     pub username: String,
+    #[validate(length(min = 1, max = 100))]
     pub display_name: String,
+    #[validate(
+        length(max = 128),
+        custom(function = "crate::utils::password::validate_password_strength")
+    )]
     pub password: String,
 }
 
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct UserMigration {
+    pub id: Uuid,
+    pub source_anonymous_id: Uuid,
+    pub destination_user_id: Option<Uuid>,
+    pub migration_status: String,
+    pub entities_migrated: Option<serde_json::Value>,
+    pub migration_log: Option<serde_json::Value>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Export RGPD des données d'un utilisateur
+#[derive(Debug, Clone, FromRow)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub status: String,
+    pub storage_key: Option<String>,
+    pub file_size: Option<i64>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub error_message: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataExportResponse {
+    pub id: Uuid,
+    pub status: String,
+    pub file_size: Option<i64>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub error_message: Option<String>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Lien de téléchargement d'un export, valable jusqu'au suivant ou à l'expiration de l'archive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataExportLinkResponse {
+    pub download_url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl DataExport {
+    pub fn into_response(self) -> DataExportResponse {
+        DataExportResponse {
+            id: self.id,
+            status: self.status,
+            file_size: self.file_size,
+            expires_at: self.expires_at,
+            error_message: self.error_message,
+            completed_at: self.completed_at,
+            created_at: self.created_at,
+        }
+    }
+}
+
+/// Paramètres du lien de téléchargement d'un export
+#[derive(Debug, Clone, Deserialize)]
+pub struct DataExportDownloadQuery {
+    pub token: String,
+}
+
+/// Sort des snippets publics d'un compte supprimé
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublicSnippetPolicy {
+    /// Conservés sans auteur
+    #[default]
+    Anonymize,
+    Delete,
+}
+
+impl PublicSnippetPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PublicSnippetPolicy::Anonymize => "anonymize",
+            PublicSnippetPolicy::Delete => "delete",
+        }
+    }
+}
+
+impl std::str::FromStr for PublicSnippetPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "anonymize" => Ok(PublicSnippetPolicy::Anonymize),
+            "delete" => Ok(PublicSnippetPolicy::Delete),
+            _ => Err(format!("Unknown public snippet policy: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDeletionRequest {
+    /// Obligatoire pour un compte disposant d'un mot de passe
+    pub password: Option<String>,
+    #[serde(default)]
+    pub public_snippets: PublicSnippetPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDeletionResponse {
+    pub requested_at: DateTime<Utc>,
+    /// Date à partir de laquelle le compte est supprimé définitivement
+    pub scheduled_at: DateTime<Utc>,
+    pub public_snippets: PublicSnippetPolicy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuestMigrationResponse {
+    pub migration: UserMigration,
+    pub session: LoginResponse,
+}
+
 impl User {
     pub fn user_type(&self) -> UserType {
         match self.user_type.as_str() {
@@ -181,13 +512,16 @@ impl User {
             role,
             is_active: self.is_active,
             is_verified: self.is_verified,
-            profile_picture: self.profile_picture,
+            email_verified_at: self.email_verified_at,
+            avatar_url: self.avatar_url,
             bio: self.bio,
             location: self.location,
             website: self.website,
             theme: self.theme,
             language: self.language,
             timezone: self.timezone,
+            anonymous_id: self.anonymous_id,
+            session_expires_at: self.session_expires_at,
             last_login: self.last_login,
             created_at: self.created_at,
             updated_at: self.updated_at,
@@ -195,6 +529,39 @@ impl User {
     }
 }
 
+impl UserRole {
+    fn rank(&self) -> u8 {
+        match self {
+            UserRole::Restricted => 0,
+            UserRole::User => 1,
+            UserRole::Reviewer => 2,
+            UserRole::Moderator => 3,
+            UserRole::Admin => 4,
+        }
+    }
+    
+    /// Vrai si ce rôle donne au moins les droits de `required`
+    pub fn includes(&self, required: UserRole) -> bool {
+        self.rank() >= required.rank()
+    }
+}
+
+impl UserSession {
+    pub fn into_response(self, current_session_id: Uuid) -> SessionResponse {
+        SessionResponse {
+            is_current: self.id == current_session_id,
+            id: self.id,
+            session_type: self.session_type.parse().unwrap_or(SessionType::Guest),
+            device_info: self.device_info,
+            ip_address: self.ip_address,
+            user_agent: self.user_agent,
+            created_at: self.created_at,
+            last_used_at: self.last_used_at,
+            expires_at: self.expires_at,
+        }
+    }
+}
+
 impl std::fmt::Display for UserType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -216,3 +583,101 @@ impl std::fmt::Display for UserRole {
         }
     }
 }
+
+impl std::fmt::Display for SessionType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SessionType::Guest => write!(f, "guest"),
+            SessionType::Authenticated => write!(f, "authenticated"),
+        }
+    }
+}
+
+impl std::str::FromStr for UserType {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "guest" => Ok(UserType::Guest),
+            "registered" => Ok(UserType::Registered),
+            "migrated" => Ok(UserType::Migrated),
+            _ => Err(format!("Unknown user type: {}", s)),
+        }
+    }
+}
+
+impl std::str::FromStr for UserRole {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "user" => Ok(UserRole::User),
+            "reviewer" => Ok(UserRole::Reviewer),
+            "moderator" => Ok(UserRole::Moderator),
+            "admin" => Ok(UserRole::Admin),
+            "restricted" => Ok(UserRole::Restricted),
+            _ => Err(format!("Unknown role: {}", s)),
+        }
+    }
+}
+
+impl std::str::FromStr for SessionType {
+    type Err = String;
+    
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "guest" => Ok(SessionType::Guest),
+            "authenticated" => Ok(SessionType::Authenticated),
+            _ => Err(format!("Unknown session type: {}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_hierarchy() {
+        assert!(UserRole::Admin.includes(UserRole::Moderator));
+        assert!(UserRole::Moderator.includes(UserRole::Moderator));
+        assert!(!UserRole::Reviewer.includes(UserRole::Moderator));
+        assert!(!UserRole::Restricted.includes(UserRole::User));
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for role in [UserRole::User, UserRole::Reviewer, UserRole::Moderator, UserRole::Admin, UserRole::Restricted] {
+            assert_eq!(role.to_string().parse::<UserRole>().unwrap(), role);
+        }
+        assert!("superuser".parse::<UserRole>().is_err());
+    }
+
+    #[test]
+    fn test_update_request_validation() {
+        assert!(UpdateUserRequest::default().validate().is_ok());
+
+        let request = UpdateUserRequest {
+            theme: Some("blue".to_string()),
+            ..Default::default()
+        };
+        assert!(request.validate().is_err());
+
+        let request = UpdateUserRequest {
+            website: Some(String::new()),
+            timezone: Some("Europe/Paris".to_string()),
+            ..Default::default()
+        };
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn test_account_deletion_request_defaults_to_anonymize() {
+        let request: AccountDeletionRequest = serde_json::from_str(r#"{"password":"secret"}"#).unwrap();
+        assert_eq!(request.public_snippets, PublicSnippetPolicy::Anonymize);
+
+        let request: AccountDeletionRequest = serde_json::from_str(r#"{"public_snippets":"delete"}"#).unwrap();
+        assert_eq!(request.public_snippets, PublicSnippetPolicy::Delete);
+        assert_eq!("delete".parse::<PublicSnippetPolicy>().unwrap(), PublicSnippetPolicy::Delete);
+    }
+}
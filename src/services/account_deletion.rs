@@ -0,0 +1,366 @@
+use chrono::{Duration, Utc};
+use metrics::counter;
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::models::{AccountDeletionRequest, AccountDeletionResponse, PublicSnippetPolicy};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::user::{self, NULLABLE_REFERENCES};
+use crate::services::{avatar, project};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::password::PasswordHasher;
+use crate::utils::storage::Storage;
+
+/// Contenu de l'utilisateur dans les projets qui lui survivent, conservé sans auteur
+const SURVIVING_CONTENT: &[(&str, &str)] = &[
+    (
+        "notes",
+        "UPDATE project_notes SET author_id = NULL WHERE author_id = $1 AND project_id NOT IN (SELECT id FROM projects WHERE owner_id = $1)",
+    ),
+    (
+        "snippets",
+        "UPDATE project_snippets SET author_id = NULL WHERE author_id = $1 AND project_id NOT IN (SELECT id FROM projects WHERE owner_id = $1)",
+    ),
+    (
+        "tasks",
+        "UPDATE tasks SET author_id = NULL WHERE author_id = $1 AND project_id NOT IN (SELECT id FROM projects WHERE owner_id = $1)",
+    ),
+];
+
+/// Programme la suppression du compte courant à l'issue du délai de grâce
+pub async fn request_deletion(
+    pool: &PgPool,
+    config: &Config,
+    context: &AuthContext,
+    request: AccountDeletionRequest,
+) -> AppResult<AccountDeletionResponse> {
+    let user = user::find_by_id(pool, context.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    // Un compte créé via OAuth n'a pas de mot de passe : la session suffit
+    if let Some(password_hash) = user.password_hash.clone() {
+        let password = request
+            .password
+            .ok_or_else(|| AppError::Unauthorized("Password is required".to_string()))?;
+        if !PasswordHasher::from_config(config)
+            .verify(password, password_hash)
+            .await?
+        {
+            return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+        }
+    }
+
+    let requested_at = Utc::now();
+    let scheduled_at = requested_at + Duration::seconds(config.auth.deletion_grace_period);
+
+    let mut tx = pool.begin().await?;
+
+    let scheduled = sqlx::query(
+        r#"
+        UPDATE users
+        SET deletion_requested_at = $2, deletion_scheduled_at = $3, deletion_snippet_policy = $4,
+            deletion_attempts = 0, deletion_last_error = NULL
+        WHERE id = $1 AND deletion_scheduled_at IS NULL
+        "#,
+    )
+    .bind(user.id)
+    .bind(requested_at)
+    .bind(scheduled_at)
+    .bind(request.public_snippets.as_str())
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if scheduled == 0 {
+        return Err(AppError::Conflict("Account deletion is already scheduled".to_string()));
+    }
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(user.id),
+            action: "account_deletion_requested",
+            entity_type: "user",
+            entity_id: Some(user.id),
+            details: json!({
+                "scheduled_at": scheduled_at,
+                "public_snippets": request.public_snippets.as_str(),
+            }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, %scheduled_at, "Account deletion scheduled");
+
+    Ok(AccountDeletionResponse {
+        requested_at,
+        scheduled_at,
+        public_snippets: request.public_snippets,
+    })
+}
+
+/// Annule une suppression programmée tant que le délai de grâce court
+pub async fn cancel_deletion(pool: &PgPool, context: &AuthContext) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+
+    let cancelled = sqlx::query(
+        r#"
+        UPDATE users
+        SET deletion_requested_at = NULL, deletion_scheduled_at = NULL, deletion_snippet_policy = NULL,
+            deletion_attempts = 0, deletion_last_error = NULL
+        WHERE id = $1 AND deletion_scheduled_at > NOW()
+        "#,
+    )
+    .bind(context.user_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if cancelled == 0 {
+        return Err(AppError::NotFound("No account deletion is scheduled".to_string()));
+    }
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(context.user_id),
+            action: "account_deletion_cancelled",
+            entity_type: "user",
+            entity_id: Some(context.user_id),
+            details: json!({}),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %context.user_id, "Account deletion cancelled");
+
+    Ok(())
+}
+
+/// Supprime définitivement les comptes dont le délai de grâce est écoulé
+///
+/// Chaque compte est traité dans sa propre transaction. Un échec est enregistré
+/// sur le compte (`deletion_attempts`, `deletion_last_error`) et le compte est
+/// écarté jusqu'à la fin du passage : il ne bloque pas les suivants, et passe
+/// après les comptes qui n'ont jamais échoué aux passages suivants.
+pub async fn purge_due(pool: &PgPool, storage: &dyn Storage) -> AppResult<u64> {
+    let mut deleted = 0;
+    let mut failed: Vec<Uuid> = Vec::new();
+
+    loop {
+        let mut tx = pool.begin().await?;
+
+        let due: Option<(Uuid, Option<String>, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT id, avatar_url, deletion_snippet_policy FROM users
+            WHERE deletion_scheduled_at <= NOW() AND id <> ALL($1)
+            ORDER BY deletion_attempts, deletion_scheduled_at
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+            "#,
+        )
+        .bind(&failed)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((user_id, avatar_url, policy)) = due else {
+            break;
+        };
+        let policy = policy
+            .as_deref()
+            .and_then(|policy| policy.parse().ok())
+            .unwrap_or_default();
+
+        let result: AppResult<Vec<String>> = async move {
+            let export_keys: Vec<String> = sqlx::query_scalar(
+                "SELECT storage_key FROM data_exports WHERE user_id = $1 AND storage_key IS NOT NULL",
+            )
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+
+            delete_account(&mut tx, user_id, policy).await?;
+            tx.commit().await?;
+
+            Ok(export_keys)
+        }
+        .await;
+
+        let export_keys = match result {
+            Ok(export_keys) => export_keys,
+            Err(e) => {
+                warn!(user_id = %user_id, "Account deletion failed: {}", e);
+                counter!("ettu_account_deletion_failures_total").increment(1);
+                record_failure(pool, user_id, &e).await;
+                failed.push(user_id);
+                continue;
+            }
+        };
+        deleted += 1;
+
+        // Les fichiers ne suivent pas la transaction : supprimés une fois le compte effacé
+        if let Some(avatar_url) = avatar_url {
+            avatar::remove_stored(storage, user_id, &avatar_url).await;
+        }
+        for key in export_keys {
+            if let Err(e) = storage.delete(&key).await {
+                warn!(user_id = %user_id, key = %key, "Failed to delete export archive: {}", e);
+            }
+        }
+
+        counter!("ettu_accounts_deleted_total").increment(1);
+        info!(user_id = %user_id, "Account deleted");
+    }
+
+    if !failed.is_empty() {
+        warn!(failed = failed.len(), "Some scheduled account deletions failed");
+    }
+
+    Ok(deleted)
+}
+
+/// Enregistre l'échec de la suppression d'un compte, après le rollback de sa transaction
+async fn record_failure(pool: &PgPool, user_id: Uuid, error: &AppError) {
+    let recorded = sqlx::query(
+        "UPDATE users SET deletion_attempts = deletion_attempts + 1, deletion_last_error = $2 WHERE id = $1",
+    )
+    .bind(user_id)
+    .bind(error.to_string())
+    .execute(pool)
+    .await;
+
+    if let Err(e) = recorded {
+        warn!(user_id = %user_id, "Failed to record account deletion failure: {}", e);
+    }
+}
+
+/// Transfère les projets partagés, traite le contenu public puis efface le compte
+async fn delete_account(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    policy: PublicSnippetPolicy,
+) -> AppResult<()> {
+    // Successeur : l'admin le plus ancien, à défaut le membre le plus ancien ;
+    // une invitation non acceptée ne compte pas
+    let projects: Vec<(Uuid, Option<Uuid>)> = sqlx::query_as(
+        r#"
+        SELECT p.id, (
+            SELECT m.user_id FROM project_permissions m
+            WHERE m.project_id = p.id AND m.user_id <> $1 AND m.accepted_at IS NOT NULL
+            ORDER BY CASE m.role WHEN 'admin' THEN 0 WHEN 'editor' THEN 1 ELSE 2 END,
+                     m.accepted_at, m.created_at
+            LIMIT 1
+        )
+        FROM projects p
+        WHERE p.owner_id = $1
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut projects_transferred = 0;
+    for (project_id, successor_id) in &projects {
+        let Some(successor_id) = successor_id else {
+            continue;
+        };
+
+        project::set_owner(&mut *conn, *project_id, *successor_id).await?;
+        project::refresh_public_count(&mut *conn, *successor_id).await?;
+
+        audit::log_event(
+            &mut *conn,
+            AuditEvent {
+                actor_id: None,
+                action: "project_ownership_transferred",
+                entity_type: "project",
+                entity_id: Some(*project_id),
+                details: json!({ "from": user_id, "to": successor_id, "reason": "account_deletion" }),
+            },
+        )
+        .await?;
+        projects_transferred += 1;
+    }
+
+    let mut anonymized = serde_json::Map::new();
+    for (entity, statement) in SURVIVING_CONTENT {
+        let count = sqlx::query(statement)
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+        anonymized.insert(entity.to_string(), json!(count));
+    }
+    audit::log_event(
+        &mut *conn,
+        AuditEvent {
+            actor_id: None,
+            action: "project_content_anonymized",
+            entity_type: "user",
+            entity_id: Some(user_id),
+            details: anonymized.into(),
+        },
+    )
+    .await?;
+
+    let public_snippets = match policy {
+        PublicSnippetPolicy::Anonymize => {
+            sqlx::query("UPDATE public_snippets SET author_id = NULL WHERE author_id = $1")
+        }
+        PublicSnippetPolicy::Delete => sqlx::query("DELETE FROM public_snippets WHERE author_id = $1"),
+    }
+    .bind(user_id)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+    audit::log_event(
+        &mut *conn,
+        AuditEvent {
+            actor_id: None,
+            action: match policy {
+                PublicSnippetPolicy::Anonymize => "public_snippets_anonymized",
+                PublicSnippetPolicy::Delete => "public_snippets_deleted",
+            },
+            entity_type: "user",
+            entity_id: Some(user_id),
+            details: json!({ "count": public_snippets }),
+        },
+    )
+    .await?;
+
+    for statement in NULLABLE_REFERENCES {
+        sqlx::query(statement)
+            .bind(vec![user_id])
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    // Projets non transférés, sessions, permissions, notifications... suivent par cascade
+    sqlx::query("DELETE FROM users WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    audit::log_event(
+        &mut *conn,
+        AuditEvent {
+            actor_id: None,
+            action: "account_deleted",
+            entity_type: "user",
+            entity_id: Some(user_id),
+            details: json!({
+                "projects_transferred": projects_transferred,
+                "projects_deleted": projects.len() - projects_transferred,
+            }),
+        },
+    )
+    .await?;
+
+    Ok(())
+}
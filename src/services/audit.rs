@@ -0,0 +1,92 @@
+use serde_json::Value;
+use sqlx::PgConnection;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::models::{SecurityEventType, SecuritySeverity};
+use crate::utils::error::AppResult;
+use crate::utils::request::ClientInfo;
+
+/// Adresse enregistrée quand celle du client est inconnue (colonne `ip_address` NOT NULL)
+const UNKNOWN_IP: &str = "0.0.0.0";
+
+/// Événement à consigner dans `security_audit_logs`
+#[derive(Debug, Clone)]
+pub struct SecurityEvent {
+    pub event_type: SecurityEventType,
+    pub severity: SecuritySeverity,
+    pub user_id: Option<Uuid>,
+    pub session_id: Option<Uuid>,
+    pub details: Value,
+}
+
+/// Écrit un événement de sécurité, dans la transaction de l'appelant si besoin
+pub async fn log_security_event(
+    conn: &mut PgConnection,
+    event: SecurityEvent,
+    client: &ClientInfo,
+) -> AppResult<()> {
+    let is_anomaly = matches!(
+        event.event_type,
+        SecurityEventType::SuspiciousActivity | SecurityEventType::DataBreachAttempt
+    );
+
+    if is_anomaly {
+        warn!(
+            event_type = %event.event_type,
+            user_id = ?event.user_id,
+            session_id = ?event.session_id,
+            "Security anomaly detected"
+        );
+    }
+
+    sqlx::query(
+        r#"
+        INSERT INTO security_audit_logs
+            (event_type, user_id, ip_address, user_agent, session_id, severity, details, is_anomaly, requires_investigation)
+        VALUES ($1, $2, $3::inet, $4, $5, $6, $7, $8, $8)
+        "#,
+    )
+    .bind(event.event_type.to_string())
+    .bind(event.user_id)
+    .bind(client.ip_address.as_deref().unwrap_or(UNKNOWN_IP))
+    .bind(client.user_agent.as_deref())
+    .bind(event.session_id)
+    .bind(event.severity.to_string())
+    .bind(event.details)
+    .bind(is_anomaly)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Opération à consigner dans `audit_logs`
+#[derive(Debug, Clone)]
+pub struct AuditEvent<'a> {
+    /// Auteur de l'opération, `None` pour une tâche système
+    pub actor_id: Option<Uuid>,
+    pub action: &'a str,
+    pub entity_type: &'a str,
+    pub entity_id: Option<Uuid>,
+    pub details: Value,
+}
+
+/// Écrit une entrée du journal d'audit, dans la transaction de l'appelant si besoin
+pub async fn log_event(conn: &mut PgConnection, event: AuditEvent<'_>) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_logs (actor_id, action, entity_type, entity_id, details)
+        VALUES ($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(event.actor_id)
+    .bind(event.action)
+    .bind(event.entity_type)
+    .bind(event.entity_id)
+    .bind(event.details)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
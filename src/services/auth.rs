@@ -1 +1,455 @@
-// Auth service - TODO: Implement
+use chrono::{DateTime, Duration, Utc};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use tracing::{info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::models::{
+    GuestSessionRequest, GuestSessionResponse, LoginOutcome, LoginRequest, LoginResponse,
+    RefreshTokenRequest, RegisterRequest, SecurityEventType, SecuritySeverity, SessionType, User,
+};
+use crate::services::audit::{self, SecurityEvent};
+use crate::services::email::EmailService;
+use crate::services::lockout::{LoginAccount, LoginGuard};
+use crate::services::{project_member, two_factor, user, verification};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::jwt;
+use crate::utils::password::PasswordHasher;
+use crate::utils::request::ClientInfo;
+use crate::utils::token::{generate_token, hash_token};
+
+/// Tokens émis lors de l'ouverture d'une session
+#[derive(Debug, Clone)]
+pub struct IssuedSession {
+    pub session_id: Uuid,
+    pub token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Authentifie un utilisateur par email ou nom d'utilisateur
+///
+/// La session n'est ouverte directement que si le compte n'a pas de double
+/// authentification : sinon un challenge est retourné à la place. Un compte ou
+/// une adresse IP verrouillé après trop d'échecs est refusé avant toute vérification.
+pub async fn login(
+    pool: &PgPool,
+    config: &Config,
+    guard: &LoginGuard,
+    request: LoginRequest,
+    client: &ClientInfo,
+) -> AppResult<LoginOutcome> {
+    guard.check(pool, None, client).await?;
+
+    let (identifier, user) = match (request.email.as_deref(), request.username.as_deref()) {
+        (Some(email), _) => (email, user::find_by_email(pool, email).await?),
+        (None, Some(username)) => (username, user::find_by_username(pool, username).await?),
+        (None, None) => {
+            return Err(AppError::BadRequest(
+                "Email or username is required".to_string(),
+            ))
+        }
+    };
+
+    let invalid_credentials = || AppError::Unauthorized("Invalid credentials".to_string());
+    let hasher = PasswordHasher::from_config(config);
+
+    // Sans hash à vérifier, un hash factice est vérifié quand même : le temps de
+    // réponse ne révèle pas si le compte existe, pas plus que le verrou
+    let Some(user) = user else {
+        let account = Some(LoginAccount::Unknown(identifier));
+        guard.check(pool, account, client).await?;
+        hasher.verify_dummy(request.password).await?;
+        guard.record_failure(pool, account, client, "unknown_account").await?;
+        return Err(invalid_credentials());
+    };
+    let account = Some(LoginAccount::Known(user.id));
+    guard.check(pool, account, client).await?;
+
+    let password_hash = match (user.can_login(), user.password_hash.clone()) {
+        (true, Some(hash)) => hash,
+        _ => {
+            hasher.verify_dummy(request.password).await?;
+            guard.record_failure(pool, account, client, "no_password").await?;
+            return Err(invalid_credentials());
+        }
+    };
+
+    let valid = hasher
+        .verify(request.password.clone(), password_hash.clone())
+        .await?;
+
+    if !valid {
+        guard.record_failure(pool, account, client, "invalid_password").await?;
+        return Err(invalid_credentials());
+    }
+
+    if !user.is_active {
+        return Err(AppError::Forbidden("Account is disabled".to_string()));
+    }
+
+    // Rehash transparent lorsque le coût ou la version de bcrypt a changé
+    if hasher.needs_rehash(&password_hash) {
+        let new_hash = hasher.hash(request.password).await?;
+        sqlx::query("UPDATE users SET password_hash = $2 WHERE id = $1")
+            .bind(user.id)
+            .bind(new_hash)
+            .execute(pool)
+            .await?;
+        info!(user_id = %user.id, "Password hash upgraded");
+    }
+
+    // Le second facteur reste à fournir : le compteur d'échecs n'est pas remis à zéro
+    if let Some(challenge) = two_factor::challenge_for(pool, config, &user).await? {
+        info!(user_id = %user.id, "Second factor required");
+        return Ok(LoginOutcome::MfaRequired(challenge));
+    }
+
+    guard.record_success(user.id).await;
+
+    complete_login(pool, config, &user, client)
+        .await
+        .map(|login| LoginOutcome::Authenticated(Box::new(login)))
+}
+
+/// Ouvre une session authentifiée pour un utilisateur dont l'identité est établie
+pub async fn complete_login(
+    pool: &PgPool,
+    config: &Config,
+    user: &User,
+    client: &ClientInfo,
+) -> AppResult<LoginResponse> {
+    let mut tx = pool.begin().await?;
+
+    let session = create_session(&mut tx, config, user, SessionType::Authenticated, client).await?;
+
+    let user = sqlx::query_as::<_, User>(
+        "UPDATE users SET last_login = NOW() WHERE id = $1 RETURNING *",
+    )
+    .bind(user.id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, session_id = %session.session_id, "User logged in");
+
+    Ok(LoginResponse {
+        user: user.into_response(),
+        token: session.token,
+        refresh_token: session.refresh_token.unwrap_or_default(),
+        expires_at: session.expires_at,
+    })
+}
+
+/// Crée un compte `registered` et ouvre une première session
+///
+/// Lorsque la vérification d'email est activée, le compte reste non vérifié
+/// et un lien signé est envoyé à l'adresse fournie.
+pub async fn register(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    request: RegisterRequest,
+    client: &ClientInfo,
+) -> AppResult<LoginResponse> {
+    if !config.features.registration_enabled {
+        return Err(AppError::Forbidden("Registration is currently disabled".to_string()));
+    }
+
+    request.validate()?;
+
+    if user::find_by_email(pool, &request.email).await?.is_some() {
+        return Err(AppError::Conflict("Email is already in use".to_string()));
+    }
+    if user::find_by_username(pool, &request.username).await?.is_some() {
+        return Err(AppError::Conflict("Username is already taken".to_string()));
+    }
+
+    let password_hash = PasswordHasher::from_config(config)
+        .hash(request.password)
+        .await?;
+
+    let mut tx = pool.begin().await?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users
+            (email, username, display_name, password_hash, user_type, is_verified, email_verification_sent_at)
+        VALUES ($1, $2, $3, $4, 'registered', FALSE, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(&request.email)
+    .bind(&request.username)
+    .bind(&request.display_name)
+    .bind(&password_hash)
+    .bind(config.features.email_verification.then(Utc::now))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        // Inscription concurrente avec le même email ou nom d'utilisateur
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Conflict("Email or username is already in use".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    project_member::start_held_invitations(&mut tx, config, &request.email).await?;
+
+    let session = create_session(&mut tx, config, &user, SessionType::Authenticated, client).await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, "User registered");
+
+    if config.features.email_verification {
+        verification::send_verification_email(config, email_service, &user)?;
+    }
+
+    Ok(LoginResponse {
+        user: user.into_response(),
+        token: session.token,
+        refresh_token: session.refresh_token.unwrap_or_default(),
+        expires_at: session.expires_at,
+    })
+}
+
+/// Crée ou reprend l'utilisateur invité identifié par l'`anonymous_id` du client
+///
+/// Chaque appel ouvre une nouvelle session invité et repousse l'expiration du compte.
+pub async fn create_guest_session(
+    pool: &PgPool,
+    config: &Config,
+    request: GuestSessionRequest,
+    client: &ClientInfo,
+) -> AppResult<GuestSessionResponse> {
+    if !config.features.guest_mode {
+        return Err(AppError::Forbidden("Guest mode is currently disabled".to_string()));
+    }
+
+    let session_expires_at = Utc::now() + Duration::seconds(config.auth.guest_session_expiration);
+
+    let mut tx = pool.begin().await?;
+
+    // Un anonymous_id déjà migré vers un compte ne peut plus ouvrir de session invité
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users (anonymous_id, user_type, session_expires_at)
+        VALUES ($1, 'guest', $2)
+        ON CONFLICT (anonymous_id) DO UPDATE
+            SET session_expires_at = EXCLUDED.session_expires_at
+            WHERE users.user_type = 'guest'
+        RETURNING *
+        "#,
+    )
+    .bind(request.anonymous_id)
+    .bind(session_expires_at)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Conflict("This guest has already been converted to an account".to_string()))?;
+
+    if !user.is_active {
+        return Err(AppError::Forbidden("Account is disabled".to_string()));
+    }
+
+    let session = create_session(&mut tx, config, &user, SessionType::Guest, client).await?;
+
+    if request.persistent {
+        sqlx::query("UPDATE user_sessions SET is_persistent = TRUE WHERE id = $1")
+            .bind(session.session_id)
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, session_id = %session.session_id, "Guest session created");
+
+    Ok(GuestSessionResponse {
+        user: user.into_response(),
+        token: session.token,
+        expires_at: session.expires_at,
+    })
+}
+
+/// Échange un refresh token contre une nouvelle paire de tokens
+///
+/// Un refresh token n'est utilisable qu'une seule fois. Présenter un token déjà
+/// consommé révoque toute la session et journalise une activité suspecte.
+pub async fn refresh(
+    pool: &PgPool,
+    config: &Config,
+    request: RefreshTokenRequest,
+    client: &ClientInfo,
+) -> AppResult<LoginResponse> {
+    let presented_hash = hash_token(&request.refresh_token);
+    let invalid_token = || AppError::Unauthorized("Invalid refresh token".to_string());
+
+    let mut tx = pool.begin().await?;
+
+    // Le verrou sérialise les refresh concurrents : le second verra un token déjà tourné
+    let session: Option<(Uuid, Uuid, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        SELECT id, user_id, expires_at FROM user_sessions
+        WHERE refresh_token_hash = $1 AND revoked_at IS NULL
+        FOR UPDATE
+        "#,
+    )
+    .bind(&presented_hash)
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let Some((session_id, user_id, session_expires_at)) = session else {
+        let reused: Option<(Uuid, Uuid)> = sqlx::query_as(
+            r#"
+            SELECT s.id, s.user_id FROM session_rotated_refresh_tokens r
+            JOIN user_sessions s ON s.id = r.session_id
+            WHERE r.token_hash = $1
+            "#,
+        )
+        .bind(&presented_hash)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some((session_id, user_id)) = reused {
+            sqlx::query("UPDATE user_sessions SET revoked_at = COALESCE(revoked_at, NOW()) WHERE id = $1")
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+
+            audit::log_security_event(
+                &mut tx,
+                SecurityEvent {
+                    event_type: SecurityEventType::SuspiciousActivity,
+                    severity: SecuritySeverity::High,
+                    user_id: Some(user_id),
+                    session_id: Some(session_id),
+                    details: json!({ "reason": "refresh_token_reuse" }),
+                },
+                client,
+            )
+            .await?;
+
+            tx.commit().await?;
+
+            warn!(user_id = %user_id, session_id = %session_id, "Refresh token reuse detected, session revoked");
+        }
+
+        return Err(invalid_token());
+    };
+
+    if session_expires_at <= Utc::now() {
+        return Err(invalid_token());
+    }
+
+    let user = user::find_by_id(pool, user_id).await?.ok_or_else(invalid_token)?;
+    if !user.is_active {
+        return Err(AppError::Forbidden("Account is disabled".to_string()));
+    }
+
+    let now = Utc::now();
+    let access_expires_at = now + Duration::seconds(config.auth.access_token_expiration);
+    let session_expires_at = now + Duration::seconds(config.auth.refresh_token_expiration);
+
+    let token = jwt::issue_access_token(config, &user, session_id, access_expires_at)?;
+    let refresh_token = generate_token();
+
+    sqlx::query("INSERT INTO session_rotated_refresh_tokens (token_hash, session_id) VALUES ($1, $2)")
+        .bind(&presented_hash)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE user_sessions
+        SET token_hash = $2,
+            refresh_token_hash = $3,
+            expires_at = $4,
+            ip_address = COALESCE($5::inet, ip_address),
+            user_agent = COALESCE($6, user_agent),
+            last_used_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(session_id)
+    .bind(hash_token(&token))
+    .bind(hash_token(&refresh_token))
+    .bind(session_expires_at)
+    .bind(client.ip_address.as_deref())
+    .bind(client.user_agent.as_deref())
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, session_id = %session_id, "Session refreshed");
+
+    Ok(LoginResponse {
+        user: user.into_response(),
+        token,
+        refresh_token,
+        expires_at: access_expires_at,
+    })
+}
+
+/// Crée une ligne `user_sessions` et retourne les tokens en clair
+///
+/// Le token d'accès est un JWT rattaché à la session. Seuls les hash des tokens
+/// sont persistés. Les sessions authentifiées reçoivent un refresh token, les
+/// sessions invité n'en ont pas.
+pub async fn create_session(
+    conn: &mut PgConnection,
+    config: &Config,
+    user: &User,
+    session_type: SessionType,
+    client: &ClientInfo,
+) -> AppResult<IssuedSession> {
+    let now = Utc::now();
+    // Une session invité n'a pas de refresh token : son token d'accès dure autant qu'elle
+    let (access_expires_at, session_expires_at) = match session_type {
+        SessionType::Authenticated => (
+            now + Duration::seconds(config.auth.access_token_expiration),
+            now + Duration::seconds(config.auth.refresh_token_expiration),
+        ),
+        SessionType::Guest => {
+            let expires_at = now + Duration::seconds(config.auth.guest_session_expiration);
+            (expires_at, expires_at)
+        }
+    };
+
+    let session_id = Uuid::new_v4();
+    let token = jwt::issue_access_token(config, user, session_id, access_expires_at)?;
+    let refresh_token = match session_type {
+        SessionType::Authenticated => Some(generate_token()),
+        SessionType::Guest => None,
+    };
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_sessions
+            (id, user_id, session_type, token_hash, refresh_token_hash, ip_address, user_agent, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6::inet, $7, $8)
+        "#,
+    )
+    .bind(session_id)
+    .bind(user.id)
+    .bind(session_type.to_string())
+    .bind(hash_token(&token))
+    .bind(refresh_token.as_deref().map(hash_token))
+    .bind(client.ip_address.as_deref())
+    .bind(client.user_agent.as_deref())
+    .bind(session_expires_at)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(IssuedSession {
+        session_id,
+        token,
+        refresh_token,
+        expires_at: access_expires_at,
+    })
+}
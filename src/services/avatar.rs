@@ -0,0 +1,222 @@
+use std::io::Cursor;
+
+use image::imageops::FilterType;
+use image::io::{Limits, Reader};
+use image::{ImageFormat, ImageOutputFormat};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::middleware::auth::AuthContext;
+use crate::models::{AvatarResponse, AvatarVariant};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::storage::Storage;
+
+/// Tailles générées, la première étant celle enregistrée dans `avatar_url`
+pub const AVATAR_SIZES: [u32; 3] = [256, 128, 64];
+
+/// Dimensions maximales acceptées avant décodage complet
+const MAX_DIMENSION: u32 = 4096;
+
+/// Format d'une image d'après ses premiers octets, sans se fier au type annoncé
+pub fn detect_format(data: &[u8]) -> Option<ImageFormat> {
+    match data {
+        [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(ImageFormat::Png),
+        [0xFF, 0xD8, 0xFF, ..] => Some(ImageFormat::Jpeg),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(ImageFormat::Gif),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ImageFormat::WebP),
+        _ => None,
+    }
+}
+
+/// Décode l'image et la recadre au carré dans chacune des tailles, en PNG
+pub fn render_variants(data: &[u8], format: ImageFormat) -> AppResult<Vec<(u32, Vec<u8>)>> {
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_DIMENSION);
+    limits.max_image_height = Some(MAX_DIMENSION);
+
+    let mut reader = Reader::with_format(Cursor::new(data), format);
+    reader.limits(limits);
+    let image = reader
+        .decode()
+        .map_err(|_| AppError::BadRequest("Invalid or oversized image".to_string()))?;
+
+    AVATAR_SIZES
+        .iter()
+        .map(|&size| {
+            let mut output = Cursor::new(Vec::new());
+            image
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .write_to(&mut output, ImageOutputFormat::Png)
+                .map_err(|e| AppError::Internal(format!("Avatar encoding failed: {}", e)))?;
+            Ok((size, output.into_inner()))
+        })
+        .collect()
+}
+
+/// Enregistre un nouvel avatar et supprime les fichiers de l'ancien
+pub async fn upload(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    context: &AuthContext,
+    data: Vec<u8>,
+) -> AppResult<AvatarResponse> {
+    let format = detect_format(&data).ok_or_else(|| {
+        AppError::BadRequest("Unsupported image type, expected PNG, JPEG, GIF or WebP".to_string())
+    })?;
+
+    // Le décodage et le redimensionnement sont coûteux : hors de l'exécuteur async
+    let variants = tokio::task::spawn_blocking(move || render_variants(&data, format))
+        .await
+        .map_err(|e| AppError::Internal(format!("Avatar processing failed: {}", e)))??;
+
+    let version = Uuid::new_v4().simple().to_string();
+    let mut stored = Vec::with_capacity(variants.len());
+    let mut sizes = Vec::with_capacity(variants.len());
+    for (size, bytes) in variants {
+        let key = variant_key(context.user_id, &version, size);
+        if let Err(e) = storage.put(&key, bytes).await {
+            discard(storage, &stored).await;
+            return Err(e);
+        }
+        sizes.push(AvatarVariant {
+            size,
+            url: storage.url(&key),
+        });
+        stored.push(key);
+    }
+    let avatar_url = sizes[0].url.clone();
+
+    let previous = match replace_avatar_url(pool, context.user_id, &avatar_url).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            discard(storage, &stored).await;
+            return Err(e);
+        }
+    };
+
+    if let Some(previous) = previous {
+        remove_stored(storage, context.user_id, &previous).await;
+    }
+
+    info!(user_id = %context.user_id, "Avatar updated");
+
+    Ok(AvatarResponse { avatar_url, sizes })
+}
+
+/// Supprime les fichiers d'un avatar remplacé, s'il a été envoyé sur ce stockage
+///
+/// Un échec n'est que journalisé : le profil est déjà à jour.
+pub async fn remove_stored(storage: &dyn Storage, user_id: Uuid, avatar_url: &str) {
+    if let Some(keys) = stored_keys(storage, user_id, avatar_url) {
+        discard(storage, &keys).await;
+    }
+}
+
+/// Remplace `avatar_url` et retourne l'ancienne valeur
+async fn replace_avatar_url(pool: &PgPool, user_id: Uuid, avatar_url: &str) -> AppResult<Option<String>> {
+    let mut tx = pool.begin().await?;
+
+    let previous = sqlx::query_scalar::<_, Option<String>>(
+        "SELECT avatar_url FROM users WHERE id = $1 FOR UPDATE",
+    )
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    sqlx::query("UPDATE users SET avatar_url = $2 WHERE id = $1")
+        .bind(user_id)
+        .bind(avatar_url)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(previous)
+}
+
+fn variant_key(user_id: Uuid, version: &str, size: u32) -> String {
+    format!("avatars/{}/{}-{}.png", user_id, version, size)
+}
+
+/// Clés de toutes les tailles d'un avatar de ce compte, à partir de son URL
+fn stored_keys(storage: &dyn Storage, user_id: Uuid, avatar_url: &str) -> Option<Vec<String>> {
+    let key = storage.key_from_url(avatar_url)?;
+    let version = key
+        .strip_prefix(&format!("avatars/{}/", user_id))?
+        .strip_suffix(&format!("-{}.png", AVATAR_SIZES[0]))?;
+    if version.len() != 32 || !version.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    Some(AVATAR_SIZES.iter().map(|&size| variant_key(user_id, version, size)).collect())
+}
+
+async fn discard(storage: &dyn Storage, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = storage.delete(key).await {
+            warn!(key = %key, "Failed to delete avatar file: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StorageConfig;
+    use crate::utils::storage::LocalStorage;
+    use image::{DynamicImage, RgbImage};
+
+    fn encode(width: u32, height: u32, format: ImageOutputFormat) -> Vec<u8> {
+        let mut output = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut output, format)
+            .unwrap();
+        output.into_inner()
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format(&encode(4, 4, ImageOutputFormat::Png)), Some(ImageFormat::Png));
+        assert_eq!(detect_format(&encode(4, 4, ImageOutputFormat::Jpeg(80))), Some(ImageFormat::Jpeg));
+        assert_eq!(detect_format(b"RIFF\0\0\0\0WEBPVP8 "), Some(ImageFormat::WebP));
+        assert_eq!(detect_format(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>"), None);
+        assert_eq!(detect_format(b""), None);
+    }
+
+    #[test]
+    fn test_render_variants() {
+        let variants = render_variants(&encode(300, 200, ImageOutputFormat::Png), ImageFormat::Png).unwrap();
+
+        assert_eq!(variants.len(), AVATAR_SIZES.len());
+        for (size, bytes) in variants {
+            let image = image::load_from_memory_with_format(&bytes, ImageFormat::Png).unwrap();
+            assert_eq!((image.width(), image.height()), (size, size));
+        }
+
+        // En-tête PNG valide mais contenu tronqué
+        assert!(render_variants(&encode(4, 4, ImageOutputFormat::Png)[..16], ImageFormat::Png).is_err());
+    }
+
+    #[test]
+    fn test_stored_keys() {
+        let storage = LocalStorage::new(&StorageConfig {
+            local_path: "/tmp/ettu".to_string(),
+            public_url: "http://localhost:8080/uploads".to_string(),
+            avatar_max_size: 1024,
+            export_ttl: 3600,
+        });
+        let user_id = Uuid::new_v4();
+        let version = Uuid::new_v4().simple().to_string();
+        let url = storage.url(&variant_key(user_id, &version, AVATAR_SIZES[0]));
+
+        let keys = stored_keys(&storage, user_id, &url).unwrap();
+        assert_eq!(keys.len(), AVATAR_SIZES.len());
+        assert!(keys.contains(&variant_key(user_id, &version, 64)));
+
+        // Avatar d'un autre compte ou hébergé ailleurs : rien à supprimer
+        assert!(stored_keys(&storage, Uuid::new_v4(), &url).is_none());
+        assert!(stored_keys(&storage, user_id, "https://avatars.githubusercontent.com/u/1").is_none());
+    }
+}
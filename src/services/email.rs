@@ -1 +1,289 @@
-// Email service - TODO: Implement
+use std::sync::Arc;
+
+use handlebars::Handlebars;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use serde_json::json;
+use tracing::{debug, info};
+
+use crate::config::EmailConfig;
+use crate::utils::error::{AppError, AppResult};
+
+const TEMPLATES: &[(&str, &str)] = &[
+    ("email_verification.subject", "Confirmez votre adresse email ETTU"),
+    (
+        "email_verification.body",
+        "Bonjour {{name}},\n\n\
+         Merci de vous être inscrit sur ETTU ! Confirmez votre adresse email en ouvrant ce lien :\n\n\
+         {{link}}\n\n\
+         Ce lien expire dans {{expires_in_hours}} heures. Si vous n'êtes pas à l'origine de cette inscription, ignorez cet email.\n\n\
+         L'équipe ETTU",
+    ),
+    ("password_reset.subject", "Réinitialisation de votre mot de passe ETTU"),
+    (
+        "password_reset.body",
+        "Bonjour {{name}},\n\n\
+         Une réinitialisation du mot de passe de votre compte ETTU a été demandée. Choisissez un nouveau mot de passe en ouvrant ce lien :\n\n\
+         {{link}}\n\n\
+         Ce lien expire dans {{expires_in_minutes}} minutes et ne peut être utilisé qu'une fois. Si vous n'êtes pas à l'origine de cette demande, ignorez cet email : votre mot de passe reste inchangé.\n\n\
+         L'équipe ETTU",
+    ),
+    ("project_invitation.subject", "{{inviter}} vous invite sur le projet {{project}}"),
+    (
+        "project_invitation.body",
+        "Bonjour,\n\n\
+         {{inviter}} vous invite à rejoindre le projet « {{project}} » sur ETTU en tant que {{role}}. Acceptez ou déclinez l'invitation en ouvrant ce lien :\n\n\
+         {{link}}\n\n\
+         Cette invitation expire dans {{expires_in_days}} jours.\n\n\
+         L'équipe ETTU",
+    ),
+    ("project_invitation_signup.subject", "{{inviter}} vous invite sur le projet {{project}}"),
+    (
+        "project_invitation_signup.body",
+        "Bonjour,\n\n\
+         {{inviter}} vous invite à rejoindre le projet « {{project}} » sur ETTU en tant que {{role}}. Créez votre compte avec cette adresse email pour retrouver l'invitation :\n\n\
+         {{link}}\n\n\
+         L'invitation vous attend jusqu'à votre inscription, puis reste valable {{expires_in_days}} jours.\n\n\
+         L'équipe ETTU",
+    ),
+];
+
+/// Service d'envoi d'emails transactionnels
+///
+/// Sans identifiants SMTP configurés, les emails sont journalisés au lieu d'être envoyés,
+/// ce qui permet de travailler en local sans serveur de mail. La production exige SMTP.
+#[derive(Clone)]
+pub struct EmailService {
+    mailer: Option<AsyncSmtpTransport<Tokio1Executor>>,
+    log_unsent_content: bool,
+    from: Mailbox,
+    templates: Arc<Handlebars<'static>>,
+}
+
+impl EmailService {
+    pub fn new(config: &EmailConfig) -> AppResult<Self> {
+        let from = format!("{} <{}>", config.from_name, config.from_email)
+            .parse::<Mailbox>()
+            .map_err(|e| AppError::Internal(format!("Invalid sender address: {}", e)))?;
+
+        let mailer = if config.smtp_username.is_empty() {
+            None
+        } else {
+            let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)
+                .map_err(|e| AppError::Internal(format!("Invalid SMTP relay: {}", e)))?
+                .port(config.smtp_port)
+                .credentials(Credentials::new(
+                    config.smtp_username.clone(),
+                    config.smtp_password.clone(),
+                ))
+                .build();
+            Some(transport)
+        };
+
+        let mut templates = Handlebars::new();
+        templates.register_escape_fn(handlebars::no_escape);
+        templates.set_strict_mode(true);
+        for (name, template) in TEMPLATES {
+            templates
+                .register_template_string(name, template)
+                .map_err(|e| AppError::Internal(format!("Invalid email template {}: {}", name, e)))?;
+        }
+
+        Ok(Self {
+            mailer,
+            log_unsent_content: config.log_unsent_content,
+            from,
+            templates: Arc::new(templates),
+        })
+    }
+
+    /// Rend le template `template` (sujet et corps) et l'envoie à `to`
+    pub async fn send_template(
+        &self,
+        to: &str,
+        template: &str,
+        data: &serde_json::Value,
+    ) -> AppResult<()> {
+        let render = |part: &str| {
+            self.templates
+                .render(&format!("{}.{}", template, part), data)
+                .map_err(|e| AppError::Internal(format!("Email template rendering failed: {}", e)))
+        };
+        let subject = render("subject")?;
+        let body = render("body")?;
+
+        let Some(mailer) = &self.mailer else {
+            info!(template, "SMTP not configured, email not sent");
+            // Le corps contient des liens de réinitialisation ou de vérification valides
+            if self.log_unsent_content {
+                debug!(template, subject = %subject, body = %body, "Email content");
+            }
+            return Ok(());
+        };
+
+        let recipient = to
+            .parse::<Mailbox>()
+            .map_err(|e| AppError::BadRequest(format!("Invalid email address: {}", e)))?;
+
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(recipient)
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|e| AppError::Internal(format!("Failed to build email: {}", e)))?;
+
+        mailer
+            .send(message)
+            .await
+            .map_err(|e| AppError::Internal(format!("Failed to send email: {}", e)))?;
+
+        info!(template, "Email sent");
+        Ok(())
+    }
+
+    /// Envoie le lien de vérification d'adresse email
+    pub async fn send_email_verification(
+        &self,
+        to: &str,
+        name: &str,
+        link: &str,
+        expires_in_hours: i64,
+    ) -> AppResult<()> {
+        self.send_template(
+            to,
+            "email_verification",
+            &json!({ "name": name, "link": link, "expires_in_hours": expires_in_hours }),
+        )
+        .await
+    }
+
+    /// Envoie le lien de réinitialisation de mot de passe
+    pub async fn send_password_reset(
+        &self,
+        to: &str,
+        name: &str,
+        link: &str,
+        expires_in_minutes: i64,
+    ) -> AppResult<()> {
+        self.send_template(
+            to,
+            "password_reset",
+            &json!({ "name": name, "link": link, "expires_in_minutes": expires_in_minutes }),
+        )
+        .await
+    }
+
+    /// Envoie une invitation à rejoindre un projet
+    ///
+    /// Sans compte associé à l'adresse, l'email invite d'abord à s'inscrire.
+    pub async fn send_project_invitation(&self, to: &str, invitation: &ProjectInvitationEmail<'_>) -> AppResult<()> {
+        self.send_template(
+            to,
+            if invitation.has_account {
+                "project_invitation"
+            } else {
+                "project_invitation_signup"
+            },
+            &json!({
+                "inviter": invitation.inviter,
+                "project": invitation.project,
+                "role": invitation.role,
+                "link": invitation.link,
+                "expires_in_days": invitation.expires_in_days,
+            }),
+        )
+        .await
+    }
+}
+
+/// Contenu d'un email d'invitation à un projet
+pub struct ProjectInvitationEmail<'a> {
+    pub inviter: &'a str,
+    pub project: &'a str,
+    pub role: &'a str,
+    pub link: &'a str,
+    pub has_account: bool,
+    pub expires_in_days: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> EmailConfig {
+        EmailConfig {
+            smtp_host: "localhost".to_string(),
+            smtp_port: 25,
+            smtp_username: String::new(),
+            smtp_password: String::new(),
+            from_email: "noreply@ettu.dev".to_string(),
+            from_name: "ETTU".to_string(),
+            log_unsent_content: false,
+        }
+    }
+
+    #[test]
+    fn test_templates_render() {
+        let service = EmailService::new(&test_config()).unwrap();
+        let body = service
+            .templates
+            .render(
+                "email_verification.body",
+                &json!({ "name": "Bob", "link": "http://x/verify?token=a&b", "expires_in_hours": 24 }),
+            )
+            .unwrap();
+
+        assert!(body.contains("Bonjour Bob"));
+        assert!(body.contains("http://x/verify?token=a&b"));
+    }
+
+    #[test]
+    fn test_password_reset_template_renders() {
+        let service = EmailService::new(&test_config()).unwrap();
+        let body = service
+            .templates
+            .render(
+                "password_reset.body",
+                &json!({ "name": "Bob", "link": "http://x/reset?token=t", "expires_in_minutes": 60 }),
+            )
+            .unwrap();
+
+        assert!(body.contains("http://x/reset?token=t"));
+        assert!(body.contains("60 minutes"));
+    }
+
+    #[test]
+    fn test_project_invitation_signup_template_renders() {
+        let service = EmailService::new(&test_config()).unwrap();
+        let body = service
+            .templates
+            .render(
+                "project_invitation_signup.body",
+                &json!({
+                    "inviter": "Alice",
+                    "project": "ETTU",
+                    "role": "editor",
+                    "link": "http://x/register?invitation=i",
+                    "expires_in_days": 7,
+                }),
+            )
+            .unwrap();
+
+        assert!(body.contains("Alice vous invite"));
+        assert!(body.contains("http://x/register?invitation=i"));
+    }
+
+    #[tokio::test]
+    async fn test_send_without_smtp_is_noop() {
+        let service = EmailService::new(&test_config()).unwrap();
+        let result = service
+            .send_email_verification("bob@example.com", "Bob", "http://x", 24)
+            .await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,436 @@
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+use sqlx::PgPool;
+use tracing::{error, info};
+use uuid::Uuid;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::models::{DataExport, DataExportLinkResponse, DataExportResponse};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::storage::Storage;
+use crate::utils::token::{generate_token, hash_token};
+
+/// Types des notifications envoyées à la fin d'un export
+pub const EXPORT_READY_NOTIFICATION: &str = "data_export_ready";
+pub const EXPORT_FAILED_NOTIFICATION: &str = "data_export_failed";
+
+/// Sections demandées mais absentes de l'archive, avec la raison indiquée dans le manifeste
+const OMITTED_SECTIONS: &[(&str, &str)] = &[(
+    "messages",
+    "Ettu has no messaging feature: no messages are stored for any account",
+)];
+
+/// Délai au-delà duquel un export en cours est considéré comme interrompu
+const STALE_EXPORT_MINUTES: i64 = 60;
+
+/// Sections de l'archive, chacune produisant une valeur JSON pour l'utilisateur `$1`
+///
+/// Les secrets (hashes de mot de passe et de tokens) ne sont jamais exportés.
+const SECTIONS: &[(&str, &str)] = &[
+    ("profile", "SELECT to_jsonb(u) - 'password_hash' FROM users u WHERE u.id = $1"),
+    (
+        "projects",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(p) ORDER BY p.created_at), '[]') FROM projects p WHERE p.owner_id = $1",
+    ),
+    (
+        "project_memberships",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(m) ORDER BY m.created_at), '[]') FROM project_permissions m WHERE m.user_id = $1",
+    ),
+    (
+        "notes",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(n) ORDER BY n.created_at), '[]') FROM project_notes n WHERE n.author_id = $1",
+    ),
+    (
+        "snippets",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(s) ORDER BY s.created_at), '[]') FROM project_snippets s WHERE s.author_id = $1",
+    ),
+    (
+        "public_snippets",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(s) ORDER BY s.created_at), '[]') FROM public_snippets s WHERE s.author_id = $1",
+    ),
+    (
+        "tasks",
+        r#"
+        SELECT COALESCE(jsonb_agg(
+            to_jsonb(t) || jsonb_build_object('checklist', (
+                SELECT COALESCE(jsonb_agg(to_jsonb(c) ORDER BY c.order_position), '[]')
+                FROM task_checklist_items c WHERE c.task_id = t.id
+            ))
+            ORDER BY t.created_at), '[]')
+        FROM tasks t WHERE t.author_id = $1
+        "#,
+    ),
+    (
+        "comments",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(c) ORDER BY c.created_at), '[]') FROM comments c WHERE c.author_id = $1",
+    ),
+    (
+        "notifications",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(n) ORDER BY n.created_at), '[]') FROM notifications n WHERE n.user_id = $1",
+    ),
+    (
+        "sessions",
+        r#"
+        SELECT COALESCE(jsonb_agg(to_jsonb(s) - 'token_hash' - 'refresh_token_hash' ORDER BY s.created_at), '[]')
+        FROM user_sessions s WHERE s.user_id = $1
+        "#,
+    ),
+    (
+        "security_events",
+        r#"
+        SELECT COALESCE(jsonb_agg(to_jsonb(e) - 'investigated_by' - 'investigation_notes' ORDER BY e.created_at), '[]')
+        FROM security_audit_logs e WHERE e.user_id = $1
+        "#,
+    ),
+    (
+        "identities",
+        "SELECT COALESCE(jsonb_agg(to_jsonb(i) ORDER BY i.created_at), '[]') FROM user_identities i WHERE i.user_id = $1",
+    ),
+    (
+        "personal_access_tokens",
+        r#"
+        SELECT COALESCE(jsonb_agg(to_jsonb(t) - 'token_hash' ORDER BY t.created_at), '[]')
+        FROM personal_access_tokens t WHERE t.user_id = $1
+        "#,
+    ),
+];
+
+/// Demande un export et le construit en arrière-plan
+///
+/// Un seul export peut être en cours par utilisateur ; l'utilisateur est notifié
+/// quand l'archive est prête, et demande ensuite un lien de téléchargement.
+pub async fn request_export(
+    pool: &PgPool,
+    config: &Config,
+    storage: Arc<dyn Storage>,
+    context: &AuthContext,
+) -> AppResult<DataExportResponse> {
+    let export = sqlx::query_as::<_, DataExport>(
+        "INSERT INTO data_exports (user_id) VALUES ($1) RETURNING *",
+    )
+    .bind(context.user_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Conflict("A data export is already in progress".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    info!(user_id = %context.user_id, export_id = %export.id, "Data export requested");
+
+    let pool = pool.clone();
+    let config = config.clone();
+    let export_id = export.id;
+    tokio::spawn(async move {
+        process(&pool, &config, storage.as_ref(), export_id).await;
+    });
+
+    Ok(export.into_response())
+}
+
+/// Exports récents de l'utilisateur courant
+pub async fn list_exports(pool: &PgPool, context: &AuthContext) -> AppResult<Vec<DataExportResponse>> {
+    let exports = sqlx::query_as::<_, DataExport>(
+        "SELECT * FROM data_exports WHERE user_id = $1 ORDER BY created_at DESC LIMIT 20",
+    )
+    .bind(context.user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(exports.into_iter().map(DataExport::into_response).collect())
+}
+
+/// Statut d'un export de l'utilisateur courant
+pub async fn find_export(pool: &PgPool, context: &AuthContext, export_id: Uuid) -> AppResult<DataExportResponse> {
+    let export = sqlx::query_as::<_, DataExport>("SELECT * FROM data_exports WHERE id = $1 AND user_id = $2")
+        .bind(export_id)
+        .bind(context.user_id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Export not found".to_string()))?;
+
+    Ok(export.into_response())
+}
+
+/// Génère un nouveau lien de téléchargement pour un export terminé de l'utilisateur courant
+///
+/// Seul le hash du token est conservé : chaque appel remplace le lien précédent,
+/// qui cesse aussitôt de fonctionner.
+pub async fn issue_download_link(
+    pool: &PgPool,
+    config: &Config,
+    context: &AuthContext,
+    export_id: Uuid,
+) -> AppResult<DataExportLinkResponse> {
+    let token = generate_token();
+    let expires_at: DateTime<Utc> = sqlx::query_scalar(
+        r#"
+        UPDATE data_exports SET download_token_hash = $3
+        WHERE id = $1 AND user_id = $2 AND status = 'completed' AND expires_at > NOW()
+        RETURNING expires_at
+        "#,
+    )
+    .bind(export_id)
+    .bind(context.user_id)
+    .bind(hash_token(&token))
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Export not found or not available".to_string()))?;
+
+    info!(user_id = %context.user_id, export_id = %export_id, "Data export link issued");
+
+    Ok(DataExportLinkResponse {
+        download_url: format!(
+            "{}/api/v1/users/me/exports/{}/download?token={}",
+            config.server.public_url.trim_end_matches('/'),
+            export_id,
+            token
+        ),
+        expires_at,
+    })
+}
+
+/// Archive d'un export terminé, authentifiée par le token du lien
+pub async fn download(
+    pool: &PgPool,
+    storage: &dyn Storage,
+    export_id: Uuid,
+    token: &str,
+) -> AppResult<(DataExport, Vec<u8>)> {
+    let export = sqlx::query_as::<_, DataExport>(
+        r#"
+        SELECT * FROM data_exports
+        WHERE id = $1 AND download_token_hash = $2 AND status = 'completed' AND expires_at > NOW()
+        "#,
+    )
+    .bind(export_id)
+    .bind(hash_token(token))
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Export not found or link expired".to_string()))?;
+
+    let key = export
+        .storage_key
+        .as_deref()
+        .ok_or_else(|| AppError::Internal(format!("Export {} has no archive", export.id)))?;
+    let archive = storage.get(key).await?;
+
+    Ok((export, archive))
+}
+
+/// Supprime les archives expirées et libère les exports interrompus
+pub async fn purge_expired(pool: &PgPool, storage: &dyn Storage) -> AppResult<()> {
+    let expired: Vec<(Uuid, Option<String>)> = sqlx::query_as(
+        r#"
+        UPDATE data_exports SET status = 'expired', download_token_hash = NULL
+        WHERE status = 'completed' AND expires_at < NOW()
+        RETURNING id, storage_key
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for (id, key) in &expired {
+        if let Some(key) = key {
+            if let Err(e) = storage.delete(key).await {
+                error!(export_id = %id, "Failed to delete expired export archive: {}", e);
+            }
+        }
+    }
+
+    // Un redémarrage pendant la construction laisse l'export bloqué en cours
+    let interrupted = sqlx::query(
+        r#"
+        UPDATE data_exports SET status = 'failed', error_message = 'Export interrupted'
+        WHERE status IN ('pending', 'processing') AND created_at < NOW() - $1
+        "#,
+    )
+    .bind(Duration::minutes(STALE_EXPORT_MINUTES))
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if !expired.is_empty() || interrupted > 0 {
+        info!(expired = expired.len(), interrupted, "Data exports purged");
+    }
+
+    Ok(())
+}
+
+/// Construit l'archive, puis notifie l'utilisateur du succès ou de l'échec
+async fn process(pool: &PgPool, config: &Config, storage: &dyn Storage, export_id: Uuid) {
+    match build(pool, config, storage, export_id).await {
+        Ok(user_id) => info!(user_id = %user_id, export_id = %export_id, "Data export completed"),
+        Err(e) => {
+            error!(export_id = %export_id, "Data export failed: {}", e);
+            if let Err(e) = fail(pool, export_id).await {
+                error!(export_id = %export_id, "Failed to record data export failure: {}", e);
+            }
+        }
+    }
+}
+
+async fn build(pool: &PgPool, config: &Config, storage: &dyn Storage, export_id: Uuid) -> AppResult<Uuid> {
+    let user_id: Uuid = sqlx::query_scalar(
+        "UPDATE data_exports SET status = 'processing' WHERE id = $1 AND status = 'pending' RETURNING user_id",
+    )
+    .bind(export_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Export not found".to_string()))?;
+
+    let mut sections = Vec::with_capacity(SECTIONS.len());
+    for (name, query) in SECTIONS {
+        let value: Option<Value> = sqlx::query_scalar(query).bind(user_id).fetch_one(pool).await?;
+        sections.push((*name, value.unwrap_or(Value::Null)));
+    }
+
+    let manifest = manifest(export_id, user_id);
+    let archive = tokio::task::spawn_blocking(move || build_archive(&manifest, &sections))
+        .await
+        .map_err(|e| AppError::Internal(format!("Export archive task failed: {}", e)))??;
+
+    let key = format!("exports/{}/{}.zip", user_id, export_id);
+    let file_size = archive.len() as i64;
+    storage.put(&key, archive).await?;
+
+    let expires_at = Utc::now() + Duration::seconds(config.storage.export_ttl);
+
+    let mut tx = pool.begin().await?;
+
+    // Le lien de téléchargement n'est généré qu'à la demande (voir `issue_download_link`)
+    sqlx::query(
+        r#"
+        UPDATE data_exports
+        SET status = 'completed', storage_key = $2, file_size = $3, download_token_hash = NULL,
+            expires_at = $4, completed_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(export_id)
+    .bind(&key)
+    .bind(file_size)
+    .bind(expires_at)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO notifications (user_id, type, title, message, entity_type, entity_id)
+        VALUES ($1, $2, 'Votre export de données est prêt', $3, 'data_export', $4)
+        "#,
+    )
+    .bind(user_id)
+    .bind(EXPORT_READY_NOTIFICATION)
+    .bind(format!(
+        "Votre archive est disponible dans vos paramètres jusqu'au {}.",
+        expires_at.format("%d/%m/%Y %H:%M UTC")
+    ))
+    .bind(export_id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(user_id)
+}
+
+async fn fail(pool: &PgPool, export_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        WITH failed AS (
+            UPDATE data_exports SET status = 'failed', error_message = 'Export failed'
+            WHERE id = $1 AND status IN ('pending', 'processing')
+            RETURNING user_id
+        )
+        INSERT INTO notifications (user_id, type, title, message, entity_type, entity_id)
+        SELECT user_id, $2, 'Votre export de données a échoué',
+               'Une erreur est survenue pendant la préparation de votre archive. Vous pouvez relancer l''export.',
+               'data_export', $1
+        FROM failed
+        "#,
+    )
+    .bind(export_id)
+    .bind(EXPORT_FAILED_NOTIFICATION)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+/// Manifeste de l'archive : sections présentes, et sections absentes avec leur raison
+fn manifest(export_id: Uuid, user_id: Uuid) -> Value {
+    json!({
+        "export_id": export_id,
+        "user_id": user_id,
+        "generated_at": Utc::now(),
+        "sections": SECTIONS.iter().map(|(name, _)| *name).collect::<Vec<_>>(),
+        "omitted_sections": OMITTED_SECTIONS
+            .iter()
+            .map(|(name, reason)| json!({ "name": name, "reason": reason }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+/// Archive ZIP contenant le manifeste et un fichier JSON par section
+pub fn build_archive(manifest: &Value, sections: &[(&str, Value)]) -> AppResult<Vec<u8>> {
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    let files = std::iter::once(("manifest", manifest)).chain(sections.iter().map(|(name, value)| (*name, value)));
+    for (name, value) in files {
+        let content = serde_json::to_vec_pretty(value).map_err(archive_error)?;
+        zip.start_file(format!("{}.json", name), options)
+            .map_err(archive_error)?;
+        zip.write_all(&content).map_err(archive_error)?;
+    }
+
+    let archive = zip.finish().map_err(archive_error)?;
+    Ok(archive.into_inner())
+}
+
+fn archive_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Internal(format!("Export archive error: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    #[test]
+    fn test_build_archive() {
+        let manifest = json!({ "sections": ["profile", "projects"] });
+        let sections = [
+            ("profile", json!({ "username": "ada" })),
+            ("projects", json!([{ "name": "ETTU" }])),
+        ];
+
+        let archive = build_archive(&manifest, &sections).unwrap();
+        let mut zip = ZipArchive::new(Cursor::new(archive)).unwrap();
+        assert_eq!(zip.len(), 3);
+
+        let mut content = String::new();
+        zip.by_name("projects.json").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&content).unwrap(), json!([{ "name": "ETTU" }]));
+        assert!(zip.by_name("manifest.json").is_ok());
+    }
+
+    #[test]
+    fn test_manifest_states_omitted_sections() {
+        let manifest = manifest(Uuid::new_v4(), Uuid::new_v4());
+
+        assert_eq!(manifest["sections"].as_array().unwrap().len(), SECTIONS.len());
+        assert_eq!(manifest["omitted_sections"][0]["name"], "messages");
+        assert!(manifest["omitted_sections"][0]["reason"].is_string());
+    }
+}
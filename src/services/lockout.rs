@@ -0,0 +1,426 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::LockoutConfig;
+use crate::middleware::auth::AuthContext;
+use crate::models::{SecurityEventType, SecuritySeverity};
+use crate::services::audit::{self, SecurityEvent};
+use crate::services::user;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::request::ClientInfo;
+use crate::utils::store::CounterStore;
+use crate::utils::token::hash_token;
+
+/// Portée d'un compteur d'échecs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Account,
+    Ip,
+}
+
+impl Scope {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Account => "account",
+            Scope::Ip => "ip",
+        }
+    }
+
+    fn locked_event(&self) -> SecurityEventType {
+        match self {
+            Scope::Account => SecurityEventType::AccountLocked,
+            Scope::Ip => SecurityEventType::IpBlocked,
+        }
+    }
+
+    fn unlocked_event(&self) -> SecurityEventType {
+        match self {
+            Scope::Account => SecurityEventType::AccountUnlocked,
+            Scope::Ip => SecurityEventType::IpUnblocked,
+        }
+    }
+}
+
+/// Compte visé par une tentative de connexion
+///
+/// Un identifiant sans compte a son propre compteur et se verrouille comme un
+/// compte existant : le verrou ne permet pas de savoir si le compte existe.
+#[derive(Debug, Clone, Copy)]
+pub enum LoginAccount<'a> {
+    Known(Uuid),
+    Unknown(&'a str),
+}
+
+impl LoginAccount<'_> {
+    fn user_id(&self) -> Option<Uuid> {
+        match self {
+            LoginAccount::Known(user_id) => Some(*user_id),
+            LoginAccount::Unknown(_) => None,
+        }
+    }
+
+    fn target(&self) -> String {
+        match self {
+            LoginAccount::Known(user_id) => user_id.to_string(),
+            // Haché : les identifiants saisis ne sont pas stockés en clair
+            LoginAccount::Unknown(identifier) => {
+                format!("unknown:{}", hash_token(&identifier.trim().to_lowercase()))
+            }
+        }
+    }
+}
+
+/// Protection des connexions contre le brute-force
+///
+/// Les échecs sont comptés par compte et par adresse IP. Au-delà du seuil, la
+/// cible est verrouillée pour une durée qui double à chaque récidive. Les
+/// erreurs du store ne bloquent jamais une connexion : elles sont journalisées.
+#[derive(Clone)]
+pub struct LoginGuard {
+    store: Arc<dyn CounterStore>,
+    config: LockoutConfig,
+}
+
+impl LoginGuard {
+    pub fn new(store: Arc<dyn CounterStore>, config: &LockoutConfig) -> Self {
+        Self {
+            store,
+            config: config.clone(),
+        }
+    }
+
+    /// Refuse la tentative si le compte ou l'adresse IP est verrouillé
+    ///
+    /// L'expiration d'un verrou n'est pas observable : elle est journalisée comme
+    /// levée automatique à la première tentative qui la suit.
+    pub async fn check(
+        &self,
+        pool: &PgPool,
+        account: Option<LoginAccount<'_>>,
+        client: &ClientInfo,
+    ) -> AppResult<()> {
+        let user_id = account.and_then(|account| account.user_id());
+        for (scope, id) in targets(account, client) {
+            let remaining = match self.store.ttl(&lock_key(scope, &id)).await {
+                Ok(remaining) => remaining,
+                Err(e) => {
+                    warn!("Lockout store unavailable: {}", e);
+                    None
+                }
+            };
+
+            if let Some(remaining) = remaining {
+                return Err(AppError::TooManyRequests(format!(
+                    "Too many failed login attempts, retry in {} seconds",
+                    remaining.as_secs().max(1)
+                )));
+            }
+
+            if self.take_expired_lock(scope, &id).await {
+                info!(scope = scope.as_str(), target = %id, "Login lock expired");
+
+                let mut conn = pool.acquire().await?;
+                audit::log_security_event(
+                    &mut conn,
+                    SecurityEvent {
+                        event_type: scope.unlocked_event(),
+                        severity: SecuritySeverity::Low,
+                        user_id,
+                        session_id: None,
+                        details: json!({ "scope": scope.as_str(), "reason": "expired" }),
+                    },
+                    client,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Compte un échec, journalise `login_failure` et verrouille au-delà du seuil
+    pub async fn record_failure(
+        &self,
+        pool: &PgPool,
+        account: Option<LoginAccount<'_>>,
+        client: &ClientInfo,
+        reason: &str,
+    ) -> AppResult<()> {
+        let user_id = account.and_then(|account| account.user_id());
+        let mut conn = pool.acquire().await?;
+        audit::log_security_event(
+            &mut conn,
+            SecurityEvent {
+                event_type: SecurityEventType::LoginFailure,
+                severity: SecuritySeverity::Low,
+                user_id,
+                session_id: None,
+                details: json!({ "reason": reason }),
+            },
+            client,
+        )
+        .await?;
+
+        for (scope, id) in targets(account, client) {
+            let lock = match self.register_failure(scope, &id).await {
+                Ok(lock) => lock,
+                Err(e) => {
+                    warn!("Lockout store unavailable: {}", e);
+                    continue;
+                }
+            };
+
+            if let Some((level, duration)) = lock {
+                warn!(scope = scope.as_str(), target = %id, level, "Login locked after repeated failures");
+
+                audit::log_security_event(
+                    &mut conn,
+                    SecurityEvent {
+                        event_type: scope.locked_event(),
+                        severity: SecuritySeverity::High,
+                        user_id,
+                        session_id: None,
+                        details: json!({
+                            "scope": scope.as_str(),
+                            "level": level,
+                            "duration_secs": duration.as_secs(),
+                        }),
+                    },
+                    client,
+                )
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remet à zéro les échecs du compte après une connexion réussie
+    pub async fn record_success(&self, user_id: Uuid) {
+        let id = user_id.to_string();
+        for key in [failures_key(Scope::Account, &id), level_key(Scope::Account, &id)] {
+            if let Err(e) = self.store.delete(&key).await {
+                warn!("Lockout store unavailable: {}", e);
+            }
+        }
+    }
+
+    /// Déverrouille manuellement un compte et journalise `account_unlocked`
+    pub async fn unlock(
+        &self,
+        pool: &PgPool,
+        admin: &AuthContext,
+        user_id: Uuid,
+        client: &ClientInfo,
+    ) -> AppResult<()> {
+        user::find_by_id(pool, user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+        let id = user_id.to_string();
+        for key in [
+            lock_key(Scope::Account, &id),
+            pending_unlock_key(Scope::Account, &id),
+            failures_key(Scope::Account, &id),
+            level_key(Scope::Account, &id),
+        ] {
+            self.store.delete(&key).await?;
+        }
+
+        let mut conn = pool.acquire().await?;
+        audit::log_security_event(
+            &mut conn,
+            SecurityEvent {
+                event_type: SecurityEventType::AccountUnlocked,
+                severity: SecuritySeverity::Medium,
+                user_id: Some(user_id),
+                session_id: Some(admin.session_id),
+                details: json!({ "unlocked_by": admin.user_id }),
+            },
+            client,
+        )
+        .await?;
+
+        info!(user_id = %user_id, admin_id = %admin.user_id, "Account unlocked");
+
+        Ok(())
+    }
+
+    /// Incrémente le compteur et pose le verrou si le seuil est atteint
+    async fn register_failure(&self, scope: Scope, id: &str) -> AppResult<Option<(u64, Duration)>> {
+        let threshold = match scope {
+            Scope::Account => self.config.max_failures,
+            Scope::Ip => self.config.ip_max_failures,
+        };
+
+        let failures = self
+            .store
+            .increment(&failures_key(scope, id), Duration::from_secs(self.config.failure_window))
+            .await?;
+        if failures < threshold {
+            return Ok(None);
+        }
+
+        // Le niveau de récidive survit au verrou pour que le suivant soit plus long
+        let level = self
+            .store
+            .increment(&level_key(scope, id), Duration::from_secs(self.config.max_duration))
+            .await?;
+        let duration = lockout_duration(&self.config, level);
+
+        self.store.set(&lock_key(scope, id), level, duration).await?;
+        // Marque le verrou pour journaliser sa levée ; survit au verrou aussi longtemps que la récidive
+        self.store
+            .set(
+                &pending_unlock_key(scope, id),
+                level,
+                duration + Duration::from_secs(self.config.max_duration),
+            )
+            .await?;
+        self.store.delete(&failures_key(scope, id)).await?;
+
+        Ok(Some((level, duration)))
+    }
+
+    /// Vrai une seule fois après l'expiration d'un verrou, pour une cible sans verrou actif
+    async fn take_expired_lock(&self, scope: Scope, id: &str) -> bool {
+        match self.store.delete(&pending_unlock_key(scope, id)).await {
+            Ok(expired) => expired,
+            Err(e) => {
+                warn!("Lockout store unavailable: {}", e);
+                false
+            }
+        }
+    }
+}
+
+/// Durée du verrou pour la n-ième récidive : base × 2^(n-1), plafonnée
+pub fn lockout_duration(config: &LockoutConfig, level: u64) -> Duration {
+    let factor = 1u64
+        .checked_shl(level.saturating_sub(1).min(u32::MAX as u64) as u32)
+        .unwrap_or(u64::MAX);
+
+    Duration::from_secs(config.base_duration.saturating_mul(factor).min(config.max_duration))
+}
+
+fn targets(account: Option<LoginAccount<'_>>, client: &ClientInfo) -> Vec<(Scope, String)> {
+    let mut targets = Vec::with_capacity(2);
+    if let Some(account) = account {
+        targets.push((Scope::Account, account.target()));
+    }
+    if let Some(ip) = client.ip_address.as_deref() {
+        targets.push((Scope::Ip, ip.to_string()));
+    }
+    targets
+}
+
+fn failures_key(scope: Scope, id: &str) -> String {
+    format!("login:failures:{}:{}", scope.as_str(), id)
+}
+
+fn level_key(scope: Scope, id: &str) -> String {
+    format!("login:level:{}:{}", scope.as_str(), id)
+}
+
+fn lock_key(scope: Scope, id: &str) -> String {
+    format!("login:lock:{}:{}", scope.as_str(), id)
+}
+
+fn pending_unlock_key(scope: Scope, id: &str) -> String {
+    format!("login:pending_unlock:{}:{}", scope.as_str(), id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::store::MemoryStore;
+
+    fn config() -> LockoutConfig {
+        LockoutConfig {
+            max_failures: 3,
+            ip_max_failures: 10,
+            failure_window: 900,
+            base_duration: 60,
+            max_duration: 3600,
+        }
+    }
+
+    #[test]
+    fn test_lockout_duration_grows_exponentially() {
+        let config = config();
+        assert_eq!(lockout_duration(&config, 1), Duration::from_secs(60));
+        assert_eq!(lockout_duration(&config, 2), Duration::from_secs(120));
+        assert_eq!(lockout_duration(&config, 4), Duration::from_secs(480));
+        assert_eq!(lockout_duration(&config, 10), Duration::from_secs(3600));
+        assert_eq!(lockout_duration(&config, u64::MAX), Duration::from_secs(3600));
+    }
+
+    /// Pool jamais connecté : les vérifications testées ne journalisent rien
+    fn pool() -> PgPool {
+        PgPool::connect_lazy("postgres://localhost/unused").unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_lock_after_threshold() {
+        let guard = LoginGuard::new(Arc::new(MemoryStore::default()), &config());
+        let user_id = Uuid::new_v4();
+        let client = ClientInfo::default();
+
+        for _ in 0..2 {
+            assert_eq!(guard.register_failure(Scope::Account, &user_id.to_string()).await.unwrap(), None);
+        }
+        guard.check(&pool(), Some(LoginAccount::Known(user_id)), &client).await.unwrap();
+
+        let lock = guard.register_failure(Scope::Account, &user_id.to_string()).await.unwrap();
+        assert_eq!(lock, Some((1, Duration::from_secs(60))));
+        assert!(matches!(
+            guard.check(&pool(), Some(LoginAccount::Known(user_id)), &client).await,
+            Err(AppError::TooManyRequests(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_identifier_locks_like_an_account() {
+        let guard = LoginGuard::new(Arc::new(MemoryStore::default()), &config());
+        let client = ClientInfo::default();
+        let target = LoginAccount::Unknown("Ghost@Example.com ").target();
+
+        for _ in 0..3 {
+            guard.register_failure(Scope::Account, &target).await.unwrap();
+        }
+
+        // Même refus qu'un compte existant, quelle que soit la casse saisie
+        assert!(matches!(
+            guard.check(&pool(), Some(LoginAccount::Unknown("ghost@example.com")), &client).await,
+            Err(AppError::TooManyRequests(_))
+        ));
+        guard.check(&pool(), Some(LoginAccount::Unknown("other@example.com")), &client).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_lock_is_reported_once() {
+        let guard = LoginGuard::new(
+            Arc::new(MemoryStore::default()),
+            &LockoutConfig {
+                base_duration: 0,
+                ..config()
+            },
+        );
+        let ip = "203.0.113.7";
+
+        assert!(!guard.take_expired_lock(Scope::Ip, ip).await);
+        for _ in 0..10 {
+            guard.register_failure(Scope::Ip, ip).await.unwrap();
+        }
+
+        // Verrou de durée nulle : déjà expiré, sa levée n'est signalée qu'une fois
+        assert!(guard.take_expired_lock(Scope::Ip, ip).await);
+        assert!(!guard.take_expired_lock(Scope::Ip, ip).await);
+        assert_eq!(Scope::Ip.locked_event(), SecurityEventType::IpBlocked);
+    }
+}
@@ -0,0 +1,290 @@
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::models::{
+    GuestMigrationResponse, GuestToUserMigrationRequest, LoginResponse, SessionType, User,
+    UserMigration,
+};
+use crate::services::email::EmailService;
+use crate::services::oauth::{self, LinkedIdentity};
+use crate::services::{auth, project_member, user, verification};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::password::PasswordHasher;
+use crate::utils::request::ClientInfo;
+
+/// Contenu possédé par l'invité, compté entité par entité
+///
+/// Le compte garde l'identifiant de l'invité : le contenu et toutes les autres
+/// références lui restent attachés sans être réécrits.
+const OWNED_ENTITIES: &[(&str, &str)] = &[
+    ("projects", "SELECT COUNT(*) FROM projects WHERE owner_id = $1"),
+    ("notes", "SELECT COUNT(*) FROM project_notes WHERE author_id = $1"),
+    ("snippets", "SELECT COUNT(*) FROM project_snippets WHERE author_id = $1"),
+    ("public_snippets", "SELECT COUNT(*) FROM public_snippets WHERE author_id = $1"),
+    ("tasks", "SELECT COUNT(*) FROM tasks WHERE author_id = $1"),
+    ("comments", "SELECT COUNT(*) FROM comments WHERE author_id = $1"),
+];
+
+/// Journal des étapes stocké dans `user_migrations.migration_log`
+#[derive(Debug, Default)]
+struct MigrationLog(Vec<Value>);
+
+impl MigrationLog {
+    fn step(&mut self, step: &str, details: Value) {
+        self.0.push(json!({ "step": step, "at": Utc::now(), "details": details }));
+    }
+
+    fn to_value(&self) -> Value {
+        Value::Array(self.0.clone())
+    }
+}
+
+/// Identifiants du compte à créer, ou de l'invité à convertir
+#[derive(Debug, Clone)]
+pub struct NewAccount {
+    pub email: String,
+    pub username: String,
+    pub display_name: String,
+    /// Absent pour un compte créé via un fournisseur OAuth
+    pub password_hash: Option<String>,
+    /// Adresse déjà vérifiée par le fournisseur OAuth
+    pub email_verified: bool,
+    /// Identité externe liée au compte dans la même transaction
+    pub identity: Option<LinkedIdentity>,
+}
+
+/// Convertit l'invité de la session courante en compte `migrated`
+pub async fn migrate_guest(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    context: &AuthContext,
+    request: GuestToUserMigrationRequest,
+    client: &ClientInfo,
+) -> AppResult<GuestMigrationResponse> {
+    if !config.features.registration_enabled {
+        return Err(AppError::Forbidden("Registration is currently disabled".to_string()));
+    }
+    if !context.is_guest() || request.guest_id != context.user_id {
+        return Err(AppError::Forbidden("Only the guest itself can be migrated".to_string()));
+    }
+
+    request.validate()?;
+
+    let guest = user::find_by_id(pool, context.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Guest not found".to_string()))?;
+
+    if user::find_by_email(pool, &request.email).await?.is_some() {
+        return Err(AppError::Conflict("Email is already in use".to_string()));
+    }
+    if user::find_by_username(pool, &request.username).await?.is_some() {
+        return Err(AppError::Conflict("Username is already taken".to_string()));
+    }
+
+    let password_hash = PasswordHasher::from_config(config)
+        .hash(request.password.clone())
+        .await?;
+
+    let account = NewAccount {
+        email: request.email,
+        username: request.username,
+        display_name: request.display_name,
+        password_hash: Some(password_hash),
+        email_verified: false,
+        identity: None,
+    };
+
+    migrate_into_account(pool, config, email_service, &guest, account, client).await
+}
+
+/// Convertit l'invité en compte, sur place
+///
+/// La ligne `users` de l'invité devient le compte : son identifiant, et donc
+/// toutes les références vers lui, sont conservés. La conversion se fait dans une
+/// seule transaction. La ligne `user_migrations` est enregistrée au préalable
+/// pour qu'un échec reste consultable après le rollback.
+pub async fn migrate_into_account(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    guest: &User,
+    new_account: NewAccount,
+    client: &ClientInfo,
+) -> AppResult<GuestMigrationResponse> {
+    let anonymous_id = guest
+        .anonymous_id
+        .ok_or_else(|| AppError::Internal(format!("Guest {} has no anonymous_id", guest.id)))?;
+    let send_verification = config.features.email_verification && !new_account.email_verified;
+
+    let migration_id: Uuid = sqlx::query_scalar(
+        "INSERT INTO user_migrations (source_anonymous_id, migration_status) VALUES ($1, 'in_progress') RETURNING id",
+    )
+    .bind(anonymous_id)
+    .fetch_one(pool)
+    .await?;
+
+    let mut log = MigrationLog::default();
+    log.step("started", json!({ "guest_id": guest.id }));
+
+    let result: AppResult<(UserMigration, LoginResponse)> = async {
+        let mut tx = pool.begin().await?;
+
+        // Un invité déjà converti n'est plus de type `guest` : la conversion n'a lieu qu'une fois
+        let account = sqlx::query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET email = $2, username = $3, display_name = $4, password_hash = $5,
+                user_type = 'migrated', migrated_from_anonymous_id = anonymous_id, migrated_at = NOW(),
+                session_expires_at = NULL, is_verified = $6,
+                email_verified_at = CASE WHEN $6 THEN NOW() END, email_verification_sent_at = $7
+            WHERE id = $1 AND user_type = 'guest'
+            RETURNING *
+            "#,
+        )
+        .bind(guest.id)
+        .bind(&new_account.email)
+        .bind(&new_account.username)
+        .bind(&new_account.display_name)
+        .bind(&new_account.password_hash)
+        .bind(new_account.email_verified)
+        .bind(send_verification.then(Utc::now))
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                AppError::Conflict("Email or username is already in use".to_string())
+            }
+            e => AppError::Database(e),
+        })?
+        .ok_or_else(|| AppError::Conflict("This guest has already been converted to an account".to_string()))?;
+        log.step("account_converted", json!({ "user_id": account.id }));
+
+        if let Some(identity) = &new_account.identity {
+            oauth::link_identity(&mut tx, account.id, identity).await?;
+            log.step("identity_linked", json!({ "provider": identity.provider }));
+        }
+
+        project_member::start_held_invitations(&mut tx, config, &new_account.email).await?;
+
+        let mut counts = Map::new();
+        for (entity, statement) in OWNED_ENTITIES {
+            let migrated: i64 = sqlx::query_scalar(statement)
+                .bind(account.id)
+                .fetch_one(&mut *tx)
+                .await?;
+            counts.insert(entity.to_string(), json!(migrated));
+            log.step(&format!("{}_migrated", entity), json!({ "count": migrated }));
+        }
+
+        // Les sessions invité ne doivent pas survivre à la conversion
+        let revoked = sqlx::query(
+            "UPDATE user_sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+        )
+        .bind(account.id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        log.step("guest_sessions_revoked", json!({ "count": revoked }));
+
+        let session =
+            auth::create_session(&mut tx, config, &account, SessionType::Authenticated, client).await?;
+        log.step("completed", Value::Null);
+
+        let migration = sqlx::query_as::<_, UserMigration>(
+            r#"
+            UPDATE user_migrations
+            SET destination_user_id = $2,
+                migration_status = 'completed',
+                entities_migrated = $3,
+                migration_log = $4,
+                completed_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(migration_id)
+        .bind(account.id)
+        .bind(Value::Object(counts))
+        .bind(log.to_value())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        if send_verification {
+            verification::send_verification_email(config, email_service, &account)?;
+        }
+
+        let login = LoginResponse {
+            user: account.into_response(),
+            token: session.token,
+            refresh_token: session.refresh_token.unwrap_or_default(),
+            expires_at: session.expires_at,
+        };
+
+        Ok((migration, login))
+    }
+    .await;
+
+    match result {
+        Ok((migration, session)) => {
+            info!(migration_id = %migration.id, user_id = %session.user.id, "Guest migrated to account");
+            Ok(GuestMigrationResponse { migration, session })
+        }
+        Err(e) => {
+            warn!(migration_id = %migration_id, "Guest migration failed: {}", e);
+            log.step("rolled_back", Value::Null);
+
+            // Le statut est lisible par l'utilisateur : pas de détail interne
+            let error_message = match &e {
+                AppError::Database(_) | AppError::Internal(_) => "Internal error".to_string(),
+                e => e.to_string(),
+            };
+
+            sqlx::query(
+                r#"
+                UPDATE user_migrations
+                SET migration_status = 'failed', error_message = $2, migration_log = $3, completed_at = NOW()
+                WHERE id = $1
+                "#,
+            )
+            .bind(migration_id)
+            .bind(error_message)
+            .bind(log.to_value())
+            .execute(pool)
+            .await?;
+
+            Err(e)
+        }
+    }
+}
+
+/// Retourne une migration visible par l'utilisateur courant
+///
+/// Le compte converti garde l'`anonymous_id` de l'invité d'origine.
+pub async fn find_migration(
+    pool: &PgPool,
+    context: &AuthContext,
+    migration_id: Uuid,
+) -> AppResult<UserMigration> {
+    sqlx::query_as::<_, UserMigration>(
+        r#"
+        SELECT m.* FROM user_migrations m
+        WHERE m.id = $1
+          AND (m.destination_user_id = $2
+               OR m.source_anonymous_id = (SELECT anonymous_id FROM users WHERE id = $2))
+        "#,
+    )
+    .bind(migration_id)
+    .bind(context.user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Migration not found".to_string()))
+}
@@ -1,4 +1,22 @@
 pub mod auth;
 pub mod user;
 pub mod project;
+pub mod project_member;
+pub mod permission;
+pub mod project_copy;
+pub mod project_template;
+pub mod project_transfer;
 pub mod email;
+pub mod verification;
+pub mod public_snippet;
+pub mod migration;
+pub mod session;
+pub mod password_reset;
+pub mod audit;
+pub mod two_factor;
+pub mod lockout;
+pub mod oauth;
+pub mod personal_token;
+pub mod avatar;
+pub mod export;
+pub mod account_deletion;
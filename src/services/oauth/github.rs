@@ -0,0 +1,116 @@
+use async_trait::async_trait;
+use reqwest::Url;
+use serde::Deserialize;
+
+use super::{provider_unavailable, request_access_token, ExternalIdentity, OAuthProvider};
+use crate::config::OAuthClientConfig;
+use crate::utils::error::AppResult;
+
+const AUTHORIZE_URL: &str = "https://github.com/login/oauth/authorize";
+const TOKEN_URL: &str = "https://github.com/login/oauth/access_token";
+const API_URL: &str = "https://api.github.com";
+
+/// Connexion avec un compte GitHub
+pub struct GitHubProvider {
+    client_id: String,
+    client_secret: String,
+    http: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    id: u64,
+    login: String,
+    name: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+impl GitHubProvider {
+    pub fn new(config: &OAuthClientConfig) -> Self {
+        Self {
+            client_id: config.client_id.clone(),
+            client_secret: config.client_secret.clone(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    async fn get<T: for<'de> Deserialize<'de>>(&self, path: &str, token: &str) -> AppResult<T> {
+        self.http
+            .get(format!("{}{}", API_URL, path))
+            .bearer_auth(token)
+            // L'API GitHub refuse les requêtes sans User-Agent
+            .header(reqwest::header::USER_AGENT, "ETTU")
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(provider_unavailable)?
+            .json()
+            .await
+            .map_err(provider_unavailable)
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for GitHubProvider {
+    fn name(&self) -> &str {
+        "github"
+    }
+
+    fn authorization_url(&self, state: &str, code_challenge: &str, redirect_uri: &str) -> String {
+        Url::parse_with_params(
+            AUTHORIZE_URL,
+            &[
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", redirect_uri),
+                ("scope", "read:user user:email"),
+                ("state", state),
+                ("code_challenge", code_challenge),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .expect("static GitHub URL is valid")
+        .into()
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> AppResult<ExternalIdentity> {
+        let token = request_access_token(
+            &self.http,
+            TOKEN_URL,
+            &self.client_id,
+            &self.client_secret,
+            code,
+            code_verifier,
+            redirect_uri,
+        )
+        .await?;
+
+        let user: GitHubUser = self.get("/user", &token).await?;
+        let emails: Vec<GitHubEmail> = self.get("/user/emails", &token).await?;
+
+        // Adresse principale si elle est vérifiée, sinon n'importe quelle adresse vérifiée
+        let email = emails
+            .iter()
+            .find(|e| e.primary && e.verified)
+            .or_else(|| emails.iter().find(|e| e.verified))
+            .or_else(|| emails.iter().find(|e| e.primary));
+
+        Ok(ExternalIdentity {
+            provider_user_id: user.id.to_string(),
+            email: email.map(|e| e.email.clone()),
+            email_verified: email.is_some_and(|e| e.verified),
+            username: Some(user.login),
+            display_name: user.name,
+        })
+    }
+}
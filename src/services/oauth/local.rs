@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use reqwest::Url;
+
+use super::{code_challenge, ExternalIdentity, OAuthProvider};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::token::generate_token;
+
+/// Fournisseur simulé, sans réseau, pour le développement et les tests
+///
+/// Le consentement est immédiat : l'URL d'autorisation pointe directement sur
+/// la redirection, avec un code accordé à une identité de développement. Les
+/// tests peuvent accorder des codes pour d'autres identités avec `grant`.
+#[derive(Clone, Default)]
+pub struct LocalProvider {
+    grants: Arc<Mutex<HashMap<String, (String, ExternalIdentity)>>>,
+}
+
+impl LocalProvider {
+    /// Identité utilisée par le consentement automatique
+    pub fn default_identity() -> ExternalIdentity {
+        ExternalIdentity {
+            provider_user_id: "local-dev".to_string(),
+            email: Some("dev@ettu.local".to_string()),
+            email_verified: true,
+            username: Some("dev".to_string()),
+            display_name: Some("Local Dev".to_string()),
+        }
+    }
+
+    /// Simule le consentement de l'utilisateur et retourne le code à échanger
+    pub fn grant(&self, code_challenge: &str, identity: ExternalIdentity) -> String {
+        let code = generate_token();
+        self.grants
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(code.clone(), (code_challenge.to_string(), identity));
+        code
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for LocalProvider {
+    fn name(&self) -> &str {
+        "local"
+    }
+
+    fn authorization_url(&self, state: &str, code_challenge: &str, redirect_uri: &str) -> String {
+        let code = self.grant(code_challenge, Self::default_identity());
+        Url::parse_with_params(redirect_uri, &[("code", code.as_str()), ("state", state)])
+            .map(String::from)
+            .unwrap_or_else(|_| format!("{}?code={}&state={}", redirect_uri, code, state))
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        _redirect_uri: &str,
+    ) -> AppResult<ExternalIdentity> {
+        let invalid_grant = || AppError::BadRequest("Invalid authorization code".to_string());
+
+        let (challenge, identity) = self
+            .grants
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(code)
+            .ok_or_else(invalid_grant)?;
+
+        if challenge != code_challenge(code_verifier) {
+            return Err(invalid_grant());
+        }
+
+        Ok(identity)
+    }
+}
@@ -0,0 +1,596 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use data_encoding::BASE64URL_NOPAD;
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::models::{LoginOutcome, OAuthAuthorizeResponse, OAuthCallbackRequest, User};
+use crate::services::email::EmailService;
+use crate::services::migration::{self, NewAccount};
+use crate::services::{auth, project_member, two_factor, user, verification};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::request::ClientInfo;
+use crate::utils::token::{generate_token, hash_token};
+
+pub mod github;
+pub mod local;
+pub mod oidc;
+
+pub use github::GitHubProvider;
+pub use local::LocalProvider;
+pub use oidc::OidcProvider;
+
+/// Durée laissée à l'utilisateur pour donner son consentement
+pub const AUTHORIZATION_TTL_MINUTES: i64 = 10;
+
+/// Cookie HttpOnly liant une autorisation au navigateur qui l'a lancée
+pub const BROWSER_BINDING_COOKIE: &str = "oauth_binding";
+
+/// Identité retournée par un fournisseur après l'échange du code
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExternalIdentity {
+    pub provider_user_id: String,
+    pub email: Option<String>,
+    pub email_verified: bool,
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+}
+
+/// Identité externe à lier à un compte
+#[derive(Debug, Clone)]
+pub struct LinkedIdentity {
+    pub provider: String,
+    pub provider_user_id: String,
+    pub email: Option<String>,
+}
+
+/// Client, navigateur et session d'où provient un callback
+#[derive(Debug, Clone, Copy)]
+pub struct CallbackOrigin<'a> {
+    pub client: &'a ClientInfo,
+    pub context: Option<&'a AuthContext>,
+    /// Valeur du cookie [`BROWSER_BINDING_COOKIE`]
+    pub browser_nonce: Option<&'a str>,
+}
+
+impl CallbackOrigin<'_> {
+    /// Vérifie que le callback vient du navigateur et de la session qui ont lancé l'autorisation
+    fn is_initiator(&self, binding_hash: &str, initiator_id: Option<Uuid>) -> bool {
+        let same_browser = self
+            .browser_nonce
+            .is_some_and(|nonce| hash_token(nonce) == binding_hash);
+        same_browser && self.context.map(|context| context.user_id) == initiator_id
+    }
+}
+
+/// Fournisseur OAuth2 supportant le flux authorization code + PKCE (S256)
+#[async_trait]
+pub trait OAuthProvider: Send + Sync {
+    /// Nom du fournisseur dans les routes et dans `user_identities.provider`
+    fn name(&self) -> &str;
+
+    /// URL de la page de consentement
+    fn authorization_url(&self, state: &str, code_challenge: &str, redirect_uri: &str) -> String;
+
+    /// Échange le code contre l'identité de l'utilisateur
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> AppResult<ExternalIdentity>;
+}
+
+/// Fournisseurs activés par la configuration
+#[derive(Clone, Default)]
+pub struct OAuthProviders {
+    providers: HashMap<String, Arc<dyn OAuthProvider>>,
+}
+
+impl OAuthProviders {
+    pub fn from_config(config: &Config) -> Self {
+        let mut providers = Self::default();
+        if let Some(github) = &config.oauth.github {
+            providers = providers.with(GitHubProvider::new(github));
+        }
+        if let Some(oidc) = &config.oauth.oidc {
+            providers = providers.with(OidcProvider::new(oidc));
+        }
+        if config.oauth.local_provider {
+            providers = providers.with(LocalProvider::default());
+        }
+        providers
+    }
+
+    pub fn with(mut self, provider: impl OAuthProvider + 'static) -> Self {
+        self.providers
+            .insert(provider.name().to_string(), Arc::new(provider));
+        self
+    }
+
+    pub fn get(&self, name: &str) -> AppResult<&Arc<dyn OAuthProvider>> {
+        self.providers
+            .get(name)
+            .ok_or_else(|| AppError::NotFound(format!("Unknown OAuth provider: {}", name)))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.providers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+/// Challenge PKCE dérivé du verifier (méthode S256, RFC 7636)
+pub fn code_challenge(code_verifier: &str) -> String {
+    BASE64URL_NOPAD.encode(&Sha256::digest(code_verifier.as_bytes()))
+}
+
+/// Échange un code d'autorisation contre un access token (RFC 6749 §4.1.3)
+async fn request_access_token(
+    http: &reqwest::Client,
+    token_url: &str,
+    client_id: &str,
+    client_secret: &str,
+    code: &str,
+    code_verifier: &str,
+    redirect_uri: &str,
+) -> AppResult<String> {
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        access_token: Option<String>,
+        error: Option<String>,
+    }
+
+    let response: TokenResponse = http
+        .post(token_url)
+        .header(reqwest::header::ACCEPT, "application/json")
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("code_verifier", code_verifier),
+        ])
+        .send()
+        .await
+        .map_err(provider_unavailable)?
+        .json()
+        .await
+        .map_err(provider_unavailable)?;
+
+    match (response.access_token, response.error) {
+        (Some(token), None) => Ok(token),
+        (_, error) => Err(AppError::BadRequest(format!(
+            "OAuth provider rejected the authorization code: {}",
+            error.unwrap_or_else(|| "unknown_error".to_string())
+        ))),
+    }
+}
+
+fn provider_unavailable(e: reqwest::Error) -> AppError {
+    AppError::ServiceUnavailable(format!("OAuth provider is unavailable: {}", e))
+}
+
+fn redirect_uri(config: &Config, provider: &str) -> String {
+    format!("{}/{}", config.oauth.redirect_url.trim_end_matches('/'), provider)
+}
+
+/// Prépare une autorisation : state et code_verifier sont conservés côté serveur
+///
+/// Lancée depuis une session invité, la connexion convertit l'invité ; depuis
+/// une session de compte, elle lie l'identité à ce compte.
+///
+/// Retourne aussi le nonce à déposer dans le cookie [`BROWSER_BINDING_COOKIE`] :
+/// seul le navigateur qui a lancé l'autorisation peut la terminer.
+pub async fn authorize(
+    pool: &PgPool,
+    config: &Config,
+    providers: &OAuthProviders,
+    provider_name: &str,
+    context: Option<&AuthContext>,
+) -> AppResult<(OAuthAuthorizeResponse, String)> {
+    let provider = providers.get(provider_name)?;
+
+    let state = generate_token();
+    let code_verifier = generate_token();
+    let browser_nonce = generate_token();
+    let expires_at = Utc::now() + Duration::minutes(AUTHORIZATION_TTL_MINUTES);
+
+    sqlx::query(
+        r#"
+        INSERT INTO oauth_authorization_requests
+            (state_hash, provider, code_verifier, initiator_id, browser_binding_hash, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(hash_token(&state))
+    .bind(provider.name())
+    .bind(&code_verifier)
+    .bind(context.map(|context| context.user_id))
+    .bind(hash_token(&browser_nonce))
+    .bind(expires_at)
+    .execute(pool)
+    .await?;
+
+    let response = OAuthAuthorizeResponse {
+        authorization_url: provider.authorization_url(
+            &state,
+            &code_challenge(&code_verifier),
+            &redirect_uri(config, provider.name()),
+        ),
+        state,
+    };
+
+    Ok((response, browser_nonce))
+}
+
+/// Termine une autorisation et ouvre une session
+///
+/// Le callback doit venir du navigateur qui a lancé l'autorisation (cookie de
+/// liaison) et de la même session : sinon un tiers pourrait faire terminer à sa
+/// victime une autorisation qu'il a lui-même lancée.
+///
+/// Le compte est choisi dans cet ordre : identité déjà liée, compte à l'origine
+/// de l'autorisation, compte existant avec la même adresse vérifiée, conversion
+/// de l'invité à l'origine de l'autorisation, et enfin nouveau compte.
+pub async fn callback(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    providers: &OAuthProviders,
+    provider_name: &str,
+    origin: CallbackOrigin<'_>,
+    request: OAuthCallbackRequest,
+) -> AppResult<LoginOutcome> {
+    let provider = providers.get(provider_name)?;
+    let client = origin.client;
+    let invalid_state = || AppError::BadRequest("Invalid or expired OAuth state".to_string());
+
+    // Le state est consommé avant l'échange : un callback rejoué échoue
+    let pending: Option<(String, Option<Uuid>, String, DateTime<Utc>)> = sqlx::query_as(
+        r#"
+        DELETE FROM oauth_authorization_requests
+        WHERE state_hash = $1 AND provider = $2
+        RETURNING code_verifier, initiator_id, browser_binding_hash, expires_at
+        "#,
+    )
+    .bind(hash_token(&request.state))
+    .bind(provider.name())
+    .fetch_optional(pool)
+    .await?;
+
+    let (code_verifier, initiator_id, binding_hash, expires_at) = pending.ok_or_else(invalid_state)?;
+    if expires_at <= Utc::now() {
+        return Err(invalid_state());
+    }
+    if !origin.is_initiator(&binding_hash, initiator_id) {
+        return Err(invalid_state());
+    }
+
+    let identity = provider
+        .exchange_code(&request.code, &code_verifier, &redirect_uri(config, provider.name()))
+        .await?;
+    let linked = LinkedIdentity {
+        provider: provider.name().to_string(),
+        provider_user_id: identity.provider_user_id.clone(),
+        email: identity.email.clone(),
+    };
+
+    let initiator = match initiator_id {
+        Some(id) => user::find_by_id(pool, id).await?,
+        None => None,
+    };
+
+    if let Some(existing) = find_linked_user(pool, &linked).await? {
+        if let Some(initiator) = initiator.as_ref().filter(|u| u.is_registered()) {
+            if initiator.id != existing.id {
+                return Err(AppError::Conflict(
+                    "This identity is already linked to another account".to_string(),
+                ));
+            }
+        }
+        return sign_in(pool, config, existing, &linked, client).await;
+    }
+
+    if let Some(initiator) = initiator.clone().filter(|u| u.is_registered()) {
+        let mut conn = pool.acquire().await?;
+        link_identity(&mut conn, initiator.id, &linked).await?;
+        info!(user_id = %initiator.id, provider = %linked.provider, "OAuth identity linked");
+        return sign_in(pool, config, initiator, &linked, client).await;
+    }
+
+    let email = identity.email.clone().ok_or_else(|| {
+        AppError::BadRequest("The provider did not return an email address".to_string())
+    })?;
+
+    if let Some(existing) = user::find_by_email(pool, &email).await? {
+        // L'adresse doit être vérifiée des deux côtés : par le fournisseur, sinon rien
+        // ne prouve que le compte lui appartient, et localement, sinon le compte a pu
+        // être créé avec l'adresse d'un autre
+        if !identity.email_verified || !existing.is_registered() || existing.email_verified_at.is_none() {
+            return Err(AppError::Conflict(
+                "An account already exists for this email; sign in to link this provider".to_string(),
+            ));
+        }
+
+        let mut conn = pool.acquire().await?;
+        link_identity(&mut conn, existing.id, &linked).await?;
+        info!(user_id = %existing.id, provider = %linked.provider, "OAuth identity linked by verified email");
+        return sign_in(pool, config, existing, &linked, client).await;
+    }
+
+    if !config.features.registration_enabled {
+        return Err(AppError::Forbidden("Registration is currently disabled".to_string()));
+    }
+
+    let new_account = NewAccount {
+        username: unique_username(pool, &identity, &email).await?,
+        display_name: identity
+            .display_name
+            .clone()
+            .or_else(|| identity.username.clone())
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default().to_string()),
+        email,
+        password_hash: None,
+        email_verified: identity.email_verified,
+        identity: Some(linked),
+    };
+
+    if let Some(guest) = initiator.filter(|u| u.is_guest()) {
+        let migrated =
+            migration::migrate_into_account(pool, config, email_service, &guest, new_account, client)
+                .await?;
+        return Ok(LoginOutcome::Authenticated(Box::new(migrated.session)));
+    }
+
+    let send_verification = config.features.email_verification && !new_account.email_verified;
+    let account = create_account(pool, config, new_account).await?;
+    if send_verification {
+        verification::send_verification_email(config, email_service, &account)?;
+    }
+
+    auth::complete_login(pool, config, &account, client)
+        .await
+        .map(|login| LoginOutcome::Authenticated(Box::new(login)))
+}
+
+/// Lie une identité externe à un compte
+pub async fn link_identity(conn: &mut PgConnection, user_id: Uuid, identity: &LinkedIdentity) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_identities (user_id, provider, provider_user_id, email)
+        VALUES ($1, $2, $3, $4)
+        "#,
+    )
+    .bind(user_id)
+    .bind(&identity.provider)
+    .bind(&identity.provider_user_id)
+    .bind(&identity.email)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Conflict("This identity is already linked to an account".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    Ok(())
+}
+
+async fn find_linked_user(pool: &PgPool, identity: &LinkedIdentity) -> AppResult<Option<User>> {
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        SELECT u.* FROM users u
+        JOIN user_identities i ON i.user_id = u.id
+        WHERE i.provider = $1 AND i.provider_user_id = $2
+        "#,
+    )
+    .bind(&identity.provider)
+    .bind(&identity.provider_user_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(user)
+}
+
+/// Ouvre la session d'un compte existant, en passant par la 2FA s'il l'exige
+async fn sign_in(
+    pool: &PgPool,
+    config: &Config,
+    user: User,
+    identity: &LinkedIdentity,
+    client: &ClientInfo,
+) -> AppResult<LoginOutcome> {
+    if !user.is_active {
+        return Err(AppError::Forbidden("Account is disabled".to_string()));
+    }
+
+    sqlx::query("UPDATE user_identities SET last_login_at = NOW() WHERE provider = $1 AND provider_user_id = $2")
+        .bind(&identity.provider)
+        .bind(&identity.provider_user_id)
+        .execute(pool)
+        .await?;
+
+    if let Some(challenge) = two_factor::challenge_for(pool, config, &user).await? {
+        return Ok(LoginOutcome::MfaRequired(challenge));
+    }
+
+    auth::complete_login(pool, config, &user, client)
+        .await
+        .map(|login| LoginOutcome::Authenticated(Box::new(login)))
+}
+
+async fn create_account(pool: &PgPool, config: &Config, account: NewAccount) -> AppResult<User> {
+    let mut tx = pool.begin().await?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        INSERT INTO users
+            (email, username, display_name, user_type, is_verified, email_verified_at, email_verification_sent_at)
+        VALUES ($1, $2, $3, 'registered', $4, CASE WHEN $4 THEN NOW() END, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(&account.email)
+    .bind(&account.username)
+    .bind(&account.display_name)
+    .bind(account.email_verified)
+    .bind((config.features.email_verification && !account.email_verified).then(Utc::now))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Conflict("Email or username is already in use".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    if let Some(identity) = &account.identity {
+        link_identity(&mut tx, user.id, identity).await?;
+    }
+
+    project_member::start_held_invitations(&mut tx, config, &account.email).await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, "User registered through OAuth");
+
+    Ok(user)
+}
+
+/// Nom d'utilisateur libre dérivé du login du fournisseur ou de l'email
+async fn unique_username(pool: &PgPool, identity: &ExternalIdentity, email: &str) -> AppResult<String> {
+    let base = sanitize_username(
+        identity
+            .username
+            .as_deref()
+            .unwrap_or_else(|| email.split('@').next().unwrap_or_default()),
+    );
+
+    if user::find_by_username(pool, &base).await?.is_none() {
+        return Ok(base);
+    }
+
+    for _ in 0..5 {
+        let mut suffix = [0u8; 3];
+        rand::thread_rng().fill_bytes(&mut suffix);
+        let candidate = format!("{}-{}", base, hex::encode(suffix));
+        if user::find_by_username(pool, &candidate).await?.is_none() {
+            return Ok(candidate);
+        }
+    }
+
+    Err(AppError::Conflict("Could not allocate a username".to_string()))
+}
+
+/// Restreint un login externe aux caractères acceptés par `validate_username`
+fn sanitize_username(value: &str) -> String {
+    let sanitized: String = value
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .take(40)
+        .collect();
+
+    if sanitized.len() < 3 {
+        format!("user-{}", sanitized)
+    } else {
+        sanitized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_challenge_rfc7636_vector() {
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_sanitize_username() {
+        assert_eq!(sanitize_username("octo.cat"), "octocat");
+        assert_eq!(sanitize_username("jean-luc_42"), "jean-luc_42");
+        assert_eq!(sanitize_username("é"), "user-");
+        assert_eq!(sanitize_username(&"a".repeat(60)).len(), 40);
+    }
+
+    #[test]
+    fn test_callback_bound_to_initiating_browser_and_session() {
+        use crate::models::{SessionType, UserRole, UserType};
+
+        let nonce = generate_token();
+        let binding_hash = hash_token(&nonce);
+        let context = AuthContext {
+            user_id: Uuid::new_v4(),
+            session_id: Uuid::new_v4(),
+            session_type: SessionType::Guest,
+            user_type: UserType::Guest,
+            role: UserRole::User,
+            is_verified: false,
+            scopes: None,
+        };
+
+        let client = ClientInfo::default();
+        let origin = |context, browser_nonce| CallbackOrigin {
+            client: &client,
+            context,
+            browser_nonce,
+        };
+
+        assert!(origin(None, Some(&nonce)).is_initiator(&binding_hash, None));
+        assert!(origin(Some(&context), Some(&nonce)).is_initiator(&binding_hash, Some(context.user_id)));
+
+        // Autre navigateur : cookie absent ou différent
+        assert!(!origin(None, None).is_initiator(&binding_hash, None));
+        let other_nonce = generate_token();
+        assert!(!origin(None, Some(&other_nonce)).is_initiator(&binding_hash, None));
+        // Même navigateur, mais session différente de celle qui a lancé l'autorisation
+        assert!(!origin(Some(&context), Some(&nonce)).is_initiator(&binding_hash, Some(Uuid::new_v4())));
+        assert!(!origin(None, Some(&nonce)).is_initiator(&binding_hash, Some(context.user_id)));
+        assert!(!origin(Some(&context), Some(&nonce)).is_initiator(&binding_hash, None));
+    }
+
+    #[tokio::test]
+    async fn test_local_provider_flow() {
+        let providers = OAuthProviders::default().with(LocalProvider::default());
+        let provider = providers.get("local").unwrap();
+
+        let verifier = generate_token();
+        let identity = ExternalIdentity {
+            provider_user_id: "42".to_string(),
+            email: Some("octo@example.com".to_string()),
+            email_verified: true,
+            ..Default::default()
+        };
+
+        let local = LocalProvider::default();
+        let code = local.grant(&code_challenge(&verifier), identity.clone());
+        assert!(local.exchange_code(&code, "wrong-verifier", "").await.is_err());
+
+        let code = local.grant(&code_challenge(&verifier), identity.clone());
+        assert_eq!(local.exchange_code(&code, &verifier, "").await.unwrap(), identity);
+        // Un code ne s'échange qu'une fois
+        assert!(local.exchange_code(&code, &verifier, "").await.is_err());
+
+        assert!(providers.get("github").is_err());
+        assert!(provider
+            .authorization_url("state", "challenge", "http://localhost/cb")
+            .starts_with("http://localhost/cb?"));
+    }
+}
@@ -0,0 +1,98 @@
+use async_trait::async_trait;
+use reqwest::Url;
+use serde::Deserialize;
+
+use super::{provider_unavailable, request_access_token, ExternalIdentity, OAuthProvider};
+use crate::config::OidcConfig;
+use crate::utils::error::{AppError, AppResult};
+
+/// Fournisseur OpenID Connect générique, configuré par ses endpoints
+pub struct OidcProvider {
+    config: OidcConfig,
+    http: reqwest::Client,
+}
+
+/// Claims standard de l'endpoint userinfo (OIDC Core §5.1)
+#[derive(Deserialize)]
+struct UserInfo {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    preferred_username: Option<String>,
+    name: Option<String>,
+}
+
+impl OidcProvider {
+    pub fn new(config: &OidcConfig) -> Self {
+        Self {
+            config: config.clone(),
+            http: reqwest::Client::new(),
+        }
+    }
+}
+
+#[async_trait]
+impl OAuthProvider for OidcProvider {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    fn authorization_url(&self, state: &str, code_challenge: &str, redirect_uri: &str) -> String {
+        let params = [
+            ("response_type", "code"),
+            ("client_id", self.config.client_id.as_str()),
+            ("redirect_uri", redirect_uri),
+            ("scope", self.config.scopes.as_str()),
+            ("state", state),
+            ("code_challenge", code_challenge),
+            ("code_challenge_method", "S256"),
+        ];
+
+        Url::parse_with_params(&self.config.authorization_url, &params)
+            .map(String::from)
+            .unwrap_or_else(|_| self.config.authorization_url.clone())
+    }
+
+    async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        redirect_uri: &str,
+    ) -> AppResult<ExternalIdentity> {
+        let token = request_access_token(
+            &self.http,
+            &self.config.token_url,
+            &self.config.client_id,
+            &self.config.client_secret,
+            code,
+            code_verifier,
+            redirect_uri,
+        )
+        .await?;
+
+        let info: UserInfo = self
+            .http
+            .get(&self.config.userinfo_url)
+            .bearer_auth(token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(provider_unavailable)?
+            .json()
+            .await
+            .map_err(provider_unavailable)?;
+
+        if info.sub.is_empty() {
+            return Err(AppError::BadRequest("OAuth provider returned no subject".to_string()));
+        }
+
+        Ok(ExternalIdentity {
+            provider_user_id: info.sub,
+            email: info.email,
+            email_verified: info.email_verified,
+            username: info.preferred_username,
+            display_name: info.name,
+        })
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::models::{ForgotPasswordRequest, ResetPasswordRequest, SecurityEventType, SecuritySeverity};
+use crate::services::audit::{self, SecurityEvent};
+use crate::services::email::EmailService;
+use crate::services::user;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::password::PasswordHasher;
+use crate::utils::request::ClientInfo;
+use crate::utils::token::{generate_token, hash_token};
+
+/// Durée de validité d'un lien de réinitialisation
+pub const PASSWORD_RESET_TTL_MINUTES: i64 = 60;
+
+/// Envoie un lien de réinitialisation si l'email correspond à un compte actif
+///
+/// La réponse est identique que le compte existe ou non, et l'email part en
+/// arrière-plan pour ne pas trahir son existence par le temps de réponse.
+pub async fn forgot_password(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    request: ForgotPasswordRequest,
+    client: &ClientInfo,
+) -> AppResult<()> {
+    let Some(user) = user::find_by_email(pool, &request.email).await? else {
+        return Ok(());
+    };
+    if !user.can_login() || !user.is_active {
+        return Ok(());
+    }
+    let Some(email) = user.email.clone() else {
+        return Ok(());
+    };
+
+    let token = generate_token();
+    let expires_at = Utc::now() + Duration::minutes(PASSWORD_RESET_TTL_MINUTES);
+
+    sqlx::query(
+        r#"
+        INSERT INTO password_reset_tokens (user_id, token_hash, expires_at, ip_address)
+        VALUES ($1, $2, $3, $4::inet)
+        "#,
+    )
+    .bind(user.id)
+    .bind(hash_token(&token))
+    .bind(expires_at)
+    .bind(client.ip_address.as_deref())
+    .execute(pool)
+    .await?;
+
+    let link = format!(
+        "{}/reset-password?token={}",
+        config.server.frontend_url.trim_end_matches('/'),
+        token
+    );
+    let name = user
+        .display_name
+        .clone()
+        .or_else(|| user.username.clone())
+        .unwrap_or_default();
+
+    let email_service = email_service.clone();
+    let user_id = user.id;
+    tokio::spawn(async move {
+        if let Err(e) = email_service
+            .send_password_reset(&email, &name, &link, PASSWORD_RESET_TTL_MINUTES)
+            .await
+        {
+            warn!(user_id = %user_id, "Failed to send password reset email: {}", e);
+        }
+    });
+
+    info!(user_id = %user.id, "Password reset requested");
+    Ok(())
+}
+
+/// Consomme un token de réinitialisation et remplace le mot de passe
+///
+/// Toutes les sessions existantes sont révoquées et les autres tokens en cours
+/// invalidés.
+pub async fn reset_password(
+    pool: &PgPool,
+    config: &Config,
+    request: ResetPasswordRequest,
+    client: &ClientInfo,
+) -> AppResult<()> {
+    request.validate()?;
+
+    let invalid_token = || AppError::BadRequest("Invalid or expired reset token".to_string());
+
+    // Hash calculé avant d'ouvrir la transaction pour ne pas garder de verrou pendant bcrypt
+    let password_hash = PasswordHasher::from_config(config)
+        .hash(request.new_password)
+        .await?;
+
+    let mut tx = pool.begin().await?;
+
+    let reset: Option<(Uuid, Uuid)> = sqlx::query_as(
+        r#"
+        SELECT id, user_id FROM password_reset_tokens
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+        FOR UPDATE
+        "#,
+    )
+    .bind(hash_token(&request.token))
+    .fetch_optional(&mut *tx)
+    .await?;
+    let (reset_id, user_id) = reset.ok_or_else(invalid_token)?;
+
+    sqlx::query(
+        "UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let updated = sqlx::query(
+        "UPDATE users SET password_hash = $2 WHERE id = $1 AND is_active AND user_type <> 'guest'",
+    )
+    .bind(user_id)
+    .bind(&password_hash)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if updated == 0 {
+        return Err(invalid_token());
+    }
+
+    let revoked = sqlx::query(
+        "UPDATE user_sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL",
+    )
+    .bind(user_id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    audit::log_security_event(
+        &mut tx,
+        SecurityEvent {
+            event_type: SecurityEventType::PasswordChange,
+            severity: SecuritySeverity::Medium,
+            user_id: Some(user_id),
+            session_id: None,
+            details: json!({ "method": "reset_token", "reset_id": reset_id, "sessions_revoked": revoked }),
+        },
+        client,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %user_id, sessions_revoked = revoked, "Password reset completed");
+    Ok(())
+}
@@ -0,0 +1,252 @@
+use sqlx::{FromRow, PgPool};
+use uuid::Uuid;
+
+use crate::middleware::auth::AuthContext;
+use crate::models::{ProjectPermission, ProjectPermissions, ProjectRole, UserRole};
+use crate::utils::error::{AppError, AppResult};
+
+/// Remet les booléens d'une ligne `project_permissions` à NULL : le membre
+/// retrouve exactement les droits de son rôle
+pub(crate) const INHERIT_ROLE_PERMISSIONS: &str = r#"
+    can_edit_project = NULL, can_manage_members = NULL,
+    can_create_notes = NULL, can_edit_notes = NULL, can_delete_notes = NULL,
+    can_create_snippets = NULL, can_edit_snippets = NULL, can_delete_snippets = NULL,
+    can_create_tasks = NULL, can_edit_tasks = NULL, can_delete_tasks = NULL
+"#;
+
+/// Projet et permission de l'utilisateur, s'il en est membre
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct Membership {
+    pub owner_id: Uuid,
+    pub visibility: String,
+    pub role: Option<String>,
+    pub can_edit_project: Option<bool>,
+    pub can_manage_members: Option<bool>,
+    pub can_create_notes: Option<bool>,
+    pub can_edit_notes: Option<bool>,
+    pub can_delete_notes: Option<bool>,
+    pub can_create_snippets: Option<bool>,
+    pub can_edit_snippets: Option<bool>,
+    pub can_delete_snippets: Option<bool>,
+    pub can_create_tasks: Option<bool>,
+    pub can_edit_tasks: Option<bool>,
+    pub can_delete_tasks: Option<bool>,
+}
+
+/// Permissions effectives de l'utilisateur sur un projet
+///
+/// Un projet qu'il ne peut pas voir est traité comme inexistant.
+pub async fn effective_permissions(
+    pool: &PgPool,
+    context: &AuthContext,
+    project_id: Uuid,
+) -> AppResult<ProjectPermissions> {
+    let membership = sqlx::query_as::<_, Membership>(
+        r#"
+        SELECT p.owner_id, p.visibility, m.role,
+               m.can_edit_project, m.can_manage_members,
+               m.can_create_notes, m.can_edit_notes, m.can_delete_notes,
+               m.can_create_snippets, m.can_edit_snippets, m.can_delete_snippets,
+               m.can_create_tasks, m.can_edit_tasks, m.can_delete_tasks
+        FROM projects p
+        LEFT JOIN project_permissions m
+               ON m.project_id = p.id AND m.user_id = $2 AND m.accepted_at IS NOT NULL
+        WHERE p.id = $1
+        "#,
+    )
+    .bind(project_id)
+    .bind(context.user_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
+
+    let permissions = evaluate(context.user_id, context.role, &membership);
+    if !permissions.allows(ProjectPermission::View) {
+        return Err(AppError::NotFound("Project not found".to_string()));
+    }
+
+    Ok(permissions)
+}
+
+/// Combine les droits du rôle, les exceptions propres au membre, la visibilité
+/// du projet et le rôle global de l'utilisateur
+///
+/// Un admin global a tous les droits ; un compte restreint n'a que la lecture.
+pub fn evaluate(user_id: Uuid, user_role: UserRole, membership: &Membership) -> ProjectPermissions {
+    // Le propriétaire du projet fait foi, quelle que soit sa ligne de permission
+    let role = if membership.owner_id == user_id {
+        Some(ProjectRole::Owner)
+    } else {
+        membership.role.as_deref().and_then(|role| role.parse().ok())
+    };
+
+    let mut permissions = role.map(role_defaults).unwrap_or_default();
+    permissions.role = role;
+
+    // Un booléen de `project_permissions` remplace le droit du rôle ; NULL en hérite.
+    // Le propriétaire garde tous ses droits.
+    if role.is_some_and(|role| role != ProjectRole::Owner) {
+        let overrides = [
+            (&mut permissions.can_edit_project, membership.can_edit_project),
+            (&mut permissions.can_manage_members, membership.can_manage_members),
+            (&mut permissions.can_create_notes, membership.can_create_notes),
+            (&mut permissions.can_edit_notes, membership.can_edit_notes),
+            (&mut permissions.can_delete_notes, membership.can_delete_notes),
+            (&mut permissions.can_create_snippets, membership.can_create_snippets),
+            (&mut permissions.can_edit_snippets, membership.can_edit_snippets),
+            (&mut permissions.can_delete_snippets, membership.can_delete_snippets),
+            (&mut permissions.can_create_tasks, membership.can_create_tasks),
+            (&mut permissions.can_edit_tasks, membership.can_edit_tasks),
+            (&mut permissions.can_delete_tasks, membership.can_delete_tasks),
+        ];
+        for (permission, flag) in overrides {
+            if let Some(flag) = flag {
+                *permission = flag;
+            }
+        }
+    }
+
+    permissions.can_view |= membership.visibility == "public";
+
+    match user_role {
+        UserRole::Admin => ProjectPermissions {
+            role,
+            ..full()
+        },
+        UserRole::Restricted => ProjectPermissions {
+            role,
+            can_view: permissions.can_view,
+            ..Default::default()
+        },
+        _ => permissions,
+    }
+}
+
+/// Droits accordés par défaut à chaque rôle de membre
+fn role_defaults(role: ProjectRole) -> ProjectPermissions {
+    match role {
+        ProjectRole::Owner => full(),
+        ProjectRole::Admin => ProjectPermissions {
+            can_delete_project: false,
+            ..full()
+        },
+        ProjectRole::Editor => ProjectPermissions {
+            can_view: true,
+            can_create_notes: true,
+            can_edit_notes: true,
+            can_create_snippets: true,
+            can_edit_snippets: true,
+            can_create_tasks: true,
+            can_edit_tasks: true,
+            ..Default::default()
+        },
+        ProjectRole::Viewer => ProjectPermissions {
+            can_view: true,
+            ..Default::default()
+        },
+    }
+}
+
+fn full() -> ProjectPermissions {
+    ProjectPermissions {
+        role: None,
+        can_view: true,
+        can_edit_project: true,
+        can_delete_project: true,
+        can_manage_members: true,
+        can_create_notes: true,
+        can_edit_notes: true,
+        can_delete_notes: true,
+        can_create_snippets: true,
+        can_edit_snippets: true,
+        can_delete_snippets: true,
+        can_create_tasks: true,
+        can_edit_tasks: true,
+        can_delete_tasks: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn membership(role: Option<&str>, visibility: &str) -> Membership {
+        Membership {
+            owner_id: Uuid::new_v4(),
+            visibility: visibility.to_string(),
+            role: role.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_role_defaults_and_overrides() {
+        let user_id = Uuid::new_v4();
+        let mut editor = membership(Some("editor"), "private");
+
+        let permissions = evaluate(user_id, UserRole::User, &editor);
+        assert_eq!(permissions.role, Some(ProjectRole::Editor));
+        assert!(permissions.can_edit_notes);
+        assert!(!permissions.can_delete_tasks);
+
+        // Un booléen remplace le droit du rôle, dans les deux sens
+        editor.can_delete_tasks = Some(true);
+        editor.can_edit_notes = Some(false);
+        let permissions = evaluate(user_id, UserRole::User, &editor);
+        assert!(permissions.can_delete_tasks);
+        assert!(!permissions.can_edit_notes);
+        assert!(permissions.can_create_notes);
+        assert!(!permissions.allows(ProjectPermission::ManageMembers));
+    }
+
+    #[test]
+    fn test_overrides_revoke_admin_rights_but_not_ownership() {
+        let user_id = Uuid::new_v4();
+        let mut admin = membership(Some("admin"), "private");
+        admin.can_manage_members = Some(false);
+        admin.can_edit_project = Some(false);
+
+        let permissions = evaluate(user_id, UserRole::User, &admin);
+        assert!(!permissions.allows(ProjectPermission::ManageMembers));
+        assert!(!permissions.allows(ProjectPermission::EditProject));
+        assert!(permissions.can_delete_notes);
+
+        admin.owner_id = user_id;
+        let permissions = evaluate(user_id, UserRole::User, &admin);
+        assert!(permissions.allows(ProjectPermission::ManageMembers));
+        assert!(permissions.allows(ProjectPermission::EditProject));
+    }
+
+    #[test]
+    fn test_owner_and_visibility() {
+        let user_id = Uuid::new_v4();
+        let mut project = membership(None, "private");
+        assert!(!evaluate(user_id, UserRole::User, &project).can_view);
+
+        project.visibility = "public".to_string();
+        let permissions = evaluate(user_id, UserRole::User, &project);
+        assert!(permissions.can_view);
+        assert!(!permissions.can_create_notes);
+
+        project.owner_id = user_id;
+        let permissions = evaluate(user_id, UserRole::User, &project);
+        assert_eq!(permissions.role, Some(ProjectRole::Owner));
+        assert!(permissions.allows(ProjectPermission::DeleteProject));
+    }
+
+    #[test]
+    fn test_global_roles() {
+        let user_id = Uuid::new_v4();
+
+        let permissions = evaluate(user_id, UserRole::Admin, &membership(None, "private"));
+        assert_eq!(permissions.role, None);
+        assert!(permissions.allows(ProjectPermission::DeleteProject));
+
+        let mut project = membership(Some("admin"), "team");
+        project.can_delete_tasks = Some(true);
+        let permissions = evaluate(user_id, UserRole::Restricted, &project);
+        assert!(permissions.can_view);
+        assert!(!permissions.allows(ProjectPermission::EditProject));
+        assert!(!permissions.can_delete_tasks);
+    }
+}
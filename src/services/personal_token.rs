@@ -0,0 +1,100 @@
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::middleware::auth::AuthContext;
+use crate::models::{
+    CreatePersonalAccessTokenRequest, CreatedPersonalAccessTokenResponse, PersonalAccessToken,
+    PersonalAccessTokenResponse,
+};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::token::{generate_token, hash_token};
+
+/// Préfixe distinguant un token personnel d'un JWT de session
+pub const TOKEN_PREFIX: &str = "ettu_pat_";
+
+/// Longueur du début de token conservé en clair pour l'affichage
+const DISPLAY_PREFIX_LEN: usize = TOKEN_PREFIX.len() + 8;
+
+pub fn is_personal_token(token: &str) -> bool {
+    token.starts_with(TOKEN_PREFIX)
+}
+
+/// Crée un token personnel ; sa valeur n'est retournée qu'à cette occasion
+pub async fn create(
+    pool: &PgPool,
+    context: &AuthContext,
+    request: CreatePersonalAccessTokenRequest,
+) -> AppResult<CreatedPersonalAccessTokenResponse> {
+    request.validate()?;
+
+    if request.expires_at.is_some_and(|expires_at| expires_at <= Utc::now()) {
+        return Err(AppError::BadRequest("Expiration date must be in the future".to_string()));
+    }
+
+    let mut scopes: Vec<String> = request.scopes.iter().map(ToString::to_string).collect();
+    scopes.sort();
+    scopes.dedup();
+
+    let token = format!("{}{}", TOKEN_PREFIX, generate_token());
+
+    let created = sqlx::query_as::<_, PersonalAccessToken>(
+        r#"
+        INSERT INTO personal_access_tokens (user_id, name, token_hash, token_prefix, scopes, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        RETURNING *
+        "#,
+    )
+    .bind(context.user_id)
+    .bind(&request.name)
+    .bind(hash_token(&token))
+    .bind(&token[..DISPLAY_PREFIX_LEN])
+    .bind(&scopes)
+    .bind(request.expires_at)
+    .fetch_one(pool)
+    .await?;
+
+    info!(user_id = %context.user_id, token_id = %created.id, "Personal access token created");
+
+    Ok(CreatedPersonalAccessTokenResponse {
+        token,
+        details: created.into_response(),
+    })
+}
+
+/// Liste les tokens non révoqués de l'utilisateur, le plus récent en premier
+pub async fn list(pool: &PgPool, context: &AuthContext) -> AppResult<Vec<PersonalAccessTokenResponse>> {
+    let tokens = sqlx::query_as::<_, PersonalAccessToken>(
+        r#"
+        SELECT * FROM personal_access_tokens
+        WHERE user_id = $1 AND revoked_at IS NULL
+        ORDER BY created_at DESC
+        "#,
+    )
+    .bind(context.user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(tokens.into_iter().map(PersonalAccessToken::into_response).collect())
+}
+
+/// Révoque un token de l'utilisateur
+pub async fn revoke(pool: &PgPool, context: &AuthContext, token_id: Uuid) -> AppResult<()> {
+    let revoked = sqlx::query(
+        "UPDATE personal_access_tokens SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+    )
+    .bind(token_id)
+    .bind(context.user_id)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if revoked == 0 {
+        return Err(AppError::NotFound("Token not found".to_string()));
+    }
+
+    info!(user_id = %context.user_id, token_id = %token_id, "Personal access token revoked");
+    Ok(())
+}
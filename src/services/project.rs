@@ -1 +1,336 @@
-// Project service - TODO: Implement
+use serde_json::json;
+use sqlx::{FromRow, PgConnection, PgPool};
+use tracing::info;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::middleware::auth::AuthContext;
+use crate::middleware::project_access::ProjectAccess;
+use crate::models::{
+    CreateProjectRequest, PaginatedResponse, PaginationParams, Project, ProjectFilter,
+    ProjectPermission, ProjectResponse, UpdateProjectRequest, UserRole,
+};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::permission::INHERIT_ROLE_PERMISSIONS;
+use crate::utils::error::{AppError, AppResult};
+
+/// Projet accompagné du nombre de ses tâches et notes
+#[derive(Debug, FromRow)]
+pub(crate) struct ProjectRow {
+    #[sqlx(flatten)]
+    project: Project,
+    task_count: i64,
+    note_count: i64,
+}
+
+impl ProjectRow {
+    pub(crate) fn into_response(self) -> ProjectResponse {
+        ProjectResponse {
+            task_count: Some(self.task_count),
+            note_count: Some(self.note_count),
+            ..self.project.into_response()
+        }
+    }
+}
+
+/// Colonnes d'un projet et de ses compteurs, pour `ProjectRow`
+pub(crate) const PROJECT_WITH_COUNTS: &str = r#"
+    SELECT p.*,
+           (SELECT COUNT(*) FROM tasks t WHERE t.project_id = p.id) AS task_count,
+           (SELECT COUNT(*) FROM project_notes n WHERE n.project_id = p.id) AS note_count
+    FROM projects p
+"#;
+
+/// Projets visibles par l'utilisateur : les siens et ceux dont il est membre
+pub(crate) const MEMBER_OF: &str = r#"
+    (p.owner_id = $1 OR EXISTS (
+        SELECT 1 FROM project_permissions m
+        WHERE m.project_id = p.id AND m.user_id = $1 AND m.accepted_at IS NOT NULL
+    ))
+"#;
+
+/// Crée un projet et la permission complète de son propriétaire
+pub async fn create_project(
+    pool: &PgPool,
+    context: &AuthContext,
+    request: CreateProjectRequest,
+) -> AppResult<ProjectResponse> {
+    ensure_can_create(context)?;
+    request.validate()?;
+
+    let mut tx = pool.begin().await?;
+
+    let project = sqlx::query_as::<_, Project>(
+        r#"
+        INSERT INTO projects
+            (name, description, color, icon, visibility, owner_id, technologies, repository_url, live_url)
+        VALUES ($1, $2, COALESCE($3, '#3b82f6'), COALESCE($4, 'folder'), $5, $6, COALESCE($7, '[]'),
+                NULLIF($8, ''), NULLIF($9, ''))
+        RETURNING *
+        "#,
+    )
+    .bind(&request.name)
+    .bind(request.description.as_deref().filter(|description| !description.is_empty()))
+    .bind(&request.color)
+    .bind(&request.icon)
+    .bind(request.visibility.to_string())
+    .bind(context.user_id)
+    .bind(request.technologies.as_ref().map(|technologies| json!(technologies)))
+    .bind(&request.repository_url)
+    .bind(&request.live_url)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    insert_owner_permission(&mut tx, project.id, context.user_id).await?;
+
+    refresh_public_count(&mut tx, context.user_id).await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %project.id, owner_id = %context.user_id, "Project created");
+
+    Ok(ProjectResponse {
+        task_count: Some(0),
+        note_count: Some(0),
+        ..project.into_response()
+    })
+}
+
+/// Projets de l'utilisateur, avec le nombre de tâches et de notes
+pub async fn list_projects(
+    pool: &PgPool,
+    context: &AuthContext,
+    pagination: &PaginationParams,
+    filter: &ProjectFilter,
+) -> AppResult<PaginatedResponse<ProjectResponse>> {
+    // Colonne et sens de tri en liste blanche : ils sont interpolés dans la requête
+    let sort = match pagination.sort() {
+        "name" => "p.name",
+        "updated_at" => "p.updated_at",
+        _ => "p.created_at",
+    };
+    let order = if pagination.order().eq_ignore_ascii_case("asc") {
+        "ASC"
+    } else {
+        "DESC"
+    };
+    let status = filter.status.map(|status| status.to_string());
+
+    let total: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM projects p WHERE {} AND ($2::text IS NULL OR p.status = $2)",
+        MEMBER_OF
+    ))
+    .bind(context.user_id)
+    .bind(&status)
+    .fetch_one(pool)
+    .await?;
+
+    let rows = sqlx::query_as::<_, ProjectRow>(&format!(
+        "{} WHERE {} AND ($2::text IS NULL OR p.status = $2) ORDER BY {} {}, p.id LIMIT $3 OFFSET $4",
+        PROJECT_WITH_COUNTS, MEMBER_OF, sort, order
+    ))
+    .bind(context.user_id)
+    .bind(&status)
+    .bind(pagination.limit() as i64)
+    .bind(pagination.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(PaginatedResponse::new(
+        rows.into_iter().map(ProjectRow::into_response).collect(),
+        total as u64,
+        pagination.page(),
+        pagination.limit(),
+    ))
+}
+
+/// Projet accessible à l'utilisateur, avec ses compteurs
+pub async fn get_project(pool: &PgPool, access: &ProjectAccess) -> AppResult<ProjectResponse> {
+    sqlx::query_as::<_, ProjectRow>(&format!("{} WHERE p.id = $1", PROJECT_WITH_COUNTS))
+        .bind(access.project_id)
+        .fetch_optional(pool)
+        .await?
+        .map(ProjectRow::into_response)
+        .ok_or_else(|| AppError::NotFound("Project not found".to_string()))
+}
+
+/// Applique une mise à jour partielle si la version envoyée est toujours la courante
+pub async fn update_project(
+    pool: &PgPool,
+    access: &ProjectAccess,
+    request: UpdateProjectRequest,
+) -> AppResult<ProjectResponse> {
+    access.require(ProjectPermission::EditProject)?;
+    request.validate()?;
+
+    if request.settings.as_ref().is_some_and(|settings| !settings.is_object()) {
+        return Err(AppError::BadRequest("Settings must be a JSON object".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let owner_id = lock_project(&mut tx, access.project_id).await?;
+
+    let project = sqlx::query_as::<_, Project>(
+        r#"
+        UPDATE projects SET
+            name = COALESCE($3, name),
+            description = NULLIF(COALESCE($4, description), ''),
+            color = COALESCE($5, color),
+            icon = COALESCE($6, icon),
+            status = COALESCE($7, status),
+            visibility = COALESCE($8, visibility),
+            technologies = COALESCE($9, technologies),
+            repository_url = NULLIF(COALESCE($10, repository_url), ''),
+            live_url = NULLIF(COALESCE($11, live_url), ''),
+            settings = COALESCE($12, settings),
+            version = version + 1
+        WHERE id = $1 AND version = $2
+        RETURNING *
+        "#,
+    )
+    .bind(access.project_id)
+    .bind(request.version)
+    .bind(&request.name)
+    .bind(&request.description)
+    .bind(&request.color)
+    .bind(&request.icon)
+    .bind(request.status.map(|status| status.to_string()))
+    .bind(request.visibility.map(|visibility| visibility.to_string()))
+    .bind(request.technologies.as_ref().map(|technologies| json!(technologies)))
+    .bind(&request.repository_url)
+    .bind(&request.live_url)
+    .bind(&request.settings)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| {
+        AppError::Conflict("Project was modified since it was read, reload it and retry".to_string())
+    })?;
+
+    if request.visibility.is_some() {
+        refresh_public_count(&mut tx, owner_id).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(project.into_response())
+}
+
+/// Supprime définitivement un projet et son contenu
+pub async fn delete_project(pool: &PgPool, access: &ProjectAccess) -> AppResult<()> {
+    access.require(ProjectPermission::DeleteProject)?;
+
+    let id = access.project_id;
+    let mut tx = pool.begin().await?;
+
+    let owner_id = lock_project(&mut tx, id).await?;
+
+    // Les snippets publics issus du projet lui survivent
+    sqlx::query(
+        r#"
+        UPDATE public_snippets SET source_project_id = NULL, source_snippet_id = NULL
+        WHERE source_project_id = $1
+           OR source_snippet_id IN (SELECT id FROM project_snippets WHERE project_id = $1)
+        "#,
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+
+    // Notes, snippets, tâches et permissions suivent par cascade
+    let name: String = sqlx::query_scalar("DELETE FROM projects WHERE id = $1 RETURNING name")
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    refresh_public_count(&mut tx, owner_id).await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(access.context.user_id),
+            action: "project_deleted",
+            entity_type: "project",
+            entity_id: Some(id),
+            details: json!({ "name": name, "owner_id": owner_id }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %id, user_id = %access.context.user_id, "Project deleted");
+
+    Ok(())
+}
+
+/// Verrouille un projet et retourne son propriétaire
+pub(crate) async fn lock_project(conn: &mut PgConnection, id: Uuid) -> AppResult<Uuid> {
+    sqlx::query_scalar("SELECT owner_id FROM projects WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Project not found".to_string()))
+}
+
+/// Un compte restreint est en lecture seule et ne crée pas de projet
+pub(crate) fn ensure_can_create(context: &AuthContext) -> AppResult<()> {
+    if context.role == UserRole::Restricted {
+        return Err(AppError::Forbidden("Restricted accounts are read-only".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Permission du propriétaire d'un nouveau projet, qui tient ses droits de son rôle
+pub(crate) async fn insert_owner_permission(
+    conn: &mut PgConnection,
+    project_id: Uuid,
+    owner_id: Uuid,
+) -> AppResult<()> {
+    sqlx::query(
+        "INSERT INTO project_permissions (project_id, user_id, role, accepted_at) VALUES ($1, $2, 'owner', NOW())",
+    )
+    .bind(project_id)
+    .bind(owner_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Donne le projet à `owner_id`, déjà membre : ses exceptions au rôle sont effacées
+pub(crate) async fn set_owner(conn: &mut PgConnection, project_id: Uuid, owner_id: Uuid) -> AppResult<()> {
+    sqlx::query("UPDATE projects SET owner_id = $2, version = version + 1 WHERE id = $1")
+        .bind(project_id)
+        .bind(owner_id)
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(&format!(
+        "UPDATE project_permissions SET role = 'owner', {}, updated_at = NOW() WHERE project_id = $1 AND user_id = $2",
+        INHERIT_ROLE_PERMISSIONS
+    ))
+    .bind(project_id)
+    .bind(owner_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Recalcule `users.public_projects_count`, affiché sur le profil public
+pub(crate) async fn refresh_public_count(conn: &mut PgConnection, owner_id: Uuid) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE users SET public_projects_count = (
+            SELECT COUNT(*) FROM projects WHERE owner_id = $1 AND visibility = 'public'
+        )
+        WHERE id = $1
+        "#,
+    )
+    .bind(owner_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
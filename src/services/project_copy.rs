@@ -0,0 +1,273 @@
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use tracing::info;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::middleware::project_access::ProjectAccess;
+use crate::models::{
+    DuplicateProjectRequest, DuplicateProjectResponse, Project, ProjectCopyOptions,
+    ProjectCopySummary, ProjectPermission, ProjectResponse, ProjectVisibility,
+};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::project::{ensure_can_create, insert_owner_permission, refresh_public_count};
+use crate::utils::error::{AppError, AppResult};
+
+/// Suffixe du nom par défaut d'une copie
+const COPY_SUFFIX: &str = " (copy)";
+
+/// Duplique un projet et le contenu choisi dans un nouveau projet de l'utilisateur
+///
+/// Tout est fait dans une transaction : en cas d'échec, aucune copie partielle ne subsiste.
+pub async fn duplicate(
+    pool: &PgPool,
+    access: &ProjectAccess,
+    request: DuplicateProjectRequest,
+) -> AppResult<DuplicateProjectResponse> {
+    access.require(ProjectPermission::View)?;
+    let context = &access.context;
+    ensure_can_create(context)?;
+    request.validate()?;
+
+    let mut tx = pool.begin().await?;
+
+    let source = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1 FOR SHARE")
+        .bind(access.project_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Project not found".to_string()))?;
+
+    let name = request.name.unwrap_or_else(|| default_copy_name(&source.name));
+    let visibility = request.visibility.unwrap_or_else(|| source.visibility());
+
+    let project = copy_project(
+        &mut tx,
+        source.id,
+        context.user_id,
+        &name,
+        visibility,
+        request.include.settings,
+    )
+    .await?;
+    let copied = copy_content(&mut tx, source.id, project.id, context.user_id, &request.include).await?;
+
+    refresh_public_count(&mut tx, context.user_id).await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(context.user_id),
+            action: "project_duplicated",
+            entity_type: "project",
+            entity_id: Some(project.id),
+            details: json!({ "source_id": source.id, "include": request.include, "copied": copied }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %project.id, source_id = %source.id, "Project duplicated");
+
+    Ok(DuplicateProjectResponse {
+        project: ProjectResponse {
+            task_count: Some(copied.tasks as i64),
+            note_count: Some(copied.notes as i64),
+            ..project.into_response()
+        },
+        copied,
+    })
+}
+
+/// Crée un projet reprenant la présentation de `source_id`, possédé par `owner_id`
+///
+/// Les URLs du dépôt et du site, propres au projet d'origine, ne sont pas reprises.
+pub async fn copy_project(
+    conn: &mut PgConnection,
+    source_id: Uuid,
+    owner_id: Uuid,
+    name: &str,
+    visibility: ProjectVisibility,
+    copy_settings: bool,
+) -> AppResult<Project> {
+    let mut project = sqlx::query_as::<_, Project>(
+        r#"
+        INSERT INTO projects (name, description, color, icon, visibility, owner_id, technologies)
+        SELECT $2, description, color, icon, $3, $4, technologies FROM projects WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(source_id)
+    .bind(name)
+    .bind(visibility.to_string())
+    .bind(owner_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    // Sans copie, le projet garde les réglages par défaut de la table
+    if copy_settings {
+        project = sqlx::query_as::<_, Project>(
+            "UPDATE projects SET settings = (SELECT settings FROM projects WHERE id = $2) WHERE id = $1 RETURNING *",
+        )
+        .bind(project.id)
+        .bind(source_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    }
+
+    insert_owner_permission(conn, project.id, owner_id).await?;
+
+    Ok(project)
+}
+
+/// Recopie le contenu choisi de `source_id` dans `target_id`, au nom de `author_id`
+///
+/// Les copies repartent de zéro : tâches au backlog sans assigné ni échéance,
+/// checklist décochée. Les références des tâches vers les notes et snippets
+/// pointent vers les copies.
+pub async fn copy_content(
+    conn: &mut PgConnection,
+    source_id: Uuid,
+    target_id: Uuid,
+    author_id: Uuid,
+    options: &ProjectCopyOptions,
+) -> AppResult<ProjectCopySummary> {
+    let mut summary = ProjectCopySummary::default();
+
+    let (old_notes, new_notes) = if options.notes {
+        id_map(conn, "SELECT id FROM project_notes WHERE project_id = $1 ORDER BY created_at, id", source_id).await?
+    } else {
+        Default::default()
+    };
+    summary.notes = sqlx::query(
+        r#"
+        INSERT INTO project_notes (id, title, content, type, tags, project_id, author_id, folder, is_pinned, is_archived)
+        SELECT map.new_id, n.title, n.content, n.type, n.tags, $1, $2, n.folder, n.is_pinned, n.is_archived
+        FROM unnest($3::uuid[], $4::uuid[]) AS map(old_id, new_id)
+        JOIN project_notes n ON n.id = map.old_id
+        "#,
+    )
+    .bind(target_id)
+    .bind(author_id)
+    .bind(&old_notes)
+    .bind(&new_notes)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    let (old_snippets, new_snippets) = if options.snippets {
+        id_map(conn, "SELECT id FROM project_snippets WHERE project_id = $1 ORDER BY created_at, id", source_id).await?
+    } else {
+        Default::default()
+    };
+    summary.snippets = sqlx::query(
+        r#"
+        INSERT INTO project_snippets
+            (id, title, description, code, language, type, tags, project_id, author_id,
+             folder, is_pinned, is_archived, dependencies, usage_example)
+        SELECT map.new_id, s.title, s.description, s.code, s.language, s.type, s.tags, $1, $2,
+               s.folder, s.is_pinned, s.is_archived, s.dependencies, s.usage_example
+        FROM unnest($3::uuid[], $4::uuid[]) AS map(old_id, new_id)
+        JOIN project_snippets s ON s.id = map.old_id
+        "#,
+    )
+    .bind(target_id)
+    .bind(author_id)
+    .bind(&old_snippets)
+    .bind(&new_snippets)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if !options.tasks {
+        return Ok(summary);
+    }
+
+    let (old_tasks, new_tasks) =
+        id_map(conn, "SELECT id FROM tasks WHERE project_id = $1 ORDER BY created_at, id", source_id).await?;
+    summary.tasks = sqlx::query(&format!(
+        r#"
+        INSERT INTO tasks
+            (id, title, description, type, priority, status, tags, project_id, author_id,
+             estimated_time, order_position, related_notes, related_snippets)
+        SELECT map.new_id, t.title, t.description, t.type, t.priority, 'backlog', t.tags, $1, $2,
+               t.estimated_time, t.order_position, {}, {}
+        FROM unnest($3::uuid[], $4::uuid[]) AS map(old_id, new_id)
+        JOIN tasks t ON t.id = map.old_id
+        "#,
+        remap_refs("t.related_notes", "$5", "$6"),
+        remap_refs("t.related_snippets", "$7", "$8"),
+    ))
+    .bind(target_id)
+    .bind(author_id)
+    .bind(&old_tasks)
+    .bind(&new_tasks)
+    .bind(&old_notes)
+    .bind(&new_notes)
+    .bind(&old_snippets)
+    .bind(&new_snippets)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    summary.checklist_items = sqlx::query(
+        r#"
+        INSERT INTO task_checklist_items (task_id, text, completed, order_position)
+        SELECT map.new_id, c.text, FALSE, c.order_position
+        FROM unnest($1::uuid[], $2::uuid[]) AS map(old_id, new_id)
+        JOIN task_checklist_items c ON c.task_id = map.old_id
+        "#,
+    )
+    .bind(&old_tasks)
+    .bind(&new_tasks)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    Ok(summary)
+}
+
+/// Expression remplaçant les identifiants du tableau JSON `refs` par ceux des copies
+///
+/// `old` et `new` sont les paramètres des identifiants source et copiés ; une
+/// référence vers un élément non copié est abandonnée.
+fn remap_refs(refs: &str, old: &str, new: &str) -> String {
+    format!(
+        r#"COALESCE((
+            SELECT jsonb_agg(to_jsonb(map.new_id) ORDER BY r.ord)
+            FROM jsonb_array_elements_text(CASE WHEN jsonb_typeof({refs}) = 'array' THEN {refs} ELSE '[]' END)
+                 WITH ORDINALITY AS r(value, ord)
+            JOIN unnest({old}::uuid[], {new}::uuid[]) AS map(old_id, new_id) ON map.old_id::text = r.value
+        ), '[]')"#
+    )
+}
+
+/// Nom du projet suivi de « (copy) », raccourci pour rester dans la limite de 255 caractères
+fn default_copy_name(name: &str) -> String {
+    let kept = 255 - COPY_SUFFIX.chars().count();
+    format!("{}{}", name.chars().take(kept).collect::<String>(), COPY_SUFFIX)
+}
+
+/// Identifiants des éléments à copier et ceux, nouveaux, de leurs copies
+async fn id_map(conn: &mut PgConnection, query: &str, source_id: Uuid) -> AppResult<(Vec<Uuid>, Vec<Uuid>)> {
+    let old_ids: Vec<Uuid> = sqlx::query_scalar(query)
+        .bind(source_id)
+        .fetch_all(&mut *conn)
+        .await?;
+    let new_ids = old_ids.iter().map(|_| Uuid::new_v4()).collect();
+
+    Ok((old_ids, new_ids))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_copy_name_fits_column() {
+        assert_eq!(default_copy_name("ETTU"), "ETTU (copy)");
+
+        let long = "é".repeat(255);
+        assert_eq!(default_copy_name(&long).chars().count(), 255);
+    }
+}
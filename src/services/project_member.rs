@@ -0,0 +1,588 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use tracing::{info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::middleware::project_access::ProjectAccess;
+use crate::models::{
+    InviteMemberRequest, ProjectInvitation, ProjectInvitationResponse, ProjectMember,
+    ProjectMemberResponse, ProjectPermission, ProjectRole, UpdateMemberRoleRequest,
+};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::email::{EmailService, ProjectInvitationEmail};
+use crate::services::permission::INHERIT_ROLE_PERMISSIONS;
+use crate::services::project::lock_project;
+use crate::services::user;
+use crate::utils::error::{AppError, AppResult};
+
+/// Membres ayant accepté leur invitation, avec leur profil
+const MEMBERS: &str = r#"
+    SELECT m.id, m.project_id, m.user_id, u.username, u.display_name, u.avatar_url,
+           m.role, m.invited_by, m.invited_at, m.accepted_at AS joined_at
+    FROM project_permissions m
+    JOIN users u ON u.id = m.user_id
+    WHERE m.accepted_at IS NOT NULL
+"#;
+
+/// Invitations accompagnées du nom de leur projet
+const INVITATIONS: &str = r#"
+    SELECT i.*, p.name AS project_name
+    FROM project_invitations i
+    JOIN projects p ON p.id = i.project_id
+"#;
+
+/// Membres d'un projet accessible à l'utilisateur
+pub async fn list_members(pool: &PgPool, access: &ProjectAccess) -> AppResult<Vec<ProjectMemberResponse>> {
+    let members = sqlx::query_as::<_, ProjectMember>(&format!(
+        "{} AND m.project_id = $1 ORDER BY m.accepted_at, m.created_at",
+        MEMBERS
+    ))
+    .bind(access.project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(members.into_iter().map(ProjectMember::into_response).collect())
+}
+
+/// Invite une adresse email à rejoindre le projet
+///
+/// Si l'adresse n'a pas encore de compte, l'invitation est conservée sans
+/// échéance : son délai démarre une fois le compte créé.
+pub async fn invite(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    access: &ProjectAccess,
+    request: InviteMemberRequest,
+) -> AppResult<ProjectInvitationResponse> {
+    access.require(ProjectPermission::ManageMembers)?;
+    request.validate()?;
+
+    if request.role == ProjectRole::Owner {
+        return Err(AppError::BadRequest(
+            "Ownership is transferred, it cannot be granted by invitation".to_string(),
+        ));
+    }
+
+    if request.role == ProjectRole::Admin && !access.has_owner_rights() {
+        return Err(AppError::Forbidden(
+            "Only the project owner can invite admins".to_string(),
+        ));
+    }
+
+    let (context, project_id) = (&access.context, access.project_id);
+    let mut tx = pool.begin().await?;
+
+    lock_project(&mut tx, project_id).await?;
+
+    let already_member: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM project_permissions m JOIN users u ON u.id = m.user_id
+            WHERE m.project_id = $1 AND u.email = $2::citext AND m.accepted_at IS NOT NULL
+        )
+        "#,
+    )
+    .bind(project_id)
+    .bind(&request.email)
+    .fetch_one(&mut *tx)
+    .await?;
+    if already_member {
+        return Err(AppError::Conflict("This user is already a member of the project".to_string()));
+    }
+
+    // Une invitation expirée ne doit pas bloquer la suivante
+    sqlx::query(
+        r#"
+        UPDATE project_invitations SET status = 'expired'
+        WHERE project_id = $1 AND email = $2::citext AND status = 'pending' AND expires_at <= NOW()
+        "#,
+    )
+    .bind(project_id)
+    .bind(&request.email)
+    .execute(&mut *tx)
+    .await?;
+
+    let has_account: bool =
+        sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE email = $1::citext)")
+            .bind(&request.email)
+            .fetch_one(&mut *tx)
+            .await?;
+    let expires_at = has_account.then(|| Utc::now() + Duration::seconds(config.projects.invitation_ttl));
+
+    let invitation = sqlx::query_as::<_, ProjectInvitation>(
+        r#"
+        WITH i AS (
+            INSERT INTO project_invitations (project_id, email, role, invited_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+        )
+        SELECT i.*, p.name AS project_name FROM i JOIN projects p ON p.id = i.project_id
+        "#,
+    )
+    .bind(project_id)
+    .bind(&request.email)
+    .bind(request.role.to_string())
+    .bind(context.user_id)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Conflict("An invitation is already pending for this email".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(context.user_id),
+            action: "project_member_invited",
+            entity_type: "project",
+            entity_id: Some(project_id),
+            details: json!({
+                "invitation_id": invitation.id,
+                "email": invitation.email,
+                "role": invitation.role,
+            }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %project_id, invitation_id = %invitation.id, "Project invitation created");
+
+    send_invitation_email(pool, config, email_service, context, &invitation, has_account).await?;
+
+    Ok(invitation.into_response())
+}
+
+/// Invitations d'un projet, réservées à ceux qui gèrent ses membres
+pub async fn list_invitations(
+    pool: &PgPool,
+    access: &ProjectAccess,
+) -> AppResult<Vec<ProjectInvitationResponse>> {
+    access.require(ProjectPermission::ManageMembers)?;
+
+    let invitations = sqlx::query_as::<_, ProjectInvitation>(&format!(
+        "{} WHERE i.project_id = $1 ORDER BY i.created_at DESC",
+        INVITATIONS
+    ))
+    .bind(access.project_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(invitations.into_iter().map(ProjectInvitation::into_response).collect())
+}
+
+/// Révoque une invitation encore en attente
+pub async fn revoke_invitation(pool: &PgPool, access: &ProjectAccess, invitation_id: Uuid) -> AppResult<()> {
+    access.require(ProjectPermission::ManageMembers)?;
+
+    let project_id = access.project_id;
+    let mut tx = pool.begin().await?;
+
+    let email: String = sqlx::query_scalar(
+        r#"
+        UPDATE project_invitations SET status = 'revoked', responded_at = NOW()
+        WHERE id = $1 AND project_id = $2 AND status = 'pending'
+        RETURNING email
+        "#,
+    )
+    .bind(invitation_id)
+    .bind(project_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invitation not found".to_string()))?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(access.context.user_id),
+            action: "project_invitation_revoked",
+            entity_type: "project",
+            entity_id: Some(project_id),
+            details: json!({ "invitation_id": invitation_id, "email": email }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Invitations en attente adressées à l'utilisateur
+pub async fn list_my_invitations(
+    pool: &PgPool,
+    config: &Config,
+    context: &AuthContext,
+) -> AppResult<Vec<ProjectInvitationResponse>> {
+    let mut conn = pool.acquire().await?;
+    let email = invitee_email(&mut conn, config, context).await?;
+
+    let invitations = sqlx::query_as::<_, ProjectInvitation>(&format!(
+        "{} WHERE i.email = $1::citext AND i.status = 'pending' AND i.expires_at > NOW() ORDER BY i.created_at DESC",
+        INVITATIONS
+    ))
+    .bind(&email)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    Ok(invitations.into_iter().map(ProjectInvitation::into_response).collect())
+}
+
+/// Accepte une invitation adressée à l'utilisateur et l'ajoute au projet
+pub async fn accept_invitation(
+    pool: &PgPool,
+    config: &Config,
+    context: &AuthContext,
+    invitation_id: Uuid,
+) -> AppResult<ProjectMemberResponse> {
+    let mut tx = pool.begin().await?;
+
+    let email = invitee_email(&mut tx, config, context).await?;
+
+    let invitation = sqlx::query_as::<_, ProjectInvitation>(&format!(
+        "{} WHERE i.id = $1 AND i.email = $2::citext AND i.status = 'pending' AND i.expires_at > NOW() FOR UPDATE OF i",
+        INVITATIONS
+    ))
+    .bind(invitation_id)
+    .bind(&email)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invitation not found or expired".to_string()))?;
+
+    // Une permission jamais acceptée est remplacée ; un membre actif reste inchangé
+    // Le nouveau membre a exactement les droits du rôle de l'invitation
+    let joined = sqlx::query(&format!(
+        r#"
+        INSERT INTO project_permissions (project_id, user_id, role, invited_by, invited_at, accepted_at)
+        VALUES ($1, $2, $3, $4, $5, NOW())
+        ON CONFLICT (project_id, user_id) DO UPDATE SET
+            role = EXCLUDED.role,
+            invited_by = EXCLUDED.invited_by,
+            invited_at = EXCLUDED.invited_at,
+            accepted_at = EXCLUDED.accepted_at,
+            {},
+            updated_at = NOW()
+        WHERE project_permissions.accepted_at IS NULL
+        "#,
+        INHERIT_ROLE_PERMISSIONS
+    ))
+    .bind(invitation.project_id)
+    .bind(context.user_id)
+    .bind(&invitation.role)
+    .bind(invitation.invited_by)
+    .bind(invitation.created_at)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+    if joined == 0 {
+        return Err(AppError::Conflict("You are already a member of this project".to_string()));
+    }
+
+    respond(&mut tx, context, &invitation, "accepted").await?;
+
+    let member = find_member(&mut tx, invitation.project_id, context.user_id).await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %invitation.project_id, user_id = %context.user_id, "Project invitation accepted");
+
+    Ok(member.into_response())
+}
+
+/// Décline une invitation adressée à l'utilisateur
+pub async fn decline_invitation(
+    pool: &PgPool,
+    config: &Config,
+    context: &AuthContext,
+    invitation_id: Uuid,
+) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+
+    let email = invitee_email(&mut tx, config, context).await?;
+
+    let invitation = sqlx::query_as::<_, ProjectInvitation>(&format!(
+        "{} WHERE i.id = $1 AND i.email = $2::citext AND i.status = 'pending' AND i.expires_at > NOW() FOR UPDATE OF i",
+        INVITATIONS
+    ))
+    .bind(invitation_id)
+    .bind(&email)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Invitation not found or expired".to_string()))?;
+
+    respond(&mut tx, context, &invitation, "declined").await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Change le rôle d'un membre
+///
+/// Seul le propriétaire attribue ou retire le rôle admin ; la propriété ne
+/// s'obtient que par transfert.
+pub async fn update_member_role(
+    pool: &PgPool,
+    access: &ProjectAccess,
+    user_id: Uuid,
+    request: UpdateMemberRoleRequest,
+) -> AppResult<ProjectMemberResponse> {
+    access.require(ProjectPermission::ManageMembers)?;
+    if request.role == ProjectRole::Owner {
+        return Err(AppError::BadRequest(
+            "Ownership is transferred, it cannot be assigned as a role".to_string(),
+        ));
+    }
+
+    let project_id = access.project_id;
+    let mut tx = pool.begin().await?;
+
+    lock_project(&mut tx, project_id).await?;
+    let current = member_role(&mut tx, project_id, user_id).await?;
+    ensure_can_manage(access, current)?;
+    if request.role == ProjectRole::Admin && !access.has_owner_rights() {
+        return Err(AppError::Forbidden("Only the project owner can grant the admin role".to_string()));
+    }
+
+    // Les exceptions accordées sous l'ancien rôle ne lui survivent pas
+    sqlx::query(&format!(
+        "UPDATE project_permissions SET role = $3, {}, updated_at = NOW() WHERE project_id = $1 AND user_id = $2",
+        INHERIT_ROLE_PERMISSIONS
+    ))
+    .bind(project_id)
+    .bind(user_id)
+    .bind(request.role.to_string())
+    .execute(&mut *tx)
+    .await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(access.context.user_id),
+            action: "project_member_role_changed",
+            entity_type: "project",
+            entity_id: Some(project_id),
+            details: json!({
+                "user_id": user_id,
+                "from": current.to_string(),
+                "to": request.role.to_string(),
+            }),
+        },
+    )
+    .await?;
+
+    let member = find_member(&mut tx, project_id, user_id).await?;
+
+    tx.commit().await?;
+
+    Ok(member.into_response())
+}
+
+/// Retire un membre du projet, ou le quitte quand `user_id` est l'utilisateur lui-même
+pub async fn remove_member(pool: &PgPool, access: &ProjectAccess, user_id: Uuid) -> AppResult<()> {
+    let leaving = user_id == access.context.user_id;
+    if !leaving {
+        access.require(ProjectPermission::ManageMembers)?;
+    }
+
+    let project_id = access.project_id;
+    let mut tx = pool.begin().await?;
+
+    lock_project(&mut tx, project_id).await?;
+    let current = member_role(&mut tx, project_id, user_id).await?;
+    if !leaving {
+        ensure_can_manage(access, current)?;
+    }
+    if current == ProjectRole::Owner {
+        return Err(AppError::Forbidden(
+            "The project owner cannot leave the project, transfer ownership first".to_string(),
+        ));
+    }
+
+    sqlx::query("DELETE FROM project_permissions WHERE project_id = $1 AND user_id = $2")
+        .bind(project_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(access.context.user_id),
+            action: if leaving { "project_member_left" } else { "project_member_removed" },
+            entity_type: "project",
+            entity_id: Some(project_id),
+            details: json!({ "user_id": user_id, "role": current.to_string() }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %project_id, user_id = %user_id, "Project member removed");
+
+    Ok(())
+}
+
+/// Un gestionnaire autre que le propriétaire ne touche ni au propriétaire ni aux admins
+fn ensure_can_manage(access: &ProjectAccess, member: ProjectRole) -> AppResult<()> {
+    match member {
+        ProjectRole::Owner => Err(AppError::Forbidden(
+            "The project owner's membership cannot be changed".to_string(),
+        )),
+        ProjectRole::Admin if !access.has_owner_rights() => Err(AppError::Forbidden(
+            "Only the project owner can manage admins".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+async fn member_role(conn: &mut PgConnection, project_id: Uuid, user_id: Uuid) -> AppResult<ProjectRole> {
+    let role: String = sqlx::query_scalar(
+        r#"
+        SELECT role FROM project_permissions
+        WHERE project_id = $1 AND user_id = $2 AND accepted_at IS NOT NULL
+        FOR UPDATE
+        "#,
+    )
+    .bind(project_id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Member not found".to_string()))?;
+
+    Ok(role.parse().unwrap_or(ProjectRole::Viewer))
+}
+
+async fn find_member(conn: &mut PgConnection, project_id: Uuid, user_id: Uuid) -> AppResult<ProjectMember> {
+    sqlx::query_as::<_, ProjectMember>(&format!("{} AND m.project_id = $1 AND m.user_id = $2", MEMBERS))
+        .bind(project_id)
+        .bind(user_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Member not found".to_string()))
+}
+
+/// Fait courir le délai des invitations conservées pour une adresse qui vient d'obtenir un compte
+///
+/// Appelé dans la transaction qui attribue l'adresse : inscription, compte OAuth,
+/// conversion d'un invité ou changement d'adresse.
+pub async fn start_held_invitations(conn: &mut PgConnection, config: &Config, email: &str) -> AppResult<()> {
+    sqlx::query(
+        r#"
+        UPDATE project_invitations SET expires_at = $2
+        WHERE email = $1::citext AND status = 'pending' AND expires_at IS NULL
+        "#,
+    )
+    .bind(email)
+    .bind(Utc::now() + Duration::seconds(config.projects.invitation_ttl))
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+/// Adresse de l'utilisateur à laquelle ses invitations sont envoyées
+///
+/// Quand la vérification est activée, l'adresse doit être confirmée : sinon
+/// n'importe qui pourrait s'inscrire avec l'email invité.
+async fn invitee_email(conn: &mut PgConnection, config: &Config, context: &AuthContext) -> AppResult<String> {
+    if config.features.email_verification && !context.is_verified {
+        return Err(AppError::Forbidden(
+            "Verify your email address before answering invitations".to_string(),
+        ));
+    }
+
+    let email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = $1 AND email IS NOT NULL")
+        .bind(context.user_id)
+        .fetch_optional(&mut *conn)
+        .await?
+        .ok_or_else(|| AppError::Forbidden("An email address is required to join projects".to_string()))?;
+
+    Ok(email)
+}
+
+async fn respond(
+    conn: &mut PgConnection,
+    context: &AuthContext,
+    invitation: &ProjectInvitation,
+    status: &'static str,
+) -> AppResult<()> {
+    sqlx::query("UPDATE project_invitations SET status = $2, responded_at = NOW() WHERE id = $1")
+        .bind(invitation.id)
+        .bind(status)
+        .execute(&mut *conn)
+        .await?;
+
+    audit::log_event(
+        &mut *conn,
+        AuditEvent {
+            actor_id: Some(context.user_id),
+            action: if status == "accepted" {
+                "project_invitation_accepted"
+            } else {
+                "project_invitation_declined"
+            },
+            entity_type: "project",
+            entity_id: Some(invitation.project_id),
+            details: json!({ "invitation_id": invitation.id, "role": invitation.role }),
+        },
+    )
+    .await
+}
+
+/// Envoie l'invitation en arrière-plan ; un échec est journalisé sans annuler l'invitation
+async fn send_invitation_email(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    context: &AuthContext,
+    invitation: &ProjectInvitation,
+    has_account: bool,
+) -> AppResult<()> {
+    let inviter = user::find_by_id(pool, context.user_id)
+        .await?
+        .and_then(|inviter| inviter.display_name.or(inviter.username))
+        .unwrap_or_default();
+
+    // Sans compte, le lien mène à l'inscription ; l'invitation sera retrouvée par l'adresse
+    let frontend_url = config.server.frontend_url.trim_end_matches('/');
+    let link = if has_account {
+        format!("{}/invitations/{}", frontend_url, invitation.id)
+    } else {
+        format!("{}/register?invitation={}", frontend_url, invitation.id)
+    };
+
+    let email_service = email_service.clone();
+    let email = invitation.email.clone();
+    let project = invitation.project_name.clone();
+    let role = invitation.role.clone();
+    let invitation_id = invitation.id;
+    // Arrondi au jour supérieur : un délai de quelques heures n'annonce pas « 0 jours »
+    let expires_in_days = (config.projects.invitation_ttl + 86_399) / 86_400;
+    tokio::spawn(async move {
+        let content = ProjectInvitationEmail {
+            inviter: &inviter,
+            project: &project,
+            role: &role,
+            link: &link,
+            has_account,
+            expires_in_days,
+        };
+        if let Err(e) = email_service.send_project_invitation(&email, &content).await {
+            warn!(invitation_id = %invitation_id, "Failed to send project invitation email: {}", e);
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::info;
+use validator::Validate;
+
+use crate::middleware::auth::AuthContext;
+use crate::middleware::project_access::ProjectAccess;
+use crate::models::{
+    CreateFromTemplateRequest, PaginatedResponse, PaginationParams, Project, ProjectCopyOptions,
+    ProjectPermission, ProjectResponse, ProjectVisibility, PublishTemplateRequest, TemplateCategory,
+    TemplateFilter,
+};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::project::{
+    ensure_can_create, lock_project, refresh_public_count, ProjectRow, MEMBER_OF, PROJECT_WITH_COUNTS,
+};
+use crate::services::project_copy;
+use crate::utils::error::{AppError, AppResult};
+
+/// Modèles visibles par l'utilisateur : publics, ou issus de ses projets
+fn visible_templates() -> String {
+    format!("p.is_template AND (p.visibility = 'public' OR {})", MEMBER_OF)
+}
+
+/// Modèles accessibles, du plus récent au plus ancien, filtrés par catégorie
+pub async fn list_templates(
+    pool: &PgPool,
+    context: &AuthContext,
+    pagination: &PaginationParams,
+    filter: &TemplateFilter,
+) -> AppResult<PaginatedResponse<ProjectResponse>> {
+    let visible = visible_templates();
+    // Les catégories sont enregistrées en minuscules
+    let category = filter.category.as_deref().map(|category| category.trim().to_lowercase());
+
+    let total: i64 = sqlx::query_scalar(&format!(
+        "SELECT COUNT(*) FROM projects p WHERE {} AND ($2::text IS NULL OR p.template_category = $2)",
+        visible
+    ))
+    .bind(context.user_id)
+    .bind(&category)
+    .fetch_one(pool)
+    .await?;
+
+    let rows = sqlx::query_as::<_, ProjectRow>(&format!(
+        "{} WHERE {} AND ($2::text IS NULL OR p.template_category = $2) ORDER BY p.updated_at DESC, p.id LIMIT $3 OFFSET $4",
+        PROJECT_WITH_COUNTS, visible
+    ))
+    .bind(context.user_id)
+    .bind(&category)
+    .bind(pagination.limit() as i64)
+    .bind(pagination.offset() as i64)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(PaginatedResponse::new(
+        rows.into_iter().map(ProjectRow::into_response).collect(),
+        total as u64,
+        pagination.page(),
+        pagination.limit(),
+    ))
+}
+
+/// Catégories des modèles accessibles, avec leur nombre de modèles
+pub async fn list_categories(pool: &PgPool, context: &AuthContext) -> AppResult<Vec<TemplateCategory>> {
+    let categories = sqlx::query_as::<_, TemplateCategory>(&format!(
+        r#"
+        SELECT p.template_category AS category, COUNT(*) AS template_count
+        FROM projects p
+        WHERE {} AND p.template_category IS NOT NULL
+        GROUP BY p.template_category
+        ORDER BY p.template_category
+        "#,
+        visible_templates()
+    ))
+    .bind(context.user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(categories)
+}
+
+/// Crée un projet appartenant à l'utilisateur à partir d'un modèle
+///
+/// Notes, snippets, tâches avec leur checklist et réglages sont recopiés.
+pub async fn instantiate(
+    pool: &PgPool,
+    access: &ProjectAccess,
+    request: CreateFromTemplateRequest,
+) -> AppResult<ProjectResponse> {
+    let context = &access.context;
+    ensure_can_create(context)?;
+    request.validate()?;
+
+    let mut tx = pool.begin().await?;
+
+    let template = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1 AND is_template FOR SHARE")
+        .bind(access.project_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Template not found".to_string()))?;
+
+    let name = request.name.as_deref().unwrap_or(&template.name);
+    let visibility = request.visibility.unwrap_or(ProjectVisibility::Private);
+    let options = ProjectCopyOptions::default();
+
+    let project =
+        project_copy::copy_project(&mut tx, template.id, context.user_id, name, visibility, options.settings)
+            .await?;
+    let summary = project_copy::copy_content(&mut tx, template.id, project.id, context.user_id, &options).await?;
+
+    refresh_public_count(&mut tx, context.user_id).await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(context.user_id),
+            action: "project_created_from_template",
+            entity_type: "project",
+            entity_id: Some(project.id),
+            details: json!({ "template_id": template.id, "copied": summary }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %project.id, template_id = %template.id, "Project created from template");
+
+    Ok(ProjectResponse {
+        task_count: Some(summary.tasks as i64),
+        note_count: Some(summary.notes as i64),
+        ..project.into_response()
+    })
+}
+
+/// Publie le projet comme modèle dans une catégorie, ou change sa catégorie
+///
+/// Réservé au propriétaire ; le modèle n'est proposé à tous que si le projet est public.
+pub async fn publish(
+    pool: &PgPool,
+    access: &ProjectAccess,
+    request: PublishTemplateRequest,
+) -> AppResult<ProjectResponse> {
+    request.validate()?;
+
+    let category = request.category.trim().to_lowercase();
+    if category.is_empty() {
+        return Err(AppError::BadRequest("Template category cannot be blank".to_string()));
+    }
+
+    set_template(pool, access, Some(&category)).await
+}
+
+/// Retire le projet des modèles
+pub async fn unpublish(pool: &PgPool, access: &ProjectAccess) -> AppResult<ProjectResponse> {
+    set_template(pool, access, None).await
+}
+
+async fn set_template(pool: &PgPool, access: &ProjectAccess, category: Option<&str>) -> AppResult<ProjectResponse> {
+    access.require(ProjectPermission::EditProject)?;
+    if !access.has_owner_rights() {
+        return Err(AppError::Forbidden(
+            "Only the project owner can publish it as a template".to_string(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    lock_project(&mut tx, access.project_id).await?;
+
+    let project = sqlx::query_as::<_, Project>(
+        r#"
+        UPDATE projects SET is_template = $2, template_category = $3, version = version + 1
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(access.project_id)
+    .bind(category.is_some())
+    .bind(category)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(access.context.user_id),
+            action: if category.is_some() {
+                "project_published_as_template"
+            } else {
+                "project_template_unpublished"
+            },
+            entity_type: "project",
+            entity_id: Some(project.id),
+            details: json!({ "category": category }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(project.into_response())
+}
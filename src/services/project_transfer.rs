@@ -0,0 +1,364 @@
+use chrono::{Duration, Utc};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::middleware::project_access::ProjectAccess;
+use crate::models::{
+    Project, ProjectPermission, ProjectResponse, ProjectTransfer, ProjectTransferResponse,
+    TransferOwnershipRequest, UserRole,
+};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::permission::INHERIT_ROLE_PERMISSIONS;
+use crate::services::project::{lock_project, refresh_public_count, set_owner};
+use crate::utils::error::{AppError, AppResult};
+
+/// Type des notifications reçues par le destinataire d'un transfert
+const TRANSFER_REQUESTED_NOTIFICATION: &str = "project_transfer_requested";
+
+/// Transferts accompagnés du nom de leur projet
+const TRANSFERS: &str = r#"
+    SELECT t.*, p.name AS project_name
+    FROM project_transfers t
+    JOIN projects p ON p.id = t.project_id
+"#;
+
+/// Propose la propriété du projet à l'un de ses membres
+///
+/// Le projet ne change de propriétaire qu'une fois le transfert accepté.
+pub async fn request_transfer(
+    pool: &PgPool,
+    config: &Config,
+    access: &ProjectAccess,
+    request: TransferOwnershipRequest,
+) -> AppResult<ProjectTransferResponse> {
+    ensure_owner_rights(access)?;
+
+    let mut tx = pool.begin().await?;
+
+    let owner_id = lock_project(&mut tx, access.project_id).await?;
+    if request.to_user_id == owner_id {
+        return Err(AppError::BadRequest("This user already owns the project".to_string()));
+    }
+
+    let is_member: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM project_permissions
+            WHERE project_id = $1 AND user_id = $2 AND accepted_at IS NOT NULL
+        )
+        "#,
+    )
+    .bind(access.project_id)
+    .bind(request.to_user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if !is_member {
+        return Err(AppError::BadRequest(
+            "Ownership can only be transferred to a project member".to_string(),
+        ));
+    }
+
+    // Un transfert expiré ne doit pas bloquer le suivant
+    sqlx::query(
+        "UPDATE project_transfers SET status = 'expired' WHERE project_id = $1 AND status = 'pending' AND expires_at <= NOW()",
+    )
+    .bind(access.project_id)
+    .execute(&mut *tx)
+    .await?;
+
+    let transfer = sqlx::query_as::<_, ProjectTransfer>(
+        r#"
+        WITH t AS (
+            INSERT INTO project_transfers (project_id, from_user_id, to_user_id, requested_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+        )
+        SELECT t.*, p.name AS project_name FROM t JOIN projects p ON p.id = t.project_id
+        "#,
+    )
+    .bind(access.project_id)
+    .bind(owner_id)
+    .bind(request.to_user_id)
+    .bind(access.context.user_id)
+    .bind(Utc::now() + Duration::seconds(config.projects.transfer_ttl))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Conflict("An ownership transfer is already pending for this project".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO notifications (user_id, type, title, message, entity_type, entity_id)
+        VALUES ($1, $2, 'Transfert de propriété', $3, 'project_transfer', $4)
+        "#,
+    )
+    .bind(transfer.to_user_id)
+    .bind(TRANSFER_REQUESTED_NOTIFICATION)
+    .bind(format!(
+        "Le projet « {} » vous est proposé. Acceptez ou refusez le transfert avant le {}.",
+        transfer.project_name,
+        transfer.expires_at.format("%d/%m/%Y %H:%M UTC")
+    ))
+    .bind(transfer.id)
+    .execute(&mut *tx)
+    .await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(access.context.user_id),
+            action: "project_transfer_requested",
+            entity_type: "project",
+            entity_id: Some(access.project_id),
+            details: json!({
+                "transfer_id": transfer.id,
+                "from": transfer.from_user_id,
+                "to": transfer.to_user_id,
+            }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %access.project_id, transfer_id = %transfer.id, "Project transfer requested");
+
+    Ok(transfer.into_response())
+}
+
+/// Annule le transfert en attente du projet
+pub async fn cancel_transfer(pool: &PgPool, access: &ProjectAccess) -> AppResult<()> {
+    ensure_owner_rights(access)?;
+
+    let mut tx = pool.begin().await?;
+
+    let transfer_id: Uuid = sqlx::query_scalar(
+        r#"
+        UPDATE project_transfers SET status = 'cancelled', responded_at = NOW()
+        WHERE project_id = $1 AND status = 'pending'
+        RETURNING id
+        "#,
+    )
+    .bind(access.project_id)
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("No ownership transfer is pending".to_string()))?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(access.context.user_id),
+            action: "project_transfer_cancelled",
+            entity_type: "project",
+            entity_id: Some(access.project_id),
+            details: json!({ "transfer_id": transfer_id }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Transferts en attente proposés à l'utilisateur
+pub async fn list_my_transfers(pool: &PgPool, context: &AuthContext) -> AppResult<Vec<ProjectTransferResponse>> {
+    let transfers = sqlx::query_as::<_, ProjectTransfer>(&format!(
+        "{} WHERE t.to_user_id = $1 AND t.status = 'pending' AND t.expires_at > NOW() ORDER BY t.created_at DESC",
+        TRANSFERS
+    ))
+    .bind(context.user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(transfers.into_iter().map(ProjectTransfer::into_response).collect())
+}
+
+/// Accepte un transfert : l'utilisateur devient propriétaire, l'ancien reste admin
+pub async fn accept_transfer(pool: &PgPool, context: &AuthContext, transfer_id: Uuid) -> AppResult<ProjectResponse> {
+    if context.role == UserRole::Restricted {
+        return Err(AppError::Forbidden("Restricted accounts are read-only".to_string()));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Le projet est verrouillé avant le transfert, dans le même ordre que `request_transfer`
+    let project_id: Uuid =
+        sqlx::query_scalar("SELECT project_id FROM project_transfers WHERE id = $1 AND to_user_id = $2")
+            .bind(transfer_id)
+            .bind(context.user_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Transfer not found or expired".to_string()))?;
+    let owner_id = lock_project(&mut tx, project_id).await?;
+    let transfer = find_pending(&mut tx, context, transfer_id).await?;
+
+    // Propriétaire changé ou destinataire retiré depuis la demande : le transfert n'a plus lieu d'être
+    let is_member: bool = sqlx::query_scalar(
+        r#"
+        SELECT EXISTS (
+            SELECT 1 FROM project_permissions
+            WHERE project_id = $1 AND user_id = $2 AND accepted_at IS NOT NULL
+        )
+        "#,
+    )
+    .bind(transfer.project_id)
+    .bind(context.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+    if owner_id != transfer.from_user_id || !is_member {
+        sqlx::query("UPDATE project_transfers SET status = 'cancelled', responded_at = NOW() WHERE id = $1")
+            .bind(transfer.id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        return Err(AppError::Conflict("This ownership transfer is no longer valid".to_string()));
+    }
+
+    set_owner(&mut tx, transfer.project_id, context.user_id).await?;
+
+    // L'ancien propriétaire garde l'accès en tant qu'admin, avec les droits du rôle
+    sqlx::query(&format!(
+        "UPDATE project_permissions SET role = 'admin', {}, updated_at = NOW() WHERE project_id = $1 AND user_id = $2",
+        INHERIT_ROLE_PERMISSIONS
+    ))
+    .bind(transfer.project_id)
+    .bind(owner_id)
+    .execute(&mut *tx)
+    .await?;
+
+    refresh_public_count(&mut tx, owner_id).await?;
+    refresh_public_count(&mut tx, context.user_id).await?;
+
+    respond(&mut tx, &transfer, "accepted").await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(context.user_id),
+            action: "project_ownership_transferred",
+            entity_type: "project",
+            entity_id: Some(transfer.project_id),
+            details: json!({
+                "transfer_id": transfer.id,
+                "from": owner_id,
+                "to": context.user_id,
+                "reason": "transfer",
+            }),
+        },
+    )
+    .await?;
+
+    let project = sqlx::query_as::<_, Project>("SELECT * FROM projects WHERE id = $1")
+        .bind(transfer.project_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    info!(project_id = %transfer.project_id, from = %owner_id, to = %context.user_id, "Project ownership transferred");
+
+    Ok(project.into_response())
+}
+
+/// Refuse un transfert proposé à l'utilisateur
+pub async fn decline_transfer(pool: &PgPool, context: &AuthContext, transfer_id: Uuid) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+
+    let transfer = find_pending(&mut tx, context, transfer_id).await?;
+    respond(&mut tx, &transfer, "declined").await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(context.user_id),
+            action: "project_transfer_declined",
+            entity_type: "project",
+            entity_id: Some(transfer.project_id),
+            details: json!({ "transfer_id": transfer.id }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Seuls le propriétaire et un admin global disposent de la propriété du projet
+fn ensure_owner_rights(access: &ProjectAccess) -> AppResult<()> {
+    access.require(ProjectPermission::EditProject)?;
+    if !access.has_owner_rights() {
+        return Err(AppError::Forbidden(
+            "Only the project owner can transfer its ownership".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn find_pending(conn: &mut PgConnection, context: &AuthContext, transfer_id: Uuid) -> AppResult<ProjectTransfer> {
+    sqlx::query_as::<_, ProjectTransfer>(&format!(
+        "{} WHERE t.id = $1 AND t.to_user_id = $2 AND t.status = 'pending' AND t.expires_at > NOW() FOR UPDATE OF t",
+        TRANSFERS
+    ))
+    .bind(transfer_id)
+    .bind(context.user_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Transfer not found or expired".to_string()))
+}
+
+async fn respond(conn: &mut PgConnection, transfer: &ProjectTransfer, status: &str) -> AppResult<()> {
+    sqlx::query("UPDATE project_transfers SET status = $2, responded_at = NOW() WHERE id = $1")
+        .bind(transfer.id)
+        .bind(status)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Utc};
+
+    use super::*;
+    use crate::models::TransferStatus;
+
+    fn transfer(status: &str, expires_at: DateTime<Utc>) -> ProjectTransfer {
+        ProjectTransfer {
+            id: Uuid::new_v4(),
+            project_id: Uuid::new_v4(),
+            project_name: "ETTU".to_string(),
+            from_user_id: Uuid::new_v4(),
+            to_user_id: Uuid::new_v4(),
+            requested_by: None,
+            status: status.to_string(),
+            expires_at,
+            responded_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_pending_transfer_past_expiry_reads_expired() {
+        let later = Utc::now() + Duration::hours(1);
+        let earlier = Utc::now() - Duration::seconds(1);
+
+        assert_eq!(transfer("pending", later).status(), TransferStatus::Pending);
+        assert_eq!(transfer("pending", earlier).status(), TransferStatus::Expired);
+        assert_eq!(transfer("expired", later).status(), TransferStatus::Expired);
+        // Une réponse donnée avant l'expiration reste valable
+        assert_eq!(transfer("accepted", earlier).status(), TransferStatus::Accepted);
+        assert_eq!(transfer("declined", earlier).status(), TransferStatus::Declined);
+    }
+}
@@ -0,0 +1,62 @@
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::info;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::models::{PublicSnippet, PublishSnippetRequest};
+use crate::services::audit::{self, AuditEvent};
+use crate::services::verification;
+use crate::utils::error::{AppError, AppResult};
+
+/// Publie un snippet ; il n'apparaît dans la liste publique qu'une fois modéré
+pub async fn publish(
+    pool: &PgPool,
+    config: &Config,
+    context: &AuthContext,
+    request: PublishSnippetRequest,
+) -> AppResult<PublicSnippet> {
+    if !config.features.public_snippets {
+        return Err(AppError::Forbidden("Public snippets are disabled".to_string()));
+    }
+    verification::ensure_can_publish(config, context)?;
+    request.validate()?;
+
+    let mut tx = pool.begin().await?;
+
+    let snippet = sqlx::query_as::<_, PublicSnippet>(
+        r#"
+        INSERT INTO public_snippets (title, description, code, language, tags, category, author_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#,
+    )
+    .bind(&request.title)
+    .bind(&request.description)
+    .bind(&request.code)
+    .bind(&request.language)
+    .bind(json!(request.tags.unwrap_or_default()))
+    .bind(&request.category)
+    .bind(context.user_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    audit::log_event(
+        &mut tx,
+        AuditEvent {
+            actor_id: Some(context.user_id),
+            action: "public_snippet_published",
+            entity_type: "public_snippet",
+            entity_id: Some(snippet.id),
+            details: json!({ "language": snippet.language }),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(snippet_id = %snippet.id, user_id = %context.user_id, "Public snippet submitted for moderation");
+
+    Ok(snippet)
+}
@@ -0,0 +1,94 @@
+use serde_json::json;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::middleware::auth::AuthContext;
+use crate::models::{SecurityEventType, SecuritySeverity, SessionResponse, UserSession};
+use crate::services::audit::{self, SecurityEvent};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::request::ClientInfo;
+
+/// Révoque la session de la requête courante
+pub async fn logout(pool: &PgPool, context: &AuthContext, client: &ClientInfo) -> AppResult<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(context.session_id)
+        .execute(&mut *tx)
+        .await?;
+
+    audit::log_security_event(
+        &mut tx,
+        SecurityEvent {
+            event_type: SecurityEventType::Logout,
+            severity: SecuritySeverity::Low,
+            user_id: Some(context.user_id),
+            session_id: Some(context.session_id),
+            details: json!({}),
+        },
+        client,
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %context.user_id, session_id = %context.session_id, "User logged out");
+    Ok(())
+}
+
+/// Liste les sessions actives de l'utilisateur, la plus récemment utilisée en premier
+pub async fn list_sessions(pool: &PgPool, context: &AuthContext) -> AppResult<Vec<SessionResponse>> {
+    let sessions = sqlx::query_as::<_, UserSession>(
+        r#"
+        SELECT id, user_id, session_type, device_info,
+               host(ip_address) AS ip_address, user_agent, expires_at, is_persistent,
+               revoked_at, created_at, last_used_at
+        FROM user_sessions
+        WHERE user_id = $1 AND revoked_at IS NULL AND expires_at > NOW()
+        ORDER BY last_used_at DESC
+        "#,
+    )
+    .bind(context.user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sessions
+        .into_iter()
+        .map(|session| session.into_response(context.session_id))
+        .collect())
+}
+
+/// Révoque une session de l'utilisateur
+pub async fn revoke_session(pool: &PgPool, context: &AuthContext, session_id: Uuid) -> AppResult<()> {
+    let revoked = sqlx::query(
+        "UPDATE user_sessions SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL",
+    )
+    .bind(session_id)
+    .bind(context.user_id)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    if revoked == 0 {
+        return Err(AppError::NotFound("Session not found".to_string()));
+    }
+
+    info!(user_id = %context.user_id, session_id = %session_id, "Session revoked");
+    Ok(())
+}
+
+/// Révoque toutes les sessions de l'utilisateur sauf la session courante
+pub async fn revoke_other_sessions(pool: &PgPool, context: &AuthContext) -> AppResult<u64> {
+    let revoked = sqlx::query(
+        "UPDATE user_sessions SET revoked_at = NOW() WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL",
+    )
+    .bind(context.user_id)
+    .bind(context.session_id)
+    .execute(pool)
+    .await?
+    .rows_affected();
+
+    info!(user_id = %context.user_id, revoked, "Other sessions revoked");
+    Ok(revoked)
+}
@@ -0,0 +1,563 @@
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::{AuthConfig, Config};
+use crate::middleware::auth::AuthContext;
+use crate::models::{
+    LoginResponse, MfaChallenge, TwoFactorConfirmRequest, TwoFactorConfirmResponse,
+    TwoFactorDisableRequest, TwoFactorEnrollRequest, TwoFactorEnrollResponse,
+    TwoFactorVerifyRequest, User, UserRole,
+};
+use crate::services::lockout::{LoginAccount, LoginGuard};
+use crate::services::{auth, user};
+use crate::utils::crypto::SecretCipher;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::jwt::JwtKeys;
+use crate::utils::password::PasswordHasher;
+use crate::utils::request::ClientInfo;
+use crate::utils::token::hash_token;
+use crate::utils::totp;
+
+/// Durée de validité d'un challenge de connexion
+pub const MFA_CHALLENGE_TTL_SECS: i64 = 300;
+
+/// Nombre de codes de secours générés à la confirmation
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Émetteur affiché par les applications d'authentification
+const TOTP_ISSUER: &str = "ETTU";
+
+const MFA_CHALLENGE_PURPOSE: &str = "mfa_challenge";
+
+/// Claims du challenge émis après un mot de passe valide
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaChallengeClaims {
+    pub sub: Uuid,
+    pub purpose: String,
+    /// Le challenge ne sert qu'à l'enrôlement imposé, pas à la vérification
+    pub enrollment: bool,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Configuration TOTP d'un compte
+#[derive(Debug, sqlx::FromRow)]
+struct TwoFactorSettings {
+    secret: String,
+    confirmed_at: Option<DateTime<Utc>>,
+    last_used_step: Option<i64>,
+}
+
+/// Indique si la configuration impose la double authentification à ce rôle
+pub fn is_enforced(config: &AuthConfig, role: UserRole) -> bool {
+    config.mfa_enforced_roles.contains(&role)
+}
+
+/// Signe un challenge de connexion pour l'utilisateur, valable à partir de `now`
+pub fn issue_challenge(keys: &JwtKeys, user_id: Uuid, enrollment: bool, now: DateTime<Utc>) -> AppResult<MfaChallenge> {
+    let expires_at = now + Duration::seconds(MFA_CHALLENGE_TTL_SECS);
+    let claims = MfaChallengeClaims {
+        sub: user_id,
+        purpose: MFA_CHALLENGE_PURPOSE.to_string(),
+        enrollment,
+        iat: now.timestamp(),
+        exp: expires_at.timestamp(),
+    };
+
+    Ok(MfaChallenge {
+        challenge_token: keys.sign(&claims)?,
+        expires_at,
+        enrollment_required: enrollment,
+    })
+}
+
+/// Vérifie la signature, l'expiration à l'instant `now` et l'usage d'un challenge
+pub fn validate_challenge(keys: &JwtKeys, token: &str, now: DateTime<Utc>) -> AppResult<MfaChallengeClaims> {
+    let claims: MfaChallengeClaims = keys.verify_at(token, now).map_err(|_| invalid_challenge())?;
+    if claims.purpose != MFA_CHALLENGE_PURPOSE {
+        return Err(invalid_challenge());
+    }
+
+    Ok(claims)
+}
+
+/// Compte à vérifier par un code : un challenge d'enrôlement ne l'ouvre pas
+fn verification_subject(claims: &MfaChallengeClaims) -> AppResult<Uuid> {
+    if claims.enrollment {
+        return Err(AppError::Forbidden("Two-factor enrollment is required".to_string()));
+    }
+
+    Ok(claims.sub)
+}
+
+/// Compte à enrôler : seul un challenge d'enrôlement le désigne
+fn enrollment_subject(claims: &MfaChallengeClaims) -> AppResult<Uuid> {
+    if !claims.enrollment {
+        return Err(invalid_challenge());
+    }
+
+    Ok(claims.sub)
+}
+
+fn invalid_challenge() -> AppError {
+    AppError::Unauthorized("Invalid or expired challenge".to_string())
+}
+
+/// Retourne le challenge à présenter à la connexion, si le compte en requiert un
+pub async fn challenge_for(pool: &PgPool, config: &Config, user: &User) -> AppResult<Option<MfaChallenge>> {
+    let confirmed = find_settings(pool, config, user.id)
+        .await?
+        .is_some_and(|settings| settings.confirmed_at.is_some());
+
+    let keys = JwtKeys::from_config(config);
+    if confirmed {
+        issue_challenge(&keys, user.id, false, Utc::now()).map(Some)
+    } else if is_enforced(&config.auth, user.role()) {
+        issue_challenge(&keys, user.id, true, Utc::now()).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Génère un nouveau secret TOTP, à confirmer par un premier code
+///
+/// L'appel est possible depuis une session de compte ou avec le challenge d'un
+/// compte contraint de s'enrôler. Un secret non confirmé est remplacé.
+pub async fn enroll(
+    pool: &PgPool,
+    config: &Config,
+    context: Option<&AuthContext>,
+    request: TwoFactorEnrollRequest,
+) -> AppResult<TwoFactorEnrollResponse> {
+    let user = resolve_subject(pool, config, context, request.challenge_token.as_deref()).await?;
+
+    if find_settings(pool, config, user.id)
+        .await?
+        .is_some_and(|settings| settings.confirmed_at.is_some())
+    {
+        return Err(AppError::Conflict("Two-factor authentication is already enabled".to_string()));
+    }
+
+    let secret = totp::generate_secret();
+    sqlx::query(
+        r#"
+        INSERT INTO user_two_factor (user_id, secret)
+        VALUES ($1, $2)
+        ON CONFLICT (user_id) DO UPDATE
+            SET secret = EXCLUDED.secret, last_used_step = NULL, created_at = NOW()
+            WHERE user_two_factor.confirmed_at IS NULL
+        "#,
+    )
+    .bind(user.id)
+    .bind(SecretCipher::from_config(config)?.encrypt(&secret, user.id.as_bytes())?)
+    .execute(pool)
+    .await?;
+
+    let account = user
+        .email
+        .clone()
+        .or_else(|| user.username.clone())
+        .unwrap_or_else(|| user.id.to_string());
+
+    Ok(TwoFactorEnrollResponse {
+        provisioning_uri: totp::provisioning_uri(&secret, &account, TOTP_ISSUER),
+        secret,
+    })
+}
+
+/// Active la double authentification après vérification d'un premier code
+///
+/// Les codes de secours sont retournés en clair une seule fois. Lorsque
+/// l'enrôlement termine une connexion, la session est ouverte dans la foulée.
+pub async fn confirm(
+    pool: &PgPool,
+    config: &Config,
+    context: Option<&AuthContext>,
+    request: TwoFactorConfirmRequest,
+    client: &ClientInfo,
+) -> AppResult<TwoFactorConfirmResponse> {
+    let user = resolve_subject(pool, config, context, request.challenge_token.as_deref()).await?;
+
+    let settings = find_settings(pool, config, user.id)
+        .await?
+        .filter(|settings| settings.confirmed_at.is_none())
+        .ok_or_else(|| AppError::BadRequest("No pending two-factor enrollment".to_string()))?;
+
+    let mut tx = pool.begin().await?;
+
+    if !check_totp(&mut tx, user.id, &settings, &request.code).await? {
+        return Err(AppError::BadRequest("Invalid two-factor code".to_string()));
+    }
+
+    sqlx::query("UPDATE user_two_factor SET confirmed_at = NOW() WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+    let recovery_codes = replace_recovery_codes(&mut tx, user.id).await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, "Two-factor authentication enabled");
+
+    let session = match request.challenge_token {
+        Some(_) => Some(auth::complete_login(pool, config, &user, client).await?),
+        None => None,
+    };
+
+    Ok(TwoFactorConfirmResponse {
+        recovery_codes,
+        session,
+    })
+}
+
+/// Échange un challenge et un code TOTP ou de secours contre une session
+pub async fn verify(
+    pool: &PgPool,
+    config: &Config,
+    guard: &LoginGuard,
+    request: TwoFactorVerifyRequest,
+    client: &ClientInfo,
+) -> AppResult<LoginResponse> {
+    let claims = validate_challenge(&JwtKeys::from_config(config), &request.challenge_token, Utc::now())?;
+    let user_id = verification_subject(&claims)?;
+
+    let user = user::find_by_id(pool, user_id)
+        .await?
+        .filter(|user| user.is_registered())
+        .ok_or_else(invalid_challenge)?;
+    if !user.is_active {
+        return Err(AppError::Forbidden("Account is disabled".to_string()));
+    }
+    guard.check(pool, Some(LoginAccount::Known(user.id)), client).await?;
+
+    let settings = find_settings(pool, config, user.id)
+        .await?
+        .filter(|settings| settings.confirmed_at.is_some())
+        .ok_or_else(invalid_challenge)?;
+
+    let mut tx = pool.begin().await?;
+    if !check_second_factor(&mut tx, user.id, &settings, &request.code).await? {
+        drop(tx);
+        warn!(user_id = %user.id, "Invalid second factor");
+        guard.record_failure(pool, Some(LoginAccount::Known(user.id)), client, "invalid_second_factor").await?;
+        return Err(AppError::Unauthorized("Invalid two-factor code".to_string()));
+    }
+    tx.commit().await?;
+    guard.record_success(user.id).await;
+
+    auth::complete_login(pool, config, &user, client).await
+}
+
+/// Désactive la double authentification du compte courant
+///
+/// Le mot de passe et un code valide sont exigés ; impossible pour un rôle
+/// auquel la configuration l'impose.
+pub async fn disable(
+    pool: &PgPool,
+    config: &Config,
+    context: &AuthContext,
+    request: TwoFactorDisableRequest,
+) -> AppResult<()> {
+    if is_enforced(&config.auth, context.role) {
+        return Err(AppError::Forbidden(
+            "Two-factor authentication is mandatory for this role".to_string(),
+        ));
+    }
+
+    let user = user::find_by_id(pool, context.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    let password_hash = user
+        .password_hash
+        .clone()
+        .ok_or_else(|| AppError::Forbidden("A password is required".to_string()))?;
+
+    if !PasswordHasher::from_config(config)
+        .verify(request.password, password_hash)
+        .await?
+    {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
+
+    let settings = find_settings(pool, config, user.id)
+        .await?
+        .filter(|settings| settings.confirmed_at.is_some())
+        .ok_or_else(|| AppError::BadRequest("Two-factor authentication is not enabled".to_string()))?;
+
+    let mut tx = pool.begin().await?;
+
+    if !check_second_factor(&mut tx, user.id, &settings, &request.code).await? {
+        return Err(AppError::Unauthorized("Invalid two-factor code".to_string()));
+    }
+
+    sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM user_two_factor WHERE user_id = $1")
+        .bind(user.id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, "Two-factor authentication disabled");
+
+    Ok(())
+}
+
+/// Identifie le compte visé, par sa session ou par un challenge d'enrôlement
+async fn resolve_subject(
+    pool: &PgPool,
+    config: &Config,
+    context: Option<&AuthContext>,
+    challenge_token: Option<&str>,
+) -> AppResult<User> {
+    let user_id = match (challenge_token, context) {
+        (Some(token), _) => {
+            let claims = validate_challenge(&JwtKeys::from_config(config), token, Utc::now())?;
+            enrollment_subject(&claims)?
+        }
+        (None, Some(context)) if context.is_registered() => context.user_id,
+        (None, Some(_)) => {
+            return Err(AppError::Forbidden("A registered account is required".to_string()))
+        }
+        (None, None) => return Err(AppError::Unauthorized("Authentication required".to_string())),
+    };
+
+    let user = user::find_by_id(pool, user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+    if !user.is_registered() {
+        return Err(AppError::Forbidden("A registered account is required".to_string()));
+    }
+
+    Ok(user)
+}
+
+/// Configuration TOTP du compte, secret déchiffré
+///
+/// Un secret enregistré en clair avant le chiffrement est chiffré au passage.
+async fn find_settings(pool: &PgPool, config: &Config, user_id: Uuid) -> AppResult<Option<TwoFactorSettings>> {
+    let Some(mut settings) = sqlx::query_as::<_, TwoFactorSettings>(
+        "SELECT secret, confirmed_at, last_used_step FROM user_two_factor WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?
+    else {
+        return Ok(None);
+    };
+
+    let cipher = SecretCipher::from_config(config)?;
+    if SecretCipher::is_encrypted(&settings.secret) {
+        settings.secret = cipher.decrypt(&settings.secret, user_id.as_bytes())?;
+    } else {
+        sqlx::query("UPDATE user_two_factor SET secret = $3 WHERE user_id = $1 AND secret = $2")
+            .bind(user_id)
+            .bind(&settings.secret)
+            .bind(cipher.encrypt(&settings.secret, user_id.as_bytes())?)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(Some(settings))
+}
+
+/// Vérifie un code TOTP et consomme sa fenêtre
+///
+/// La mise à jour conditionnelle de `last_used_step` empêche deux requêtes
+/// concurrentes d'accepter le même code.
+async fn check_totp(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    settings: &TwoFactorSettings,
+    code: &str,
+) -> AppResult<bool> {
+    let now = Utc::now().timestamp() as u64;
+    let last_used_step = settings.last_used_step.map(|step| step as u64);
+    let Some(step) = totp::verify(&settings.secret, code, now, last_used_step) else {
+        return Ok(false);
+    };
+
+    let accepted = sqlx::query(
+        r#"
+        UPDATE user_two_factor SET last_used_step = $2
+        WHERE user_id = $1 AND (last_used_step IS NULL OR last_used_step < $2)
+        "#,
+    )
+    .bind(user_id)
+    .bind(step as i64)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected()
+        == 1;
+
+    Ok(accepted)
+}
+
+/// Accepte un code TOTP ou, à défaut, un code de secours inutilisé
+async fn check_second_factor(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    settings: &TwoFactorSettings,
+    code: &str,
+) -> AppResult<bool> {
+    if check_totp(conn, user_id, settings, code).await? {
+        return Ok(true);
+    }
+
+    let used = sqlx::query(
+        r#"
+        UPDATE user_recovery_codes SET used_at = NOW()
+        WHERE id = (
+            SELECT id FROM user_recovery_codes
+            WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL
+            LIMIT 1
+            FOR UPDATE
+        )
+        "#,
+    )
+    .bind(user_id)
+    .bind(hash_token(&normalize_recovery_code(code)))
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    if used == 1 {
+        info!(user_id = %user_id, "Recovery code used");
+    }
+
+    Ok(used == 1)
+}
+
+/// Remplace les codes de secours du compte et retourne les nouveaux en clair
+async fn replace_recovery_codes(conn: &mut PgConnection, user_id: Uuid) -> AppResult<Vec<String>> {
+    sqlx::query("DELETE FROM user_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+    for code in &codes {
+        sqlx::query("INSERT INTO user_recovery_codes (user_id, code_hash) VALUES ($1, $2)")
+            .bind(user_id)
+            .bind(hash_token(&normalize_recovery_code(code)))
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(codes)
+}
+
+/// Code de secours lisible, au format `xxxxx-xxxxx`
+fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let code = hex::encode(bytes);
+    format!("{}-{}", &code[..5], &code[5..])
+}
+
+/// Forme canonique d'un code de secours, insensible à la casse et aux séparateurs
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn keys() -> JwtKeys {
+        JwtKeys::new("k1", "secret-1")
+    }
+
+    fn fixed_now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_challenge_valid_until_expiry() {
+        let user_id = Uuid::new_v4();
+        let challenge = issue_challenge(&keys(), user_id, false, fixed_now()).unwrap();
+        assert_eq!(challenge.expires_at, fixed_now() + Duration::seconds(MFA_CHALLENGE_TTL_SECS));
+
+        let claims = validate_challenge(&keys(), &challenge.challenge_token, fixed_now()).unwrap();
+        assert_eq!(claims.sub, user_id);
+
+        let just_before = challenge.expires_at - Duration::seconds(1);
+        assert!(validate_challenge(&keys(), &challenge.challenge_token, just_before).is_ok());
+        assert!(validate_challenge(&keys(), &challenge.challenge_token, challenge.expires_at).is_err());
+        assert!(validate_challenge(&JwtKeys::new("k1", "other"), &challenge.challenge_token, fixed_now()).is_err());
+    }
+
+    #[test]
+    fn test_challenge_rejects_other_purpose() {
+        let now = fixed_now();
+        let claims = MfaChallengeClaims {
+            sub: Uuid::new_v4(),
+            purpose: "email_verification".to_string(),
+            enrollment: false,
+            iat: now.timestamp(),
+            exp: (now + Duration::minutes(5)).timestamp(),
+        };
+        let token = keys().sign(&claims).unwrap();
+
+        assert!(validate_challenge(&keys(), &token, now).is_err());
+    }
+
+    #[test]
+    fn test_enrollment_flag_routes_the_challenge() {
+        let user_id = Uuid::new_v4();
+        let decode = |enrollment| {
+            let challenge = issue_challenge(&keys(), user_id, enrollment, fixed_now()).unwrap();
+            validate_challenge(&keys(), &challenge.challenge_token, fixed_now()).unwrap()
+        };
+
+        // Un challenge d'enrôlement ne permet pas de se connecter avec un code
+        let enrollment = decode(true);
+        assert!(matches!(verification_subject(&enrollment), Err(AppError::Forbidden(_))));
+        assert_eq!(enrollment_subject(&enrollment).unwrap(), user_id);
+
+        let login = decode(false);
+        assert_eq!(verification_subject(&login).unwrap(), user_id);
+        assert!(matches!(enrollment_subject(&login), Err(AppError::Unauthorized(_))));
+    }
+
+    #[test]
+    fn test_enforced_roles() {
+        let config = AuthConfig {
+            access_token_expiration: 3600,
+            refresh_token_expiration: 2_592_000,
+            guest_session_expiration: 2_592_000,
+            bcrypt_cost: 4,
+            jwt_key_id: "k1".to_string(),
+            jwt_previous_keys: Vec::new(),
+            mfa_enforced_roles: vec![UserRole::Admin, UserRole::Moderator],
+            deletion_grace_period: 2_592_000,
+            two_factor_encryption_key: String::new(),
+        };
+
+        assert!(is_enforced(&config, UserRole::Admin));
+        assert!(is_enforced(&config, UserRole::Moderator));
+        assert!(!is_enforced(&config, UserRole::User));
+        assert!(!is_enforced(&AuthConfig { mfa_enforced_roles: Vec::new(), ..config }, UserRole::Admin));
+    }
+
+    #[test]
+    fn test_recovery_code_normalization() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), 11);
+        assert_eq!(normalize_recovery_code(&code), code.replace('-', ""));
+        assert_eq!(normalize_recovery_code(" AB12C-de34F "), "ab12cde34f");
+    }
+}
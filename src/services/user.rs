@@ -1 +1,230 @@
-// User service - TODO: Implement
+use chrono::Utc;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::models::{PublicProfileResponse, UpdateUserRequest, User, UserResponse};
+use crate::services::avatar;
+use crate::services::email::EmailService;
+use crate::services::{project_member, verification};
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::password::PasswordHasher;
+use crate::utils::storage::Storage;
+
+/// Références sans `ON DELETE CASCADE` qui bloqueraient la suppression d'un utilisateur
+pub const NULLABLE_REFERENCES: &[&str] = &[
+    "UPDATE project_permissions SET invited_by = NULL WHERE invited_by = ANY($1)",
+    "UPDATE project_notes SET last_edited_by = NULL WHERE last_edited_by = ANY($1)",
+    "UPDATE project_snippets SET last_edited_by = NULL WHERE last_edited_by = ANY($1)",
+    "UPDATE tasks SET assignee_id = NULL WHERE assignee_id = ANY($1)",
+    "UPDATE public_snippets SET moderated_by = NULL WHERE moderated_by = ANY($1)",
+    "UPDATE security_audit_logs SET investigated_by = NULL WHERE investigated_by = ANY($1)",
+    // Les snippets publics issus de projets ou snippets supprimés avec l'utilisateur gardent leur contenu
+    "UPDATE public_snippets SET source_project_id = NULL
+     WHERE source_project_id IN (SELECT id FROM projects WHERE owner_id = ANY($1))",
+    "UPDATE public_snippets SET source_snippet_id = NULL
+     WHERE source_snippet_id IN (
+         SELECT s.id FROM project_snippets s JOIN projects p ON p.id = s.project_id
+         WHERE p.owner_id = ANY($1) OR s.author_id = ANY($1)
+     )",
+];
+
+/// Recherche un utilisateur par identifiant
+pub async fn find_by_id(pool: &PgPool, id: Uuid) -> AppResult<Option<User>> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(user)
+}
+
+/// Recherche un utilisateur par email (insensible à la casse grâce à CITEXT)
+pub async fn find_by_email(pool: &PgPool, email: &str) -> AppResult<Option<User>> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE email = $1::citext")
+        .bind(email)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(user)
+}
+
+/// Recherche un utilisateur par nom d'utilisateur, sans tenir compte de la casse
+pub async fn find_by_username(pool: &PgPool, username: &str) -> AppResult<Option<User>> {
+    let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE lower(username) = lower($1)")
+        .bind(username)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(user)
+}
+
+/// Retourne le compte de la session courante
+pub async fn current_user(pool: &PgPool, context: &AuthContext) -> AppResult<UserResponse> {
+    find_by_id(pool, context.user_id)
+        .await?
+        .map(User::into_response)
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+}
+
+/// Applique une mise à jour partielle au compte courant
+///
+/// Un invité ne peut pas se donner d'email ni de nom d'utilisateur : il doit
+/// passer par la migration. Changer d'email exige le mot de passe actuel et une
+/// session : l'adresse sert à réinitialiser le mot de passe, un token personnel
+/// volé ne doit pas permettre de prendre le compte. Le changement invalide la
+/// vérification de l'adresse.
+pub async fn update_current_user(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    storage: &dyn Storage,
+    context: &AuthContext,
+    request: UpdateUserRequest,
+) -> AppResult<UserResponse> {
+    request.validate()?;
+
+    if request.settings.as_ref().is_some_and(|settings| !settings.is_object()) {
+        return Err(AppError::BadRequest("Settings must be a JSON object".to_string()));
+    }
+    if context.is_guest() && (request.email.is_some() || request.username.is_some()) {
+        return Err(AppError::Forbidden(
+            "A registered account is required to set an email or username".to_string(),
+        ));
+    }
+
+    let current = find_by_id(pool, context.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+
+    let email_changed = request.email.as_deref().is_some_and(|email| {
+        current
+            .email
+            .as_deref()
+            .is_none_or(|current| !current.eq_ignore_ascii_case(email))
+    });
+    if email_changed {
+        if context.is_personal_token() {
+            return Err(AppError::Forbidden(
+                "Email changes require a session, not a personal access token".to_string(),
+            ));
+        }
+
+        // Un compte créé via OAuth n'a pas de mot de passe : la session suffit
+        if let Some(password_hash) = current.password_hash.clone() {
+            let password = request
+                .current_password
+                .clone()
+                .ok_or_else(|| AppError::Unauthorized("Current password is required".to_string()))?;
+            if !PasswordHasher::from_config(config)
+                .verify(password, password_hash)
+                .await?
+            {
+                return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+            }
+        }
+
+        if let Some(email) = request.email.as_deref() {
+            if find_by_email(pool, email).await?.is_some() {
+                return Err(AppError::Conflict("Email is already in use".to_string()));
+            }
+        }
+    }
+    if let Some(username) = request.username.as_deref() {
+        // Le compte courant peut changer la casse de son propre nom
+        let taken = find_by_username(pool, username).await?.is_some_and(|user| user.id != current.id);
+        if taken {
+            return Err(AppError::Conflict("Username is already taken".to_string()));
+        }
+    }
+
+    let send_verification = email_changed && config.features.email_verification;
+
+    let mut tx = pool.begin().await?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users SET
+            email = COALESCE($2, email),
+            username = COALESCE($3, username),
+            display_name = COALESCE($4, display_name),
+            avatar_url = NULLIF(COALESCE($5, avatar_url), ''),
+            bio = NULLIF(COALESCE($6, bio), ''),
+            location = NULLIF(COALESCE($7, location), ''),
+            website = NULLIF(COALESCE($8, website), ''),
+            theme = COALESCE($9, theme),
+            language = COALESCE($10, language),
+            timezone = COALESCE($11, timezone),
+            settings = COALESCE($12, settings),
+            is_verified = is_verified AND NOT $13,
+            email_verified_at = CASE WHEN $13 THEN NULL ELSE email_verified_at END,
+            email_verification_sent_at = CASE WHEN $13 THEN $14 ELSE email_verification_sent_at END
+        WHERE id = $1
+        RETURNING *
+        "#,
+    )
+    .bind(context.user_id)
+    .bind(&request.email)
+    .bind(&request.username)
+    .bind(&request.display_name)
+    .bind(&request.avatar_url)
+    .bind(&request.bio)
+    .bind(&request.location)
+    .bind(&request.website)
+    .bind(&request.theme)
+    .bind(&request.language)
+    .bind(&request.timezone)
+    .bind(&request.settings)
+    .bind(email_changed)
+    .bind(send_verification.then(Utc::now))
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| match e {
+        // Changement concurrent vers le même email ou nom d'utilisateur
+        sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+            AppError::Conflict("Email or username is already in use".to_string())
+        }
+        e => AppError::Database(e),
+    })?;
+
+    if let Some(email) = user.email.as_deref().filter(|_| email_changed) {
+        project_member::start_held_invitations(&mut tx, config, email).await?;
+    }
+
+    tx.commit().await?;
+
+    info!(user_id = %user.id, email_changed, "User profile updated");
+
+    if let Some(previous) = current.avatar_url.as_deref() {
+        if user.avatar_url.as_deref() != Some(previous) {
+            avatar::remove_stored(storage, user.id, previous).await;
+        }
+    }
+
+    if send_verification {
+        verification::send_verification_email(config, email_service, &user)?;
+    }
+
+    Ok(user.into_response())
+}
+
+/// Retourne le profil public d'un compte actif
+pub async fn find_public_profile(pool: &PgPool, username: &str) -> AppResult<PublicProfileResponse> {
+    sqlx::query_as::<_, PublicProfileResponse>(
+        r#"
+        SELECT id, username, display_name, avatar_url, bio, location, website,
+               COALESCE(public_snippets_count, 0) AS public_snippets_count,
+               COALESCE(public_projects_count, 0) AS public_projects_count,
+               created_at
+        FROM users
+        WHERE lower(username) = lower($1) AND user_type <> 'guest' AND is_active
+        "#,
+    )
+    .bind(username)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("User not found".to_string()))
+}
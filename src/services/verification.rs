@@ -0,0 +1,210 @@
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::middleware::auth::AuthContext;
+use crate::models::{User, UserResponse, VerifyEmailRequest};
+use crate::services::email::EmailService;
+use crate::services::user;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::jwt::JwtKeys;
+
+/// Durée de validité d'un lien de vérification d'email
+pub const EMAIL_VERIFICATION_TTL_HOURS: i64 = 24;
+
+/// Délai minimal entre deux envois d'email de vérification, en secondes
+pub const RESEND_THROTTLE_SECS: i64 = 300;
+
+const EMAIL_VERIFICATION_PURPOSE: &str = "email_verification";
+
+/// Claims d'un token signé de vérification d'email
+///
+/// L'adresse est incluse pour qu'un lien devienne invalide si l'email du compte change.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailVerificationClaims {
+    pub sub: Uuid,
+    pub email: String,
+    pub purpose: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Signe un token de vérification pour l'adresse de l'utilisateur
+pub fn issue_email_verification_token(config: &Config, user_id: Uuid, email: &str) -> AppResult<String> {
+    JwtKeys::from_config(config).sign(&verification_claims(user_id, email))
+}
+
+/// Vérifie la signature, l'expiration et l'usage d'un token de vérification
+pub fn validate_email_verification_token(config: &Config, token: &str) -> AppResult<EmailVerificationClaims> {
+    decode_verification_token(&JwtKeys::from_config(config), token)
+}
+
+fn verification_claims(user_id: Uuid, email: &str) -> EmailVerificationClaims {
+    let now = Utc::now();
+    EmailVerificationClaims {
+        sub: user_id,
+        email: email.to_string(),
+        purpose: EMAIL_VERIFICATION_PURPOSE.to_string(),
+        iat: now.timestamp(),
+        exp: (now + Duration::hours(EMAIL_VERIFICATION_TTL_HOURS)).timestamp(),
+    }
+}
+
+fn decode_verification_token(keys: &JwtKeys, token: &str) -> AppResult<EmailVerificationClaims> {
+    let invalid_token = || AppError::BadRequest("Invalid or expired verification token".to_string());
+
+    let claims: EmailVerificationClaims = keys.verify(token).map_err(|_| invalid_token())?;
+    if claims.purpose != EMAIL_VERIFICATION_PURPOSE {
+        return Err(invalid_token());
+    }
+
+    Ok(claims)
+}
+
+/// Marque l'adresse du compte comme vérifiée
+///
+/// Le lien n'est accepté que si l'email du compte n'a pas changé depuis son envoi.
+/// Un compte déjà vérifié est retourné tel quel.
+pub async fn verify_email(pool: &PgPool, config: &Config, request: VerifyEmailRequest) -> AppResult<UserResponse> {
+    let claims = validate_email_verification_token(config, &request.token)?;
+
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET is_verified = TRUE, email_verified_at = COALESCE(email_verified_at, NOW())
+        WHERE id = $1 AND email = $2::citext
+        RETURNING *
+        "#,
+    )
+    .bind(claims.sub)
+    .bind(&claims.email)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::BadRequest("Invalid or expired verification token".to_string()))?;
+
+    info!(user_id = %user.id, "Email verified");
+    Ok(user.into_response())
+}
+
+/// Renvoie un lien de vérification, au plus une fois par `RESEND_THROTTLE_SECS`
+pub async fn resend_verification(
+    pool: &PgPool,
+    config: &Config,
+    email_service: &EmailService,
+    context: &AuthContext,
+) -> AppResult<()> {
+    if !config.features.email_verification {
+        return Err(AppError::BadRequest("Email verification is disabled".to_string()));
+    }
+
+    // La mise à jour conditionnelle sert de verrou : deux renvois simultanés ne
+    // peuvent pas passer tous les deux
+    let user = sqlx::query_as::<_, User>(
+        r#"
+        UPDATE users
+        SET email_verification_sent_at = NOW()
+        WHERE id = $1
+          AND NOT is_verified
+          AND (email_verification_sent_at IS NULL
+               OR email_verification_sent_at < NOW() - make_interval(secs => $2))
+        RETURNING *
+        "#,
+    )
+    .bind(context.user_id)
+    .bind(RESEND_THROTTLE_SECS as f64)
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(user) = user else {
+        let current = user::find_by_id(pool, context.user_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound("User not found".to_string()))?;
+        if current.is_verified {
+            return Err(AppError::Conflict("Email is already verified".to_string()));
+        }
+        return Err(AppError::TooManyRequests(
+            "A verification email was sent recently, please wait before retrying".to_string(),
+        ));
+    };
+
+    send_verification_email(config, email_service, &user)
+}
+
+/// Refuse la publication publique aux comptes non vérifiés quand la vérification est activée
+pub fn ensure_can_publish(config: &Config, context: &AuthContext) -> AppResult<()> {
+    if config.features.email_verification && !context.is_verified {
+        return Err(AppError::Forbidden(
+            "Verify your email address before publishing".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Envoie le lien de vérification en arrière-plan
+///
+/// Un échec d'envoi est journalisé sans faire échouer la requête : l'utilisateur
+/// pourra redemander un lien.
+pub fn send_verification_email(config: &Config, email_service: &EmailService, user: &User) -> AppResult<()> {
+    let Some(email) = user.email.clone() else {
+        return Ok(());
+    };
+
+    let token = issue_email_verification_token(config, user.id, &email)?;
+    let link = format!(
+        "{}/verify-email?token={}",
+        config.server.frontend_url.trim_end_matches('/'),
+        token
+    );
+    let name = user
+        .display_name
+        .clone()
+        .or_else(|| user.username.clone())
+        .unwrap_or_default();
+
+    let email_service = email_service.clone();
+    let user_id = user.id;
+    tokio::spawn(async move {
+        if let Err(e) = email_service
+            .send_email_verification(&email, &name, &link, EMAIL_VERIFICATION_TTL_HOURS)
+            .await
+        {
+            warn!(user_id = %user_id, "Failed to send verification email: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::two_factor::MfaChallengeClaims;
+    use crate::utils::jwt::AccessClaims;
+
+    #[test]
+    fn test_token_is_bound_to_its_purpose() {
+        let keys = JwtKeys::new("k1", "secret-1");
+        let mut claims = verification_claims(Uuid::new_v4(), "ada@example.com");
+
+        let token = keys.sign(&claims).unwrap();
+        assert_eq!(decode_verification_token(&keys, &token).unwrap().sub, claims.sub);
+
+        // Même signature et même forme, mais émis pour un autre usage
+        claims.purpose = "mfa_challenge".to_string();
+        let token = keys.sign(&claims).unwrap();
+        assert!(decode_verification_token(&keys, &token).is_err());
+    }
+
+    #[test]
+    fn test_verification_token_is_not_a_session_or_challenge() {
+        let keys = JwtKeys::new("k1", "secret-1");
+        let token = keys.sign(&verification_claims(Uuid::new_v4(), "ada@example.com")).unwrap();
+
+        assert!(keys.verify::<AccessClaims>(&token).is_err());
+        assert!(keys.verify::<MfaChallengeClaims>(&token).is_err());
+    }
+}
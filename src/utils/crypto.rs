@@ -0,0 +1,104 @@
+use data_encoding::{BASE64, HEXLOWER_PERMISSIVE};
+use rand::RngCore;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+
+use crate::config::Config;
+use crate::utils::error::{AppError, AppResult};
+
+/// Préfixe des valeurs chiffrées, pour les distinguer d'anciennes valeurs en clair
+const ENCRYPTED_PREFIX: &str = "v1:";
+
+/// Chiffrement symétrique (AES-256-GCM) des secrets qui doivent rester lisibles par le serveur
+///
+/// Les données associées lient le chiffré à son propriétaire : une valeur recopiée
+/// sur la ligne d'un autre utilisateur ne se déchiffre pas.
+#[derive(Clone)]
+pub struct SecretCipher {
+    key: LessSafeKey,
+}
+
+impl SecretCipher {
+    pub fn new(key: &[u8]) -> AppResult<Self> {
+        let key = UnboundKey::new(&AES_256_GCM, key)
+            .map_err(|_| AppError::Internal("Encryption key must be 32 bytes".to_string()))?;
+
+        Ok(Self {
+            key: LessSafeKey::new(key),
+        })
+    }
+
+    /// Clé de chiffrement des secrets TOTP, `TWO_FACTOR_ENCRYPTION_KEY` en hexadécimal
+    pub fn from_config(config: &Config) -> AppResult<Self> {
+        let key = HEXLOWER_PERMISSIVE
+            .decode(config.auth.two_factor_encryption_key.as_bytes())
+            .map_err(|_| AppError::Internal("Invalid TWO_FACTOR_ENCRYPTION_KEY".to_string()))?;
+
+        Self::new(&key)
+    }
+
+    pub fn encrypt(&self, plaintext: &str, associated_data: &[u8]) -> AppResult<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(associated_data), &mut sealed)
+            .map_err(|_| AppError::Internal("Encryption failed".to_string()))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend_from_slice(&sealed);
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, BASE64.encode(&payload)))
+    }
+
+    pub fn decrypt(&self, stored: &str, associated_data: &[u8]) -> AppResult<String> {
+        let invalid = || AppError::Internal("Stored secret cannot be decrypted".to_string());
+
+        let encoded = stored.strip_prefix(ENCRYPTED_PREFIX).ok_or_else(invalid)?;
+        let mut payload = BASE64.decode(encoded.as_bytes()).map_err(|_| invalid())?;
+        if payload.len() < NONCE_LEN {
+            return Err(invalid());
+        }
+
+        let (nonce, sealed) = payload.split_at_mut(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| invalid())?;
+        let plaintext = self
+            .key
+            .open_in_place(nonce, Aad::from(associated_data), sealed)
+            .map_err(|_| invalid())?;
+
+        String::from_utf8(plaintext.to_vec()).map_err(|_| invalid())
+    }
+
+    /// Indique si une valeur stockée est chiffrée, ou antérieure au chiffrement
+    pub fn is_encrypted(stored: &str) -> bool {
+        stored.starts_with(ENCRYPTED_PREFIX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let cipher = SecretCipher::new(&[7u8; 32]).unwrap();
+        let stored = cipher.encrypt("JBSWY3DPEHPK3PXP", b"user-1").unwrap();
+
+        assert!(SecretCipher::is_encrypted(&stored));
+        assert!(!stored.contains("JBSWY3DPEHPK3PXP"));
+        assert_eq!(cipher.decrypt(&stored, b"user-1").unwrap(), "JBSWY3DPEHPK3PXP");
+        // Nonce aléatoire : deux chiffrements du même secret diffèrent
+        assert_ne!(cipher.encrypt("JBSWY3DPEHPK3PXP", b"user-1").unwrap(), stored);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_other_owner_or_key() {
+        let cipher = SecretCipher::new(&[7u8; 32]).unwrap();
+        let stored = cipher.encrypt("JBSWY3DPEHPK3PXP", b"user-1").unwrap();
+
+        assert!(cipher.decrypt(&stored, b"user-2").is_err());
+        assert!(SecretCipher::new(&[8u8; 32]).unwrap().decrypt(&stored, b"user-1").is_err());
+        assert!(cipher.decrypt("JBSWY3DPEHPK3PXP", b"user-1").is_err());
+        assert!(SecretCipher::new(&[7u8; 16]).is_err());
+    }
+}
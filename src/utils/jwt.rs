@@ -1 +1,205 @@
-// JWT utilities - TODO: Implement
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::models::{User, UserRole, UserType};
+use crate::utils::error::{AppError, AppResult};
+
+/// Claims d'un token d'accès
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessClaims {
+    /// Identifiant de l'utilisateur
+    pub sub: Uuid,
+    pub user_type: UserType,
+    pub role: UserRole,
+    /// Session `user_sessions` à laquelle le token est rattaché
+    pub sid: Uuid,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+/// Jeu de clés HS256 identifiées par leur `kid`
+///
+/// Les tokens sont signés avec la clé active ; les anciennes clés restent
+/// acceptées en vérification pour qu'une rotation ne déconnecte personne.
+pub struct JwtKeys {
+    active_kid: String,
+    secrets: HashMap<String, Vec<u8>>,
+}
+
+impl JwtKeys {
+    pub fn new(active_kid: &str, active_secret: &str) -> Self {
+        let mut secrets = HashMap::new();
+        secrets.insert(active_kid.to_string(), active_secret.as_bytes().to_vec());
+
+        Self {
+            active_kid: active_kid.to_string(),
+            secrets,
+        }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        let mut keys = Self::new(&config.auth.jwt_key_id, &config.jwt_secret);
+        for key in &config.auth.jwt_previous_keys {
+            keys = keys.with_previous_key(&key.kid, &key.secret);
+        }
+        keys
+    }
+
+    /// Ajoute une clé acceptée uniquement en vérification
+    pub fn with_previous_key(mut self, kid: &str, secret: &str) -> Self {
+        // La clé active ne doit jamais être écrasée par une ancienne clé homonyme
+        self.secrets
+            .entry(kid.to_string())
+            .or_insert_with(|| secret.as_bytes().to_vec());
+        self
+    }
+
+    /// Signe des claims avec la clé active
+    pub fn sign<T: Serialize>(&self, claims: &T) -> AppResult<String> {
+        let mut header = Header::new(Algorithm::HS256);
+        header.kid = Some(self.active_kid.clone());
+
+        encode(&header, claims, &EncodingKey::from_secret(&self.secrets[&self.active_kid]))
+            .map_err(|e| AppError::Internal(format!("Failed to sign token: {}", e)))
+    }
+
+    /// Vérifie la signature et l'expiration d'un token
+    ///
+    /// Un token sans `kid` est vérifié avec la clé active.
+    pub fn verify<T: DeserializeOwned>(&self, token: &str) -> AppResult<T> {
+        self.decode(token, &Validation::new(Algorithm::HS256))
+    }
+
+    /// Vérifie la signature d'un token et son expiration à l'instant `now`
+    ///
+    /// Contrairement à `verify`, l'expiration ne dépend pas de l'horloge système
+    /// et n'a pas de marge de tolérance.
+    pub fn verify_at<T: DeserializeOwned>(&self, token: &str, now: DateTime<Utc>) -> AppResult<T> {
+        #[derive(Deserialize)]
+        struct Expiry {
+            exp: i64,
+        }
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.validate_exp = false;
+
+        let expiry: Expiry = self.decode(token, &validation)?;
+        if expiry.exp <= now.timestamp() {
+            return Err(AppError::Unauthorized("Invalid or expired token".to_string()));
+        }
+
+        self.decode(token, &validation)
+    }
+
+    fn decode<T: DeserializeOwned>(&self, token: &str, validation: &Validation) -> AppResult<T> {
+        let invalid_token = || AppError::Unauthorized("Invalid or expired token".to_string());
+
+        let header = decode_header(token).map_err(|_| invalid_token())?;
+        let kid = header.kid.as_deref().unwrap_or(&self.active_kid);
+        let secret = self.secrets.get(kid).ok_or_else(invalid_token)?;
+
+        decode::<T>(token, &DecodingKey::from_secret(secret), validation)
+            .map(|data| data.claims)
+            .map_err(|_| invalid_token())
+    }
+}
+
+/// Émet le token d'accès d'une session
+pub fn issue_access_token(
+    config: &Config,
+    user: &User,
+    session_id: Uuid,
+    expires_at: DateTime<Utc>,
+) -> AppResult<String> {
+    let claims = AccessClaims {
+        sub: user.id,
+        user_type: user.user_type(),
+        role: user.role(),
+        sid: session_id,
+        iat: Utc::now().timestamp(),
+        exp: expires_at.timestamp(),
+    };
+
+    JwtKeys::from_config(config).sign(&claims)
+}
+
+/// Valide un token d'accès et retourne ses claims
+pub fn validate_access_token(config: &Config, token: &str) -> AppResult<AccessClaims> {
+    JwtKeys::from_config(config).verify(token)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn claims(expires_in: Duration) -> AccessClaims {
+        let now = Utc::now();
+        AccessClaims {
+            sub: Uuid::new_v4(),
+            user_type: UserType::Registered,
+            role: UserRole::User,
+            sid: Uuid::new_v4(),
+            iat: now.timestamp(),
+            exp: (now + expires_in).timestamp(),
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let keys = JwtKeys::new("k1", "secret-1");
+        let original = claims(Duration::hours(1));
+        let token = keys.sign(&original).unwrap();
+
+        let decoded: AccessClaims = keys.verify(&token).unwrap();
+        assert_eq!(decoded.sub, original.sub);
+        assert_eq!(decoded.sid, original.sid);
+        assert_eq!(decoded.user_type, UserType::Registered);
+    }
+
+    #[test]
+    fn test_verify_at_uses_given_clock() {
+        let keys = JwtKeys::new("k1", "secret-1");
+        let original = claims(Duration::hours(1));
+        let token = keys.sign(&original).unwrap();
+        let expires_at = DateTime::from_timestamp(original.exp, 0).unwrap();
+
+        let decoded: AccessClaims = keys.verify_at(&token, expires_at - Duration::seconds(1)).unwrap();
+        assert_eq!(decoded.sub, original.sub);
+        assert!(keys.verify_at::<AccessClaims>(&token, expires_at).is_err());
+        assert!(JwtKeys::new("k1", "other").verify_at::<AccessClaims>(&token, Utc::now()).is_err());
+    }
+
+    #[test]
+    fn test_expired_token_is_rejected() {
+        let keys = JwtKeys::new("k1", "secret-1");
+        let token = keys.sign(&claims(Duration::hours(-2))).unwrap();
+
+        assert!(keys.verify::<AccessClaims>(&token).is_err());
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old_keys = JwtKeys::new("k1", "secret-1");
+        let token = old_keys.sign(&claims(Duration::hours(1))).unwrap();
+
+        let rotated = JwtKeys::new("k2", "secret-2").with_previous_key("k1", "secret-1");
+        assert!(rotated.verify::<AccessClaims>(&token).is_ok());
+
+        let retired = JwtKeys::new("k2", "secret-2");
+        assert!(retired.verify::<AccessClaims>(&token).is_err());
+    }
+
+    #[test]
+    fn test_previous_key_cannot_override_active_key() {
+        let keys = JwtKeys::new("k1", "secret-1").with_previous_key("k1", "forged");
+        let forged = JwtKeys::new("k1", "forged").sign(&claims(Duration::hours(1))).unwrap();
+
+        assert!(keys.verify::<AccessClaims>(&forged).is_err());
+    }
+}
@@ -2,3 +2,9 @@ pub mod jwt;
 pub mod password;
 pub mod validation;
 pub mod error;
+pub mod request;
+pub mod token;
+pub mod crypto;
+pub mod totp;
+pub mod store;
+pub mod storage;
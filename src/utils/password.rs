@@ -1 +1,169 @@
-// Password utilities - TODO: Implement
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use validator::ValidationError;
+
+use crate::config::Config;
+use crate::utils::error::{AppError, AppResult};
+use crate::utils::token::generate_token;
+
+/// Longueur minimale d'un mot de passe, en caractères
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Version de l'algorithme bcrypt produite par `bcrypt::hash`
+const CURRENT_BCRYPT_VERSION: &str = "2b";
+
+/// Mots de passe trop courants pour être acceptés, un par ligne
+const COMMON_PASSWORDS: &str = include_str!("common_passwords.txt");
+
+/// Hachage et vérification des mots de passe avec un coût bcrypt configurable
+///
+/// bcrypt est coûteux en CPU : les opérations sont exécutées hors du runtime async.
+#[derive(Debug, Clone, Copy)]
+pub struct PasswordHasher {
+    cost: u32,
+}
+
+impl PasswordHasher {
+    pub fn new(cost: u32) -> Self {
+        Self { cost }
+    }
+
+    pub fn from_config(config: &Config) -> Self {
+        Self::new(config.auth.bcrypt_cost)
+    }
+
+    pub async fn hash(&self, password: String) -> AppResult<String> {
+        let cost = self.cost;
+        tokio::task::spawn_blocking(move || hash_password(&password, cost))
+            .await
+            .map_err(|e| AppError::Internal(format!("Password hashing task failed: {}", e)))?
+    }
+
+    pub async fn verify(&self, password: String, hash: String) -> AppResult<bool> {
+        tokio::task::spawn_blocking(move || verify_password(&password, &hash))
+            .await
+            .map_err(|e| AppError::Internal(format!("Password verification task failed: {}", e)))
+    }
+
+    /// Vérifie le mot de passe contre un hash factice, au coût configuré
+    ///
+    /// Appelée quand il n'y a pas de hash à vérifier (compte inconnu ou sans mot de
+    /// passe) : la réponse prend le même temps que pour un compte existant.
+    pub async fn verify_dummy(&self, password: String) -> AppResult<()> {
+        let cost = self.cost;
+        tokio::task::spawn_blocking(move || {
+            verify_password(&password, &dummy_hash(cost)?);
+            Ok(())
+        })
+        .await
+        .map_err(|e| AppError::Internal(format!("Password verification task failed: {}", e)))?
+    }
+
+    /// Vrai si le hash a été produit avec un autre coût ou une ancienne version de bcrypt
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        match parse_bcrypt_hash(hash) {
+            Some((version, cost)) => version != CURRENT_BCRYPT_VERSION || cost != self.cost,
+            None => true,
+        }
+    }
+}
+
+/// Hash un mot de passe avec bcrypt
+pub fn hash_password(password: &str, cost: u32) -> AppResult<String> {
+    bcrypt::hash(password, cost)
+        .map_err(|e| AppError::Internal(format!("Password hashing failed: {}", e)))
+}
+
+/// Hash factice d'un secret aléatoire, calculé une fois par coût
+fn dummy_hash(cost: u32) -> AppResult<String> {
+    static DUMMY_HASHES: OnceLock<Mutex<HashMap<u32, String>>> = OnceLock::new();
+
+    let mut hashes = DUMMY_HASHES
+        .get_or_init(Default::default)
+        .lock()
+        .map_err(|_| AppError::Internal("Dummy password hash lock poisoned".to_string()))?;
+    if let Some(hash) = hashes.get(&cost) {
+        return Ok(hash.clone());
+    }
+
+    let hash = hash_password(&generate_token(), cost)?;
+    hashes.insert(cost, hash.clone());
+    Ok(hash)
+}
+
+/// Vérifie un mot de passe contre un hash bcrypt
+///
+/// Un hash illisible est traité comme un échec de vérification.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    bcrypt::verify(password, hash).unwrap_or(false)
+}
+
+/// Extrait la version et le coût d'un hash au format `$2b$12$...`
+fn parse_bcrypt_hash(hash: &str) -> Option<(&str, u32)> {
+    let mut parts = hash.split('$');
+    if !parts.next()?.is_empty() {
+        return None;
+    }
+    let version = parts.next()?;
+    let cost = parts.next()?.parse().ok()?;
+    parts.next()?;
+
+    Some((version, cost))
+}
+
+/// Politique de robustesse : longueur minimale et refus des mots de passe courants
+pub fn validate_password_strength(password: &str) -> Result<(), ValidationError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(ValidationError::new("password_too_short"));
+    }
+
+    let normalized = password.to_lowercase();
+    if COMMON_PASSWORDS.lines().any(|common| common == normalized) {
+        return Err(ValidationError::new("password_too_common"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify() {
+        let hash = hash_password("correct horse", 4).unwrap();
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("wrong horse", &hash));
+        assert!(!verify_password("correct horse", "not-a-hash"));
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let hash = hash_password("correct horse", 4).unwrap();
+        assert!(!PasswordHasher::new(4).needs_rehash(&hash));
+        assert!(PasswordHasher::new(5).needs_rehash(&hash));
+
+        let legacy = hash.replacen("$2b$", "$2a$", 1);
+        assert!(verify_password("correct horse", &legacy));
+        assert!(PasswordHasher::new(4).needs_rehash(&legacy));
+
+        assert!(PasswordHasher::new(4).needs_rehash("not-a-hash"));
+    }
+
+    #[test]
+    fn test_dummy_hash_uses_configured_cost() {
+        let hash = dummy_hash(4).unwrap();
+        assert_eq!(parse_bcrypt_hash(&hash), Some((CURRENT_BCRYPT_VERSION, 4)));
+        assert_eq!(dummy_hash(4).unwrap(), hash);
+        assert_eq!(parse_bcrypt_hash(&dummy_hash(5).unwrap()), Some((CURRENT_BCRYPT_VERSION, 5)));
+        assert!(!verify_password("correct horse", &hash));
+    }
+
+    #[test]
+    fn test_password_strength() {
+        assert!(validate_password_strength("short").is_err());
+        assert!(validate_password_strength("Password123").is_err());
+        assert!(validate_password_strength("correct horse battery").is_ok());
+    }
+}
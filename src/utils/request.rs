@@ -0,0 +1,119 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts},
+};
+use ipnet::IpNet;
+
+use crate::AppState;
+
+/// Informations sur le client à l'origine de la requête
+#[derive(Debug, Clone, Default)]
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for ClientInfo {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &Arc<AppState>) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip());
+        let forwarded = parts
+            .headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok());
+
+        let ip_address = client_ip(peer, forwarded, &state.config.server.trusted_proxies).map(|ip| ip.to_string());
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
+        Ok(ClientInfo {
+            ip_address,
+            user_agent,
+        })
+    }
+}
+
+/// Adresse du client : celle de la connexion, sauf si elle provient d'un proxy de confiance
+///
+/// `X-Forwarded-For` est alors lu de droite à gauche et la première adresse hors
+/// des proxies de confiance est retenue ; les entrées plus à gauche, fournies par
+/// le client lui-même, sont ignorées.
+fn client_ip(peer: Option<IpAddr>, forwarded: Option<&str>, trusted_proxies: &[IpNet]) -> Option<IpAddr> {
+    let is_trusted = |ip: &IpAddr| trusted_proxies.iter().any(|proxy| proxy.contains(ip));
+
+    let mut client = peer?;
+    if !is_trusted(&client) {
+        return Some(client);
+    }
+
+    for hop in forwarded.unwrap_or_default().rsplit(',') {
+        match hop.trim().parse::<IpAddr>() {
+            Ok(ip) => {
+                client = ip;
+                if !is_trusted(&ip) {
+                    break;
+                }
+            }
+            // Entrée illisible : rien de ce qui précède n'est fiable
+            Err(_) => break,
+        }
+    }
+
+    Some(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(value: &str) -> IpAddr {
+        value.parse().unwrap()
+    }
+
+    fn proxies() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap(), IpNet::from(ip("192.168.1.1"))]
+    }
+
+    #[test]
+    fn test_forwarded_for_ignored_from_untrusted_peer() {
+        let client = client_ip(Some(ip("203.0.113.7")), Some("1.2.3.4"), &proxies());
+        assert_eq!(client, Some(ip("203.0.113.7")));
+
+        let client = client_ip(Some(ip("10.0.0.2")), Some("1.2.3.4"), &[]);
+        assert_eq!(client, Some(ip("10.0.0.2")));
+    }
+
+    #[test]
+    fn test_forwarded_for_takes_rightmost_untrusted_hop() {
+        // Le client a ajouté 1.2.3.4 lui-même ; le proxy a ajouté son adresse réelle
+        let client = client_ip(
+            Some(ip("10.0.0.2")),
+            Some("1.2.3.4, 203.0.113.7, 192.168.1.1"),
+            &proxies(),
+        );
+        assert_eq!(client, Some(ip("203.0.113.7")));
+    }
+
+    #[test]
+    fn test_forwarded_for_stops_at_invalid_entry() {
+        let client = client_ip(Some(ip("10.0.0.2")), Some("1.2.3.4, garbage, 10.0.0.9"), &proxies());
+        assert_eq!(client, Some(ip("10.0.0.9")));
+
+        let client = client_ip(Some(ip("10.0.0.2")), None, &proxies());
+        assert_eq!(client, Some(ip("10.0.0.2")));
+    }
+}
@@ -0,0 +1,146 @@
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::config::StorageConfig;
+use crate::utils::error::{AppError, AppResult};
+
+/// Stockage de fichiers adressés par une clé relative (`avatars/<id>/<nom>`)
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Enregistre un fichier, en remplaçant celui de même clé
+    async fn put(&self, key: &str, data: Vec<u8>) -> AppResult<()>;
+
+    /// Contenu d'un fichier, `NotFound` s'il n'existe pas
+    async fn get(&self, key: &str) -> AppResult<Vec<u8>>;
+
+    /// Supprime un fichier ; une clé absente n'est pas une erreur
+    async fn delete(&self, key: &str) -> AppResult<()>;
+
+    /// URL publique d'un fichier
+    fn url(&self, key: &str) -> String;
+
+    /// Clé d'un fichier à partir de son URL publique, `None` s'il n'est pas stocké ici
+    fn key_from_url(&self, url: &str) -> Option<String>;
+}
+
+/// Stockage sur le disque local, servi par le serveur HTTP
+pub struct LocalStorage {
+    root: PathBuf,
+    public_url: String,
+}
+
+impl LocalStorage {
+    pub fn new(config: &StorageConfig) -> Self {
+        Self {
+            root: PathBuf::from(&config.local_path),
+            public_url: config.public_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Chemin d'un fichier, en refusant toute clé qui sortirait du répertoire
+    fn path(&self, key: &str) -> AppResult<PathBuf> {
+        let relative = Path::new(key);
+        let valid = !key.is_empty()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !valid {
+            return Err(AppError::Internal(format!("Invalid storage key: {}", key)));
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+fn io_error(e: std::io::Error) -> AppError {
+    AppError::Internal(format!("Storage error: {}", e))
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn put(&self, key: &str, data: Vec<u8>) -> AppResult<()> {
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(io_error)?;
+        }
+
+        // Écriture dans un fichier temporaire puis renommage : jamais de fichier partiel servi
+        let temporary = path.with_extension(format!("{}.tmp", Uuid::new_v4().simple()));
+        tokio::fs::write(&temporary, data).await.map_err(io_error)?;
+        if let Err(e) = tokio::fs::rename(&temporary, &path).await {
+            let _ = tokio::fs::remove_file(&temporary).await;
+            return Err(io_error(e));
+        }
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> AppResult<Vec<u8>> {
+        tokio::fs::read(self.path(key)?).await.map_err(|e| match e.kind() {
+            ErrorKind::NotFound => AppError::NotFound("File not found".to_string()),
+            _ => io_error(e),
+        })
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<()> {
+        match tokio::fs::remove_file(self.path(key)?).await {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(io_error(e)),
+            _ => Ok(()),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}", self.public_url, key)
+    }
+
+    fn key_from_url(&self, url: &str) -> Option<String> {
+        url.strip_prefix(&self.public_url)?
+            .strip_prefix('/')
+            .filter(|key| self.path(key).is_ok())
+            .map(str::to_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storage(root: &Path) -> LocalStorage {
+        LocalStorage::new(&StorageConfig {
+            local_path: root.to_string_lossy().into_owned(),
+            public_url: "http://localhost:8080/uploads/".to_string(),
+            avatar_max_size: 1024,
+            export_ttl: 3600,
+        })
+    }
+
+    #[test]
+    fn test_keys_and_urls() {
+        let storage = storage(Path::new("/tmp/ettu"));
+
+        let url = storage.url("avatars/a/b.png");
+        assert_eq!(url, "http://localhost:8080/uploads/avatars/a/b.png");
+        assert_eq!(storage.key_from_url(&url).as_deref(), Some("avatars/a/b.png"));
+        assert_eq!(storage.key_from_url("https://cdn.example.com/avatars/a/b.png"), None);
+        assert_eq!(storage.key_from_url("http://localhost:8080/uploads/../etc/passwd"), None);
+        assert!(storage.path("/etc/passwd").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_put_and_delete() {
+        let root = std::env::temp_dir().join(format!("ettu-storage-{}", Uuid::new_v4()));
+        let storage = storage(&root);
+
+        storage.put("avatars/a/b.png", b"data".to_vec()).await.unwrap();
+        assert_eq!(storage.get("avatars/a/b.png").await.unwrap(), b"data");
+
+        storage.delete("avatars/a/b.png").await.unwrap();
+        storage.delete("avatars/a/b.png").await.unwrap();
+        assert!(matches!(storage.get("avatars/a/b.png").await, Err(AppError::NotFound(_))));
+
+        tokio::fs::remove_dir_all(&root).await.unwrap();
+    }
+}
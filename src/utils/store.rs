@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use tracing::{info, warn};
+
+use crate::utils::error::{AppError, AppResult};
+
+/// Délai maximal accordé à Redis pour répondre au démarrage
+const REDIS_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Compteurs à durée de vie limitée, partagés entre les instances via Redis
+#[async_trait]
+pub trait CounterStore: Send + Sync {
+    /// Incrémente un compteur ; la durée de vie n'est fixée qu'à sa création
+    ///
+    /// Incrément et durée de vie sont posés en une seule opération : un compteur
+    /// ne peut pas rester sans expiration.
+    async fn increment(&self, key: &str, ttl: Duration) -> AppResult<u64>;
+
+    /// Fixe la valeur d'un compteur et remplace sa durée de vie
+    async fn set(&self, key: &str, value: u64, ttl: Duration) -> AppResult<()>;
+
+    /// Durée de vie restante d'une clé, `None` si elle n'existe pas
+    async fn ttl(&self, key: &str) -> AppResult<Option<Duration>>;
+
+    /// Supprime une clé et indique si elle existait
+    async fn delete(&self, key: &str) -> AppResult<bool>;
+}
+
+/// INCR et EXPIRE atomiques ; une clé restée sans expiration en reçoit une
+const INCREMENT_SCRIPT: &str = r#"
+local count = redis.call('INCR', KEYS[1])
+if redis.call('TTL', KEYS[1]) < 0 then
+    redis.call('EXPIRE', KEYS[1], ARGV[1])
+end
+return count
+"#;
+
+/// Ouvre le store Redis, ou un store en mémoire si Redis est injoignable
+///
+/// Le store en mémoire n'est pas partagé entre instances : acceptable en
+/// développement, mais chaque instance compte alors ses propres échecs.
+pub async fn connect(redis_url: &str) -> Arc<dyn CounterStore> {
+    match RedisStore::connect(redis_url).await {
+        Ok(store) => {
+            info!("✅ Redis connected successfully");
+            Arc::new(store)
+        }
+        Err(e) => {
+            warn!("⚠️  Redis connection failed: {}. Using in-memory counters.", e);
+            Arc::new(MemoryStore::default())
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RedisStore {
+    connection: ConnectionManager,
+}
+
+impl RedisStore {
+    pub async fn connect(redis_url: &str) -> AppResult<Self> {
+        let client = redis::Client::open(redis_url).map_err(redis_error)?;
+        let connection = tokio::time::timeout(REDIS_CONNECT_TIMEOUT, ConnectionManager::new(client))
+            .await
+            .map_err(|_| AppError::ServiceUnavailable("Redis connection timed out".to_string()))?
+            .map_err(redis_error)?;
+
+        Ok(Self { connection })
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> AppError {
+    AppError::Internal(format!("Redis error: {}", e))
+}
+
+#[async_trait]
+impl CounterStore for RedisStore {
+    async fn increment(&self, key: &str, ttl: Duration) -> AppResult<u64> {
+        redis::Script::new(INCREMENT_SCRIPT)
+            .key(key)
+            .arg(ttl.as_secs().max(1))
+            .invoke_async(&mut self.connection.clone())
+            .await
+            .map_err(redis_error)
+    }
+
+    async fn set(&self, key: &str, value: u64, ttl: Duration) -> AppResult<()> {
+        self.connection
+            .clone()
+            .set_ex(key, value, ttl.as_secs())
+            .await
+            .map_err(redis_error)
+    }
+
+    async fn ttl(&self, key: &str) -> AppResult<Option<Duration>> {
+        // -2 : clé absente, -1 : clé sans expiration
+        let ttl: i64 = self.connection.clone().ttl(key).await.map_err(redis_error)?;
+        Ok(match ttl {
+            -2 => None,
+            ttl => Some(Duration::from_secs(ttl.max(0) as u64)),
+        })
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<bool> {
+        let deleted: u64 = self.connection.clone().del(key).await.map_err(redis_error)?;
+        Ok(deleted > 0)
+    }
+}
+
+/// Store local au processus, utilisé quand Redis n'est pas disponible
+#[derive(Default, Clone)]
+pub struct MemoryStore {
+    entries: Arc<Mutex<HashMap<String, (u64, Instant)>>>,
+}
+
+impl MemoryStore {
+    fn entries(&self) -> std::sync::MutexGuard<'_, HashMap<String, (u64, Instant)>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        entries.retain(|_, (_, expires_at)| *expires_at > now);
+        entries
+    }
+}
+
+#[async_trait]
+impl CounterStore for MemoryStore {
+    async fn increment(&self, key: &str, ttl: Duration) -> AppResult<u64> {
+        let mut entries = self.entries();
+        let entry = entries
+            .entry(key.to_string())
+            .or_insert_with(|| (0, Instant::now() + ttl));
+        entry.0 += 1;
+
+        Ok(entry.0)
+    }
+
+    async fn set(&self, key: &str, value: u64, ttl: Duration) -> AppResult<()> {
+        self.entries()
+            .insert(key.to_string(), (value, Instant::now() + ttl));
+        Ok(())
+    }
+
+    async fn ttl(&self, key: &str) -> AppResult<Option<Duration>> {
+        Ok(self
+            .entries()
+            .get(key)
+            .map(|(_, expires_at)| expires_at.saturating_duration_since(Instant::now())))
+    }
+
+    async fn delete(&self, key: &str) -> AppResult<bool> {
+        Ok(self.entries().remove(key).is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_store_counters() {
+        let store = MemoryStore::default();
+        let ttl = Duration::from_secs(60);
+
+        assert_eq!(store.increment("a", ttl).await.unwrap(), 1);
+        assert_eq!(store.increment("a", ttl).await.unwrap(), 2);
+        assert!(store.ttl("a").await.unwrap().is_some());
+
+        assert!(store.delete("a").await.unwrap());
+        assert!(!store.delete("a").await.unwrap());
+        assert_eq!(store.ttl("a").await.unwrap(), None);
+        assert_eq!(store.increment("a", ttl).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_expiry() {
+        let store = MemoryStore::default();
+
+        store.set("lock", 1, Duration::ZERO).await.unwrap();
+        assert_eq!(store.ttl("lock").await.unwrap(), None);
+    }
+}
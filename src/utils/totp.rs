@@ -0,0 +1,124 @@
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+/// Durée d'une fenêtre TOTP, en secondes (RFC 6238)
+pub const STEP_SECS: u64 = 30;
+
+/// Nombre de chiffres d'un code
+pub const DIGITS: u32 = 6;
+
+/// Fenêtres acceptées de part et d'autre de l'instant courant, pour absorber la dérive d'horloge
+pub const ALLOWED_SKEW: u64 = 1;
+
+/// Génère un secret de 160 bits encodé en base32, le format attendu par les applications
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 20];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    BASE32_NOPAD.encode(&bytes)
+}
+
+/// Calcule le code HOTP (RFC 4226) d'un secret brut pour un compteur donné
+pub fn code_at(secret: &[u8], counter: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+    mac.update(&counter.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+
+    format!("{:0width$}", binary % 10u32.pow(DIGITS), width = DIGITS as usize)
+}
+
+/// Vérifie un code pour l'instant `unix_time` et retourne la fenêtre correspondante
+///
+/// Ne retourne jamais une fenêtre inférieure ou égale à `last_used_step`, pour
+/// qu'un code déjà utilisé ne puisse pas être rejoué.
+pub fn verify(secret_base32: &str, code: &str, unix_time: u64, last_used_step: Option<u64>) -> Option<u64> {
+    let secret = BASE32_NOPAD.decode(secret_base32.as_bytes()).ok()?;
+    let code = code.trim().replace(' ', "");
+    let current = unix_time / STEP_SECS;
+
+    (current.saturating_sub(ALLOWED_SKEW)..=current + ALLOWED_SKEW)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| constant_time_eq(code_at(&secret, *step).as_bytes(), code.as_bytes()))
+}
+
+/// URI `otpauth://` à encoder dans un QR code pour l'application d'authentification
+pub fn provisioning_uri(secret_base32: &str, account: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = percent_encode(issuer),
+        account = percent_encode(account),
+        secret = secret_base32,
+        digits = DIGITS,
+        period = STEP_SECS,
+    )
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Secret des vecteurs de test SHA-1 de la RFC 6238
+    const RFC_SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_rfc6238_vectors() {
+        // Les vecteurs de la RFC ont 8 chiffres : on compare les 6 derniers
+        assert_eq!(code_at(RFC_SECRET, 59 / STEP_SECS), "287082");
+        assert_eq!(code_at(RFC_SECRET, 1111111109 / STEP_SECS), "081804");
+        assert_eq!(code_at(RFC_SECRET, 1234567890 / STEP_SECS), "005924");
+        assert_eq!(code_at(RFC_SECRET, 2000000000 / STEP_SECS), "279037");
+    }
+
+    #[test]
+    fn test_verify_with_fixed_clock() {
+        let secret = BASE32_NOPAD.encode(RFC_SECRET);
+        let now = 1111111109;
+
+        assert_eq!(verify(&secret, "081804", now, None), Some(now / STEP_SECS));
+        // Code de la fenêtre précédente toléré, mais pas au-delà
+        assert!(verify(&secret, "081804", now + STEP_SECS, None).is_some());
+        assert!(verify(&secret, "081804", now + 3 * STEP_SECS, None).is_none());
+        assert!(verify(&secret, "000000", now, None).is_none());
+    }
+
+    #[test]
+    fn test_verify_rejects_replay() {
+        let secret = BASE32_NOPAD.encode(RFC_SECRET);
+        let now = 1111111109;
+        let step = verify(&secret, "081804", now, None).unwrap();
+
+        assert!(verify(&secret, "081804", now, Some(step)).is_none());
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = provisioning_uri("JBSWY3DPEHPK3PXP", "bob@example.com", "ETTU");
+        assert_eq!(
+            uri,
+            "otpauth://totp/ETTU:bob%40example.com?secret=JBSWY3DPEHPK3PXP&issuer=ETTU&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
@@ -1 +1,144 @@
-// Validation utilities - TODO: Implement
+use validator::ValidationError;
+
+/// Un nom d'utilisateur contient uniquement des lettres ASCII, chiffres, `_` et `-`
+pub fn validate_username(username: &str) -> Result<(), ValidationError> {
+    let valid = username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_username"))
+    }
+}
+
+/// Valeurs acceptées par la contrainte CHECK de `users.theme`
+pub const THEMES: &[&str] = &["light", "dark", "auto"];
+
+/// Valeurs acceptées par la contrainte CHECK de `users.language`
+pub const LANGUAGES: &[&str] = &["fr", "en", "es"];
+
+pub fn validate_theme(theme: &str) -> Result<(), ValidationError> {
+    if THEMES.contains(&theme) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_theme"))
+    }
+}
+
+pub fn validate_language(language: &str) -> Result<(), ValidationError> {
+    if LANGUAGES.contains(&language) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_language"))
+    }
+}
+
+/// Un fuseau au format IANA (`Europe/Paris`, `America/Argentina/Buenos_Aires`) ou `UTC`
+pub fn validate_timezone(timezone: &str) -> Result<(), ValidationError> {
+    let mut segments = timezone.split('/');
+    let area_is_valid = segments
+        .next()
+        .is_some_and(|area| !area.is_empty() && area.chars().all(|c| c.is_ascii_alphabetic()));
+    let locations_are_valid = segments.all(|segment| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '+'))
+    });
+
+    if timezone.len() <= 50 && area_is_valid && locations_are_valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_timezone"))
+    }
+}
+
+/// URL http(s) ; une chaîne vide est acceptée pour effacer le champ
+pub fn validate_optional_url(value: &str) -> Result<(), ValidationError> {
+    if value.is_empty() {
+        return Ok(());
+    }
+
+    match reqwest::Url::parse(value) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host().is_some() => Ok(()),
+        _ => Err(ValidationError::new("invalid_url")),
+    }
+}
+
+/// Couleur hexadécimale `#rrggbb`, format de `projects.color`
+pub fn validate_hex_color(color: &str) -> Result<(), ValidationError> {
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_color"))
+    }
+}
+
+/// Au plus 20 technologies de 1 à 50 caractères
+pub fn validate_technologies(technologies: &[String]) -> Result<(), ValidationError> {
+    let valid = technologies.len() <= 20
+        && technologies
+            .iter()
+            .all(|technology| !technology.trim().is_empty() && technology.len() <= 50);
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("invalid_technologies"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_preferences() {
+        assert!(validate_theme("auto").is_ok());
+        assert!(validate_theme("blue").is_err());
+        assert!(validate_language("es").is_ok());
+        assert!(validate_language("de").is_err());
+    }
+
+    #[test]
+    fn test_validate_timezone() {
+        assert!(validate_timezone("UTC").is_ok());
+        assert!(validate_timezone("Europe/Paris").is_ok());
+        assert!(validate_timezone("America/Argentina/Buenos_Aires").is_ok());
+        assert!(validate_timezone("Etc/GMT+2").is_ok());
+        assert!(validate_timezone("Europe/").is_err());
+        assert!(validate_timezone("Paris, France").is_err());
+    }
+
+    #[test]
+    fn test_validate_optional_url() {
+        assert!(validate_optional_url("").is_ok());
+        assert!(validate_optional_url("https://ettu.dev/about").is_ok());
+        assert!(validate_optional_url("javascript:alert(1)").is_err());
+        assert!(validate_optional_url("ettu.dev").is_err());
+    }
+
+    #[test]
+    fn test_validate_project_fields() {
+        assert!(validate_hex_color("#3b82f6").is_ok());
+        assert!(validate_hex_color("3b82f6").is_err());
+        assert!(validate_hex_color("#3b82fg").is_err());
+        assert!(validate_technologies(&["rust".to_string(), "axum".to_string()]).is_ok());
+        assert!(validate_technologies(&[" ".to_string()]).is_err());
+        assert!(validate_technologies(&vec!["rust".to_string(); 21]).is_err());
+    }
+
+    #[test]
+    fn test_validate_username() {
+        assert!(validate_username("bob_42").is_ok());
+        assert!(validate_username("jean-luc").is_ok());
+        assert!(validate_username("bob smith").is_err());
+        assert!(validate_username("bob@example").is_err());
+    }
+}